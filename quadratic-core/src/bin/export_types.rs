@@ -5,8 +5,8 @@ use controller::operations::clipboard::PasteSpecial;
 use formulas::{CellRef, CellRefCoord, RangeRef};
 use grid::formats::format::Format;
 use grid::js_types::{
-    CellFormatSummary, JsCellValue, JsClipboard, JsOffset, JsPos, JsRenderFill, JsRowHeight,
-    JsSheetFill, JsValidationWarning,
+    CellFormatSummary, FormatState, FormatSummary, JsCellValue, JsClipboard, JsOffset, JsPos,
+    JsRenderFill, JsRowHeight, JsSheetFill, JsValidationWarning,
 };
 use grid::sheet::borders::{BorderStyleCell, BorderStyleTimestamp};
 use grid::sheet::validations::validation::{
@@ -31,6 +31,7 @@ use grid::{
     CellAlign, CellVerticalAlign, CellWrap, GridBounds, NumericFormat, NumericFormatKind, SheetId,
 };
 use quadratic_core::color::Rgba;
+use quadratic_core::controller::active_transactions::pending_transaction::TransactionWarning;
 use quadratic_core::controller::active_transactions::transaction_name::TransactionName;
 use quadratic_core::controller::execution::run_code::get_cells::JsGetCellResponse;
 use quadratic_core::controller::transaction_types::JsCodeResult;
@@ -85,6 +86,8 @@ fn main() {
         DateTimeRange,
         Duration,
         Format,
+        FormatState,
+        FormatSummary,
         GridBounds,
         Instant,
         JsBorderHorizontal,
@@ -132,6 +135,7 @@ fn main() {
         TextCase,
         TextMatch,
         TransactionName,
+        TransactionWarning,
         TransientResize,
         Validation,
         ValidationDateTime,
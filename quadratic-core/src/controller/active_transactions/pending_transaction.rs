@@ -6,15 +6,18 @@
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
+use serde::Serialize;
 use uuid::Uuid;
 
 use crate::{
     controller::{
         execution::TransactionType, operations::operation::Operation, transaction::Transaction,
     },
+    error_core::CoreError,
     grid::{sheet::validations::validation::Validation, CodeCellLanguage, CodeRun, Sheet, SheetId},
+    renderer_constants::{CELL_SHEET_HEIGHT, CELL_SHEET_WIDTH},
     selection::Selection,
-    Pos, SheetPos, SheetRect,
+    Pos, Rect, SheetPos, SheetRect,
 };
 
 use super::transaction_name::TransactionName;
@@ -22,6 +25,35 @@ use super::transaction_name::TransactionName;
 // offsets modified ((column, row) -> new_size)
 type SheetOffsets = HashMap<(Option<i64>, Option<i64>), f64>;
 
+/// Counts of the work done by column and row operations, for profiling.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TransactionMetrics {
+    // cell values moved to a new position
+    pub cells_shifted: usize,
+
+    // format fields (per column) that were shifted
+    pub formats_shifted: usize,
+
+    // code runs moved to a new position
+    pub code_runs_reindexed: usize,
+
+    // row or column shifts that changed borders
+    pub borders_shifted: usize,
+}
+
+/// A warning collected during a transaction so the client can prompt the
+/// user. Warnings are sent to the client when the transaction finalizes.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "js", derive(ts_rs::TS))]
+pub enum TransactionWarning {
+    /// Code cells (in any sheet) that referenced a deleted row.
+    DeletedRowDependents {
+        sheet_id: SheetId,
+        row: i64,
+        dependents: HashSet<SheetPos>,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PendingTransaction {
     pub id: Uuid,
@@ -92,6 +124,19 @@ pub struct PendingTransaction {
 
     // offsets modified (sheet_id -> SheetOffsets)
     pub offsets_modified: HashMap<SheetId, SheetOffsets>,
+
+    // warnings for the client (eg, formulas that referenced a deleted row)
+    pub warnings: Vec<TransactionWarning>,
+
+    // operations that could not be applied (eg, on a missing sheet)
+    pub errors: Vec<CoreError>,
+
+    // work done by column and row operations
+    pub(crate) metrics: TransactionMetrics,
+
+    // durations of the hot shift loops of column and row operations
+    #[cfg(feature = "bench")]
+    pub(crate) timings: Vec<(&'static str, std::time::Duration)>,
 }
 
 impl Default for PendingTransaction {
@@ -121,6 +166,11 @@ impl Default for PendingTransaction {
             fill_cells: HashSet::new(),
             sheet_info: HashSet::new(),
             offsets_modified: HashMap::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            metrics: TransactionMetrics::default(),
+            #[cfg(feature = "bench")]
+            timings: Vec::new(),
         }
     }
 }
@@ -329,6 +379,54 @@ impl PendingTransaction {
         }
     }
 
+    /// Returns the bounding rect of everything the transaction has touched in
+    /// the sheet (dirty hashes and code, html, and image cells). This is meant
+    /// for debugging, and is approximate since a dirty hash covers a whole
+    /// render hash of cells.
+    pub fn impacted_region(&self, sheet_id: SheetId) -> Option<Rect> {
+        let mut region: Option<Rect> = None;
+        let mut add = |rect: Rect| {
+            region = Some(region.map_or(rect, |region| region.union(&rect)));
+        };
+
+        if let Some(hashes) = self.dirty_hashes.get(&sheet_id) {
+            let width = CELL_SHEET_WIDTH as i64;
+            let height = CELL_SHEET_HEIGHT as i64;
+            for hash in hashes {
+                add(Rect::from_numbers(
+                    hash.x * width,
+                    hash.y * height,
+                    width,
+                    height,
+                ));
+            }
+        }
+        for cells in [&self.code_cells, &self.html_cells, &self.image_cells] {
+            if let Some(positions) = cells.get(&sheet_id) {
+                positions.iter().for_each(|pos| add(Rect::single_pos(*pos)));
+            }
+        }
+
+        region
+    }
+
+    /// Returns the work done by column and row operations in this transaction.
+    pub fn metrics(&self) -> TransactionMetrics {
+        self.metrics
+    }
+
+    /// Records how long a hot loop took, measured from `start`.
+    #[cfg(feature = "bench")]
+    pub fn record_timing(&mut self, name: &'static str, start: std::time::Instant) {
+        self.timings.push((name, start.elapsed()));
+    }
+
+    /// Returns the durations recorded by record_timing, in order.
+    #[cfg(feature = "bench")]
+    pub fn timings(&self) -> &[(&'static str, std::time::Duration)] {
+        &self.timings
+    }
+
     /// Updates the offsets modified for a column or row.
     pub fn offsets_modified(
         &mut self,
@@ -599,4 +697,27 @@ mod tests {
         assert!(dirty_hashes.contains(&Pos { x: 0, y: 0 }));
         assert_eq!(dirty_hashes.len(), 1);
     }
+
+    #[test]
+    #[parallel]
+    fn impacted_region() {
+        let mut sheet = Sheet::test();
+        for x in 1..=3 {
+            for y in 1..=40 {
+                sheet.set_cell_value(Pos::new(x, y), format!("{x},{y}"));
+            }
+        }
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        assert_eq!(transaction.impacted_region(sheet.id), None);
+
+        sheet.delete_row(&mut transaction, 2);
+
+        let region = transaction.impacted_region(sheet.id).unwrap();
+        assert!(region.contains(Pos::new(1, 2)));
+        assert!(region.contains(Pos::new(3, 2)));
+        assert!(region.contains(Pos::new(3, 40)));
+        assert_eq!(transaction.impacted_region(SheetId::new()), None);
+    }
 }
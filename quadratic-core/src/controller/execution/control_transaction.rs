@@ -122,6 +122,27 @@ impl GridController {
 
         // TODO(ayush): consolidate these calls, when viewport buffer PR is merged
         if (cfg!(target_family = "wasm") || cfg!(test)) && !transaction.is_server() {
+            // operations that could not be applied (eg, on a missing sheet)
+            if !transaction.errors.is_empty() {
+                if let Ok(errors) = serde_json::to_string(&transaction.errors) {
+                    crate::wasm_bindings::js::jsTransactionErrors(
+                        transaction.id.to_string(),
+                        errors,
+                    );
+                }
+            }
+
+            // warnings the client may show the user (eg, code cells that
+            // referenced a deleted row)
+            if !transaction.warnings.is_empty() {
+                if let Ok(warnings) = serde_json::to_string(&transaction.warnings) {
+                    crate::wasm_bindings::js::jsTransactionWarnings(
+                        transaction.id.to_string(),
+                        warnings,
+                    );
+                }
+            }
+
             crate::wasm_bindings::js::jsUndoRedo(
                 !self.undo_stack.is_empty(),
                 !self.redo_stack.is_empty(),
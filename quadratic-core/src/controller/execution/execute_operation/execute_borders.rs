@@ -79,9 +79,18 @@ impl GridController {
                     return;
                 };
 
-                transaction
-                    .reverse_operations
-                    .extend(sheet.borders.set_borders(&selection, &borders));
+                let (reverse_operations, affected) =
+                    sheet.set_borders_selection(&selection, &borders);
+                transaction.reverse_operations.extend(reverse_operations);
+
+                // only redraw the hashes whose borders changed
+                if let Some(rects) = affected {
+                    for rect in rects {
+                        transaction.add_dirty_hashes_from_sheet_rect(
+                            rect.to_sheet_rect(selection.sheet_id),
+                        );
+                    }
+                }
 
                 transaction
                     .forward_operations
@@ -104,7 +113,7 @@ mod tests {
 
     use crate::{
         color::Rgba, controller::active_transactions::unsaved_transactions::UnsavedTransaction,
-        grid::sheet::borders::CellBorderLine,
+        grid::sheet::borders::CellBorderLine, Rect,
     };
 
     use super::*;
@@ -162,4 +171,25 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    #[parallel]
+    fn set_borders_selection_dirty_hashes() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let rect = Rect::new(2, 2, 4, 4);
+        let mut transaction = PendingTransaction::default();
+        gc.execute_set_borders_selection(
+            &mut transaction,
+            Operation::SetBordersSelection {
+                selection: Selection::rect(rect, sheet_id),
+                borders: RunLengthEncoding::repeat(BorderStyleCellUpdate::all(), 9),
+            },
+        );
+        assert_eq!(
+            transaction.dirty_hashes.get(&sheet_id),
+            Some(&rect.to_sheet_rect(sheet_id).to_hashes())
+        );
+        assert!(transaction.sheet_borders.contains(&sheet_id));
+    }
 }
@@ -1,11 +1,18 @@
+use std::ops::RangeInclusive;
+
 use crate::{
     controller::{
-        active_transactions::pending_transaction::PendingTransaction,
-        operations::operation::Operation, GridController,
+        active_transactions::pending_transaction::{PendingTransaction, TransactionWarning},
+        operations::operation::{Operation, ShiftDirection},
+        GridController,
+    },
+    error_core::CoreError,
+    formulas::{
+        replace_cell_references_or_ref_error, replace_cell_references_with, CellRef, CellRefCoord,
+        RangeRef,
     },
-    formulas::{replace_cell_references_with, CellRefCoord},
-    grid::{GridBounds, SheetId},
-    CellValue, CodeCellValue,
+    grid::{CodeCellLanguage, GridBounds, SheetId},
+    CellValue, CodeCellValue, SheetRect,
 };
 
 impl GridController {
@@ -18,13 +25,48 @@ impl GridController {
         row: Option<i64>,
         delta: i64,
     ) {
+        if column.is_none() {
+            if let Some(row) = row {
+                self.adjust_formulas_rows(
+                    transaction,
+                    sheet_id,
+                    &sheet_name,
+                    row..=i64::MAX,
+                    delta,
+                );
+            }
+            return;
+        }
+
+        // Shifts a relative coordinate that is at or beyond `at` by delta.
+        // base is the formula's current position and original_base its
+        // position before the shift, so relative references keep pointing at
+        // the same cell when the formula itself moved. Absolute ($)
+        // references stay fixed.
+        let shift = |coord: CellRefCoord, base: i64, original_base: i64, at: i64| match coord {
+            CellRefCoord::Relative(offset) => {
+                let target = original_base + offset;
+                let target = if target >= at { target + delta } else { target };
+                CellRefCoord::Relative(target - base)
+            }
+            absolute => absolute,
+        };
+
         self.grid.sheets().iter().for_each(|sheet| {
             sheet.code_runs.iter().for_each(|(pos, code_run)| {
                 if let Some(column) = column {
-                    if code_run.cells_accessed.iter().any(|sheet_rect| {
-                        // if the cells accessed is beyond the column that was deleted
-                        sheet_rect.sheet_id == sheet_id && sheet_rect.max.x >= column
-                    }) {
+                    // formulas on the sheet at or beyond the column were moved
+                    let original_x = if sheet.id == sheet_id && pos.x >= column {
+                        pos.x - delta
+                    } else {
+                        pos.x
+                    };
+                    if original_x != pos.x
+                        || code_run.cells_accessed.iter().any(|sheet_rect| {
+                            // if the cells accessed is beyond the column that was deleted
+                            sheet_rect.sheet_id == sheet_id && sheet_rect.max.x >= column
+                        })
+                    {
                         // only update formulas (for now)
                         if let Some(CellValue::Code(code)) = sheet.cell_value_ref(*pos) {
                             let new_code = replace_cell_references_with(
@@ -34,24 +76,14 @@ impl GridController {
                                     let coord_sheet_name =
                                         coord_sheet_name.as_ref().unwrap_or(&sheet.name);
                                     if *coord_sheet_name == sheet_name {
+                                        shift(cell_ref, pos.x, original_x, column)
+                                    } else {
                                         match cell_ref {
-                                            CellRefCoord::Relative(x) => {
-                                                if x + pos.x >= column {
-                                                    CellRefCoord::Relative(x + delta)
-                                                } else {
-                                                    CellRefCoord::Relative(x)
-                                                }
-                                            }
-                                            CellRefCoord::Absolute(x) => {
-                                                if x >= column {
-                                                    CellRefCoord::Absolute(x + delta)
-                                                } else {
-                                                    CellRefCoord::Absolute(x)
-                                                }
+                                            CellRefCoord::Relative(offset) => {
+                                                CellRefCoord::Relative(original_x + offset - pos.x)
                                             }
+                                            absolute => absolute,
                                         }
-                                    } else {
-                                        cell_ref
                                     }
                                 },
                                 |_, cell_ref| cell_ref,
@@ -62,61 +94,231 @@ impl GridController {
                                     ..code.clone()
                                 });
                                 transaction.operations.push_back(Operation::SetCellValues {
-                                    sheet_pos: pos.to_sheet_pos(sheet_id),
+                                    sheet_pos: pos.to_sheet_pos(sheet.id),
                                     values: code_cell_value.into(),
                                 });
                             }
                         }
                     }
-                } else if let Some(row) = row {
-                    if code_run.cells_accessed.iter().any(|sheet_rect| {
-                        // if the cells accessed is beyond the row that was deleted
-                        sheet_rect.sheet_id == sheet_id && sheet_rect.max.y >= row
-                    }) {
-                        // only update formulas (for now)
-                        if let Some(CellValue::Code(code)) = sheet.cell_value_ref(*pos) {
-                            let new_code = replace_cell_references_with(
-                                &code.code,
-                                *pos,
-                                |_, cell_ref| cell_ref,
-                                |coord_sheet_name, cell_ref| {
-                                    let coord_sheet_name =
-                                        coord_sheet_name.as_ref().unwrap_or(&sheet.name);
-                                    if *coord_sheet_name == sheet_name {
-                                        match cell_ref {
-                                            CellRefCoord::Relative(y) => {
-                                                if y + pos.y >= row {
-                                                    CellRefCoord::Relative(y + delta)
-                                                } else {
-                                                    CellRefCoord::Relative(y)
-                                                }
-                                            }
-                                            CellRefCoord::Absolute(y) => {
-                                                if y >= row {
-                                                    CellRefCoord::Absolute(y + delta)
-                                                } else {
-                                                    CellRefCoord::Absolute(y)
-                                                }
-                                            }
-                                        }
-                                    } else {
-                                        cell_ref
-                                    }
-                                },
-                            );
-                            if new_code != code.code {
-                                let code_cell_value = CellValue::Code(CodeCellValue {
-                                    code: new_code,
-                                    ..code.clone()
-                                });
-                                let sheet_pos = pos.to_sheet_pos(sheet_id);
-                                transaction.operations.push_back(Operation::SetCellValues {
-                                    sheet_pos,
-                                    values: code_cell_value.into(),
-                                });
+                }
+            });
+        });
+    }
+
+    /// Adjusts formula references after the rows of sheet_id in `rows` were
+    /// shifted by delta. Relative references to those rows are shifted, and
+    /// formulas that moved with them keep pointing at the same cells.
+    /// Absolute ($) references stay fixed.
+    fn adjust_formulas_rows(
+        &self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        sheet_name: &str,
+        rows: RangeInclusive<i64>,
+        delta: i64,
+    ) {
+        // base is the formula's current row and original_base its row before
+        // the shift
+        let shift = |coord: CellRefCoord, base: i64, original_base: i64| match coord {
+            CellRefCoord::Relative(offset) => {
+                let target = original_base + offset;
+                let target = if rows.contains(&target) {
+                    target + delta
+                } else {
+                    target
+                };
+                CellRefCoord::Relative(target - base)
+            }
+            absolute => absolute,
+        };
+
+        self.grid.sheets().iter().for_each(|sheet| {
+            sheet.code_runs.iter().for_each(|(pos, code_run)| {
+                // formulas on the sheet within the rows were moved
+                let original_y = if sheet.id == sheet_id && rows.contains(&pos.y) {
+                    pos.y - delta
+                } else {
+                    pos.y
+                };
+                if original_y == pos.y
+                    && !code_run.cells_accessed.iter().any(|sheet_rect| {
+                        // if the cells accessed overlap the shifted rows
+                        sheet_rect.sheet_id == sheet_id
+                            && sheet_rect.max.y >= *rows.start()
+                            && sheet_rect.min.y <= *rows.end()
+                    })
+                {
+                    return;
+                }
+
+                // only update formulas (for now)
+                let Some(CellValue::Code(code)) = sheet.cell_value_ref(*pos) else {
+                    return;
+                };
+                let new_code = replace_cell_references_with(
+                    &code.code,
+                    *pos,
+                    |_, cell_ref| cell_ref,
+                    |coord_sheet_name, cell_ref| {
+                        let coord_sheet_name = coord_sheet_name.as_ref().unwrap_or(&sheet.name);
+                        if coord_sheet_name == sheet_name {
+                            shift(cell_ref, pos.y, original_y)
+                        } else {
+                            match cell_ref {
+                                CellRefCoord::Relative(offset) => {
+                                    CellRefCoord::Relative(original_y + offset - pos.y)
+                                }
+                                absolute => absolute,
                             }
                         }
-                    }
+                    },
+                );
+                if new_code != code.code {
+                    let code_cell_value = CellValue::Code(CodeCellValue {
+                        code: new_code,
+                        ..code.clone()
+                    });
+                    transaction.operations.push_back(Operation::SetCellValues {
+                        sheet_pos: pos.to_sheet_pos(sheet.id),
+                        values: code_cell_value.into(),
+                    });
+                }
+            });
+        });
+    }
+
+    /// Adds a warning for each of the rows of sheet_id (before they are
+    /// deleted) that code cells reference. Code cells in the deleted rows are
+    /// left out since they are deleted too.
+    fn warn_deleted_row_dependents(
+        &self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        rows: &[i64],
+    ) {
+        for &row in rows {
+            let sheet_rect = SheetRect::new(i64::MIN, row, i64::MAX, row, sheet_id);
+            let Some(mut dependents) = self.get_dependent_code_cells(&sheet_rect) else {
+                continue;
+            };
+            dependents.retain(|pos| pos.sheet_id != sheet_id || !rows.contains(&pos.y));
+            if !dependents.is_empty() {
+                transaction
+                    .warnings
+                    .push(TransactionWarning::DeletedRowDependents {
+                        sheet_id,
+                        row,
+                        dependents,
+                    });
+            }
+        }
+    }
+
+    /// Rewrites formula references after rows (sorted and deduplicated) were
+    /// deleted from sheet_id. References into the deleted rows become #REF!,
+    /// and references below them shift up. A range only becomes #REF! when
+    /// all its rows were deleted; otherwise it shrinks.
+    fn adjust_formulas_deleted_rows(
+        &self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        sheet_name: &str,
+        rows: &[i64],
+    ) {
+        let Some(&first_row) = rows.first() else {
+            return;
+        };
+
+        // number of deleted rows before (or through) y
+        let before = |y: i64| rows.partition_point(|&r| r < y) as i64;
+        let through = |y: i64| rows.partition_point(|&r| r <= y) as i64;
+
+        self.grid.sheets().iter().for_each(|sheet| {
+            sheet.code_runs.iter().for_each(|(pos, code_run)| {
+                // formulas on the sheet below a deleted row were moved up
+                let original_y = if sheet.id == sheet_id {
+                    rows.iter()
+                        .fold(pos.y, |y, &row| if row <= y { y + 1 } else { y })
+                } else {
+                    pos.y
+                };
+                if original_y == pos.y
+                    && !code_run.cells_accessed.iter().any(|sheet_rect| {
+                        sheet_rect.sheet_id == sheet_id && sheet_rect.max.y >= first_row
+                    })
+                {
+                    return;
+                }
+                let Some(CellValue::Code(code)) = sheet.cell_value_ref(*pos) else {
+                    return;
+                };
+                if code.language != CodeCellLanguage::Formula {
+                    return;
+                }
+
+                let rebase = |coord: CellRefCoord, y: i64| match coord {
+                    CellRefCoord::Relative(_) => CellRefCoord::Relative(y - pos.y),
+                    CellRefCoord::Absolute(_) => CellRefCoord::Absolute(y),
+                };
+
+                // returns the new coordinates of the rows from top to bottom,
+                // or None if all of them were deleted
+                let shift_rows =
+                    |coord_sheet_name: &Option<String>, top: CellRefCoord, bottom: CellRefCoord| {
+                        let (top_y, bottom_y) = (
+                            top.resolve_from(original_y),
+                            bottom.resolve_from(original_y),
+                        );
+                        if coord_sheet_name.as_ref().unwrap_or(&sheet.name) != sheet_name {
+                            return Some((rebase(top, top_y), rebase(bottom, bottom_y)));
+                        }
+                        let (min, max) = (top_y.min(bottom_y), top_y.max(bottom_y));
+                        if through(max) - before(min) == max - min + 1 {
+                            return None;
+                        }
+                        let (new_min, new_max) = (min - before(min), max - through(max));
+                        if top_y <= bottom_y {
+                            Some((rebase(top, new_min), rebase(bottom, new_max)))
+                        } else {
+                            Some((rebase(top, new_max), rebase(bottom, new_min)))
+                        }
+                    };
+
+                let new_code =
+                    replace_cell_references_or_ref_error(&code.code, *pos, |range_ref| {
+                        match range_ref {
+                            RangeRef::Cell { pos: cell_ref } => {
+                                let (y, _) = shift_rows(&cell_ref.sheet, cell_ref.y, cell_ref.y)?;
+                                Some(RangeRef::Cell {
+                                    pos: CellRef { y, ..cell_ref },
+                                })
+                            }
+                            RangeRef::CellRange { start, end } => {
+                                let (start_y, end_y) = shift_rows(&start.sheet, start.y, end.y)?;
+                                Some(RangeRef::CellRange {
+                                    start: CellRef {
+                                        y: start_y,
+                                        ..start
+                                    },
+                                    end: CellRef { y: end_y, ..end },
+                                })
+                            }
+                            RangeRef::RowRange { start, end, sheet } => {
+                                let (start, end) = shift_rows(&sheet, start, end)?;
+                                Some(RangeRef::RowRange { start, end, sheet })
+                            }
+                            RangeRef::ColRange { .. } => Some(range_ref),
+                        }
+                    });
+                if new_code != code.code {
+                    let code_cell_value = CellValue::Code(CodeCellValue {
+                        code: new_code,
+                        ..code.clone()
+                    });
+                    transaction.operations.push_back(Operation::SetCellValues {
+                        sheet_pos: pos.to_sheet_pos(sheet.id),
+                        values: code_cell_value.into(),
+                    });
                 }
             });
         });
@@ -160,29 +362,123 @@ impl GridController {
     }
 
     pub fn execute_delete_row(&mut self, transaction: &mut PendingTransaction, op: Operation) {
-        if let Operation::DeleteRow { sheet_id, row } = op.clone() {
+        if let Operation::DeleteRow {
+            sheet_id,
+            row,
+            shift,
+            freeze_on_delete,
+        } = op.clone()
+        {
+            if transaction.is_user() {
+                self.warn_deleted_row_dependents(transaction, sheet_id, &[row]);
+            }
+
             let sheet_name: String;
+            // the rows from shifted.start() to shifted.end() move by delta
+            let (shifted, delta);
             if let Some(sheet) = self.try_sheet_mut(sheet_id) {
-                sheet.delete_row(transaction, row);
+                (shifted, delta) = match shift {
+                    ShiftDirection::Up => (row..=i64::MAX, -1),
+                    ShiftDirection::Down => (sheet.shift_down_top(row)..=row, 1),
+                };
+                match (shift, freeze_on_delete) {
+                    (_, true) => sheet.delete_row_freeze_code_runs(transaction, row, shift),
+                    (ShiftDirection::Up, false) => sheet.delete_row(transaction, row),
+                    (ShiftDirection::Down, false) => sheet.delete_row_shift_down(transaction, row),
+                }
                 transaction.forward_operations.push(op);
 
-                sheet.recalculate_bounds();
                 sheet_name = sheet.name.clone();
             } else {
                 // nothing more can be done
+                transaction
+                    .errors
+                    .push(CoreError::SheetNotFound(sheet_id.to_string()));
                 return;
             }
 
             if transaction.is_user() {
-                // adjust formulas to account for deleted column (needs to be
-                // here since it's across sheets)
-                self.adjust_formulas(transaction, sheet_id, sheet_name, None, Some(row), -1);
+                // adjust formulas to account for deleted row (needs to be
+                // here since it's across sheets). References to the deleted
+                // row move with the shifted rows.
+                self.adjust_formulas_rows(transaction, sheet_id, &sheet_name, shifted, delta);
 
-                // update information for all cells below the deleted row
+                // update information for all cells below the deleted row (or
+                // above it when shifting down)
+                if let Some(sheet) = self.try_sheet(sheet_id) {
+                    if let GridBounds::NonEmpty(bounds) = sheet.bounds(true) {
+                        let mut sheet_rect = bounds.to_sheet_rect(sheet_id);
+                        if shift == ShiftDirection::Up {
+                            sheet_rect.min.y = row;
+                        }
+                        self.check_deleted_code_runs(transaction, &sheet_rect);
+                        self.add_compute_operations(transaction, &sheet_rect, None);
+                        self.check_all_spills(transaction, sheet_rect.sheet_id, true);
+                    }
+                }
+            }
+
+            if !transaction.is_server() {
+                self.send_updated_bounds(sheet_id);
+            }
+        }
+    }
+
+    pub fn execute_delete_rows(&mut self, transaction: &mut PendingTransaction, op: Operation) {
+        if let Operation::DeleteRows {
+            sheet_id,
+            rows,
+            ref_errors,
+        } = op.clone()
+        {
+            if transaction.is_user() {
+                self.warn_deleted_row_dependents(transaction, sheet_id, &rows);
+            }
+
+            let sheet_name: String;
+            if let Some(sheet) = self.try_sheet_mut(sheet_id) {
+                sheet.delete_rows(transaction, &rows);
+                transaction.forward_operations.push(op);
+
+                sheet_name = sheet.name.clone();
+            } else {
+                // nothing more can be done
+                transaction
+                    .errors
+                    .push(CoreError::SheetNotFound(sheet_id.to_string()));
+                return;
+            }
+
+            let Some(min_row) = rows.iter().min().copied() else {
+                return;
+            };
+
+            if transaction.is_user() {
+                // adjust formulas to account for the deleted rows (from the
+                // bottom, matching the order the rows were deleted)
+                let mut rows = rows;
+                rows.sort_unstable();
+                rows.dedup();
+                if ref_errors {
+                    self.adjust_formulas_deleted_rows(transaction, sheet_id, &sheet_name, &rows);
+                } else {
+                    for row in rows.into_iter().rev() {
+                        self.adjust_formulas(
+                            transaction,
+                            sheet_id,
+                            sheet_name.clone(),
+                            None,
+                            Some(row),
+                            -1,
+                        );
+                    }
+                }
+
+                // update information for all cells below the first deleted row
                 if let Some(sheet) = self.try_sheet(sheet_id) {
                     if let GridBounds::NonEmpty(bounds) = sheet.bounds(true) {
                         let mut sheet_rect = bounds.to_sheet_rect(sheet_id);
-                        sheet_rect.min.y = row;
+                        sheet_rect.min.y = min_row;
                         self.check_deleted_code_runs(transaction, &sheet_rect);
                         self.add_compute_operations(transaction, &sheet_rect, None);
                         self.check_all_spills(transaction, sheet_rect.sheet_id, true);
@@ -205,7 +501,18 @@ impl GridController {
         {
             let sheet_name: String;
             if let Some(sheet) = self.try_sheet_mut(sheet_id) {
-                sheet.insert_column(transaction, column, copy_formats);
+                if let Err(error) = sheet.insert_column(transaction, column, copy_formats) {
+                    // user actions check the limits before queuing, so
+                    // this only catches replayed operations (eg, from
+                    // another user). The sheet is unchanged, so the
+                    // operation is dropped; the remaining operations were
+                    // built assuming the insert (eg, moving content into the
+                    // new space), so they are dropped as well
+                    dbgjs!(&format!("[execute_insert_column] {error}"));
+                    transaction.errors.push(error);
+                    transaction.operations.clear();
+                    return;
+                }
                 transaction.forward_operations.push(op);
 
                 sheet.recalculate_bounds();
@@ -247,16 +554,33 @@ impl GridController {
         {
             let sheet_name: String;
             if let Some(sheet) = self.try_sheet_mut(sheet_id) {
-                sheet.insert_row(transaction, row, copy_formats);
+                if let Err(error) = sheet.insert_row(transaction, row, copy_formats) {
+                    // user actions check the limits before queuing, so
+                    // this only catches replayed operations (eg, from
+                    // another user). The sheet is unchanged, so the
+                    // operation is dropped; the remaining operations were
+                    // built assuming the insert (eg, moving content into the
+                    // new space), so they are dropped as well
+                    dbgjs!(&format!("[execute_insert_row] {error}"));
+                    transaction.errors.push(error);
+                    transaction.operations.clear();
+                    return;
+                }
                 transaction.forward_operations.push(op);
 
-                sheet.recalculate_bounds();
                 sheet_name = sheet.name.clone();
             } else {
                 // nothing more can be done
+                transaction
+                    .errors
+                    .push(CoreError::SheetNotFound(sheet_id.to_string()));
                 return;
             }
 
+            // Once inserted, the row is always committed. Code runs recomputed
+            // below that fail store their error in the code run (like any
+            // other run), so the sheet stays consistent and there is nothing
+            // to roll back.
             if transaction.is_user() {
                 // adjust formulas to account for deleted column (needs to be
                 // here since it's across sheets)
@@ -279,23 +603,72 @@ impl GridController {
             }
         }
     }
+
+    pub fn execute_swap_rows(&mut self, transaction: &mut PendingTransaction, op: Operation) {
+        if let Operation::SwapRows {
+            sheet_id,
+            row_a,
+            row_b,
+        } = op
+        {
+            if let Some(sheet) = self.try_sheet_mut(sheet_id) {
+                sheet.swap_rows(transaction, row_a, row_b);
+                transaction.forward_operations.push(op);
+
+                sheet.recalculate_bounds();
+            } else {
+                // nothing more can be done
+                transaction
+                    .errors
+                    .push(CoreError::SheetNotFound(sheet_id.to_string()));
+                return;
+            }
+
+            if transaction.is_user() {
+                // update information for all cells in the swapped rows
+                if let Some(sheet) = self.try_sheet(sheet_id) {
+                    if let GridBounds::NonEmpty(bounds) = sheet.bounds(true) {
+                        for row in [row_a, row_b] {
+                            let mut sheet_rect = bounds.to_sheet_rect(sheet_id);
+                            sheet_rect.min.y = row;
+                            sheet_rect.max.y = row;
+                            self.add_compute_operations(transaction, &sheet_rect, None);
+                        }
+                        self.check_all_spills(transaction, sheet_id, true);
+                    }
+                }
+            }
+
+            if !transaction.is_server() {
+                self.send_updated_bounds(sheet_id);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
     use serial_test::{parallel, serial};
     use uuid::Uuid;
 
     use crate::{
+        cell_values::CellValues,
+        controller::{
+            active_transactions::transaction_name::TransactionName,
+            operations::operation::CopyFormats,
+        },
         grid::{
+            formats::{format_update::FormatUpdate, Formats},
             sheet::validations::{validation::Validation, validation_rules::ValidationRule},
-            CodeCellLanguage,
+            BorderSelection, BorderStyle, CodeCellLanguage,
         },
         selection::Selection,
-        wasm_bindings::js::{clear_js_calls, expect_js_call_count, expect_js_offsets},
-        Pos, Rect, SheetPos, DEFAULT_COLUMN_WIDTH, DEFAULT_ROW_HEIGHT,
+        wasm_bindings::js::{
+            clear_js_calls, expect_js_call, expect_js_call_count, expect_js_offsets,
+        },
+        Pos, Rect, RunErrorMsg, SheetPos, SheetRect, DEFAULT_COLUMN_WIDTH, DEFAULT_ROW_HEIGHT,
     };
 
     use super::*;
@@ -407,7 +780,7 @@ mod tests {
             sheet.bounds(false),
             GridBounds::NonEmpty(Rect::new(1, 1, 3, 1))
         );
-        gc.insert_column(sheet_id, 3, true, None);
+        gc.insert_column(sheet_id, 3, true, None).unwrap();
 
         let sheet = gc.sheet(sheet_id);
         assert_eq!(
@@ -437,7 +810,7 @@ mod tests {
             sheet.bounds(false),
             GridBounds::NonEmpty(Rect::new(1, 1, 1, 3))
         );
-        gc.insert_row(sheet_id, 3, true, None);
+        gc.insert_row(sheet_id, 3, true, None).unwrap();
 
         let sheet = gc.sheet(sheet_id);
         assert_eq!(
@@ -446,6 +819,110 @@ mod tests {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn execute_insert_row_max_rows() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_values(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            vec![vec!["A"], vec!["B"], vec!["C"]],
+            None,
+        );
+        gc.sheet_mut(sheet_id).max_rows = Some(3);
+        let original = gc.sheet(sheet_id).clone();
+
+        // the insert is refused and the sheet is unchanged
+        assert!(matches!(
+            gc.insert_row(sheet_id, 2, true, None),
+            Err(CoreError::SheetLimitExceeded(_))
+        ));
+        assert_eq!(gc.sheet(sheet_id), &original);
+
+        // replayed operations (eg, from another user) are checked again when
+        // executed: the error is recorded and the operations that follow the
+        // insert are dropped
+        let mut transaction = PendingTransaction {
+            operations: vec![
+                Operation::InsertRow {
+                    sheet_id,
+                    row: 2,
+                    copy_formats: CopyFormats::None,
+                },
+                Operation::SetCellValues {
+                    sheet_pos: SheetPos {
+                        x: 1,
+                        y: 2,
+                        sheet_id,
+                    },
+                    values: CellValues::from(CellValue::Text("X".to_string())),
+                },
+            ]
+            .into(),
+            ..Default::default()
+        };
+        gc.execute_operation(&mut transaction);
+        assert!(transaction.operations.is_empty());
+        assert!(matches!(
+            transaction.errors.as_slice(),
+            [CoreError::SheetLimitExceeded(_)]
+        ));
+        assert!(transaction.forward_operations.is_empty());
+        assert_eq!(gc.sheet(sheet_id), &original);
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_undo_code_run_indices() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        // code_runs is in creation order (not position order)
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet.test_set_code_run_single(1, 5, CellValue::Number(5.into()));
+        sheet.test_set_code_run_single(1, 2, CellValue::Number(2.into()));
+        sheet.test_set_code_run_single(1, 3, CellValue::Number(3.into()));
+        sheet.test_set_code_run_single(1, 4, CellValue::Number(4.into()));
+        let order = |gc: &GridController| {
+            gc.sheet(sheet_id)
+                .code_runs
+                .keys()
+                .map(|pos| pos.y)
+                .collect::<Vec<_>>()
+        };
+
+        // the moved runs keep their place in code_runs
+        gc.delete_rows(sheet_id, vec![2], None);
+        assert_eq!(order(&gc), vec![4, 2, 3]);
+
+        // the reverse operation's index is where the run was
+        let undo = gc.undo_stack.last().unwrap();
+        let index = undo
+            .operations
+            .iter()
+            .find_map(|op| match op {
+                Operation::SetCodeRun {
+                    sheet_pos, index, ..
+                } if sheet_pos.y == 2 => Some(*index),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(index, 1);
+
+        gc.undo(None);
+        assert_eq!(order(&gc), vec![5, 2, 3, 4]);
+
+        gc.redo(None);
+        assert_eq!(order(&gc), vec![4, 2, 3]);
+        gc.undo(None);
+        assert_eq!(order(&gc), vec![5, 2, 3, 4]);
+    }
+
     #[test]
     #[parallel]
     fn delete_column_formula() {
@@ -556,75 +1033,394 @@ mod tests {
 
     #[test]
     #[parallel]
-    fn insert_column_validation() {
+    fn insert_row_recomputes_formula() {
         let mut gc = GridController::test();
         let sheet_id = gc.sheet_ids()[0];
-        gc.update_validation(
-            Validation {
-                id: Uuid::new_v4(),
-                selection: Selection {
-                    sheet_id,
-                    rects: Some(vec![Rect::new(1, 1, 3, 3)]),
-                    columns: Some(vec![2]),
-                    ..Default::default()
-                },
-                rule: ValidationRule::Logical(Default::default()),
-                message: Default::default(),
-                error: Default::default(),
-            },
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 3), "1".into(), None);
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 1, 1),
+            CodeCellLanguage::Formula,
+            "B3".into(),
             None,
         );
 
-        gc.insert_column(sheet_id, 2, true, None);
+        gc.insert_row(sheet_id, 2, false, None).unwrap();
 
+        // the formula is rerun within the insert transaction, so it now reads
+        // from the shifted cell without needing a manual rerun
         let sheet = gc.sheet(sheet_id);
-        assert_eq!(sheet.validations.validations.len(), 1);
-
-        assert_eq!(
-            sheet.validations.validations[0].selection.columns,
-            Some(vec![3])
-        );
+        let code_run = sheet.code_run(Pos { x: 1, y: 1 }).unwrap();
+        assert!(code_run
+            .cells_accessed
+            .contains(&SheetRect::single_pos(Pos { x: 1, y: 4 }, sheet_id)));
         assert_eq!(
-            sheet.validations.validations[0].selection.rects,
-            Some(vec![Rect::new(1, 1, 4, 3)])
+            sheet.rendered_value(Pos { x: 1, y: 1 }).unwrap(),
+            "1".to_string()
         );
     }
 
     #[test]
     #[parallel]
-    fn insert_row_validation() {
+    fn insert_row_recompute_error() {
         let mut gc = GridController::test();
         let sheet_id = gc.sheet_ids()[0];
-        gc.update_validation(
-            Validation {
-                id: Uuid::new_v4(),
-                selection: Selection {
-                    sheet_id,
-                    rects: Some(vec![Rect::new(1, 1, 3, 3)]),
-                    rows: Some(vec![2]),
-                    ..Default::default()
-                },
-                rule: ValidationRule::Logical(Default::default()),
-                message: Default::default(),
-                error: Default::default(),
-            },
+
+        gc.set_cell_values(
+            SheetPos::new(sheet_id, 2, 1),
+            vec![vec!["1"], vec!["2"], vec!["3"]],
             None,
         );
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 3, 1),
+            CodeCellLanguage::Formula,
+            "1/(1-COUNTBLANK(B1:B3))".into(),
+            None,
+        );
+        assert_eq!(
+            gc.sheet(sheet_id)
+                .rendered_value(Pos { x: 3, y: 1 })
+                .unwrap(),
+            "1".to_string()
+        );
 
-        gc.insert_row(sheet_id, 2, true, None);
-
+        // the inserted row adds a blank cell to the range, so the recompute
+        // fails; the insert is kept and the code run holds the error
+        gc.insert_row(sheet_id, 2, false, None).unwrap();
         let sheet = gc.sheet(sheet_id);
-        assert_eq!(sheet.validations.validations.len(), 1);
-
+        assert_eq!(sheet.cell_value(Pos { x: 2, y: 2 }), None);
         assert_eq!(
-            sheet.validations.validations[0].selection.rows,
-            Some(vec![3])
+            sheet.cell_value(Pos { x: 2, y: 4 }),
+            Some(CellValue::Number(3.into()))
         );
+        let code_run = sheet.code_run(Pos { x: 3, y: 1 }).unwrap();
         assert_eq!(
-            sheet.validations.validations[0].selection.rects,
-            Some(vec![Rect::new(1, 1, 3, 4)])
+            code_run.get_error().map(|error| error.msg),
+            Some(RunErrorMsg::DivideByZero)
         );
-    }
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 2 }),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            sheet.rendered_value(Pos { x: 3, y: 1 }).unwrap(),
+            "1".to_string()
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_formula_references() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 0, 4), "3".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 0, 5), "7".into(), None);
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 1, 10),
+            CodeCellLanguage::Formula,
+            "A5".into(),
+            None,
+        );
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 2, 10),
+            CodeCellLanguage::Formula,
+            "$A$5".into(),
+            None,
+        );
+
+        gc.insert_row(sheet_id, 3, false, None).unwrap();
+
+        // the relative reference follows the shifted cell; the absolute
+        // reference stays fixed
+        let sheet = gc.sheet(sheet_id);
+        let code_string = |pos: Pos| sheet.edit_code_value(pos).unwrap().code_string;
+        assert_eq!(code_string(Pos { x: 1, y: 11 }), "A6");
+        assert_eq!(code_string(Pos { x: 2, y: 11 }), "$A$5");
+
+        gc.rerun_code_cell(SheetPos::new(sheet_id, 1, 11), None);
+        gc.rerun_code_cell(SheetPos::new(sheet_id, 2, 11), None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.rendered_value(Pos { x: 1, y: 11 }).unwrap(),
+            "7".to_string()
+        );
+        assert_eq!(
+            sheet.rendered_value(Pos { x: 2, y: 11 }).unwrap(),
+            "3".to_string()
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_dependents_warning() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 0, 2), "5".into(), None);
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 0, 5),
+            CodeCellLanguage::Formula,
+            "A2".into(),
+            None,
+        );
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 1, 5),
+            CodeCellLanguage::Formula,
+            "A3".into(),
+            None,
+        );
+
+        let mut transaction = PendingTransaction {
+            operations: vec![Operation::DeleteRow {
+                sheet_id,
+                row: 2,
+                shift: ShiftDirection::Up,
+                freeze_on_delete: false,
+            }]
+            .into(),
+            ..Default::default()
+        };
+        while !transaction.operations.is_empty() {
+            gc.execute_operation(&mut transaction);
+        }
+        assert_eq!(
+            transaction.warnings,
+            vec![TransactionWarning::DeletedRowDependents {
+                sheet_id,
+                row: 2,
+                dependents: HashSet::from([SheetPos::new(sheet_id, 0, 5)]),
+            }]
+        );
+
+        // deleting a row that nothing references adds no warning
+        let mut transaction = PendingTransaction {
+            operations: vec![Operation::DeleteRows {
+                sheet_id,
+                rows: vec![10],
+                ref_errors: false,
+            }]
+            .into(),
+            ..Default::default()
+        };
+        while !transaction.operations.is_empty() {
+            gc.execute_operation(&mut transaction);
+        }
+        assert!(transaction.warnings.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn delete_row_dependents_warning_sent() {
+        clear_js_calls();
+
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(SheetPos::new(sheet_id, 0, 2), "5".into(), None);
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 0, 5),
+            CodeCellLanguage::Formula,
+            "A2".into(),
+            None,
+        );
+        gc.delete_rows(sheet_id, vec![2], None);
+
+        // the warnings are sent to the client when the transaction finalizes
+        let transaction_id = gc.undo_stack.last().unwrap().id;
+        let warnings = vec![TransactionWarning::DeletedRowDependents {
+            sheet_id,
+            row: 2,
+            dependents: HashSet::from([SheetPos::new(sheet_id, 0, 5)]),
+        }];
+        expect_js_call(
+            "jsTransactionWarnings",
+            format!(
+                "{},{}",
+                transaction_id,
+                serde_json::to_string(&warnings).unwrap()
+            ),
+            true,
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_rows_keeping_formulas() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_values(
+            SheetPos::new(sheet_id, 0, 3),
+            vec![vec!["3"], vec!["4"], vec!["5"]],
+            None,
+        );
+        let formulas = [(2, "A5"), (3, "A3"), (4, "SUM(A3:A5)"), (5, "SUM(A3:A3)")];
+        for (x, code) in formulas {
+            gc.set_code_cell(
+                SheetPos::new(sheet_id, x, 1),
+                CodeCellLanguage::Formula,
+                code.into(),
+                None,
+            );
+        }
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 2, 10),
+            CodeCellLanguage::Formula,
+            "A5 + A1".into(),
+            None,
+        );
+
+        gc.delete_rows_keeping_formulas(sheet_id, vec![3], None);
+        for x in 2..=5 {
+            gc.rerun_code_cell(SheetPos::new(sheet_id, x, 1), None);
+        }
+        gc.rerun_code_cell(SheetPos::new(sheet_id, 2, 9), None);
+
+        let sheet = gc.sheet(sheet_id);
+        let code_string = |pos: Pos| sheet.edit_code_value(pos).unwrap().code_string;
+        assert_eq!(code_string(Pos { x: 2, y: 1 }), "A4");
+        assert_eq!(code_string(Pos { x: 3, y: 1 }), "#REF!");
+        assert_eq!(code_string(Pos { x: 4, y: 1 }), "SUM(A3:A4)");
+        assert_eq!(code_string(Pos { x: 5, y: 1 }), "SUM(#REF!)");
+        assert_eq!(code_string(Pos { x: 2, y: 9 }), "A4 + A1");
+
+        assert_eq!(
+            sheet.rendered_value(Pos { x: 2, y: 1 }).unwrap(),
+            "5".to_string()
+        );
+        assert_eq!(
+            sheet.rendered_value(Pos { x: 4, y: 1 }).unwrap(),
+            "9".to_string()
+        );
+        let code_run = sheet.code_run(Pos { x: 3, y: 1 }).unwrap();
+        assert_eq!(
+            code_run.get_error().map(|error| error.msg),
+            Some(RunErrorMsg::BadCellReference)
+        );
+
+        // undo restores the original references
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet
+                .edit_code_value(Pos { x: 3, y: 1 })
+                .unwrap()
+                .code_string,
+            "A3"
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 0, y: 3 }),
+            Some(CellValue::Number(3.into()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_moved_formula_references() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 0, 1), "5".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 0, 6), "6".into(), None);
+        gc.set_code_cell(
+            SheetPos::new(sheet_id, 1, 10),
+            CodeCellLanguage::Formula,
+            "A1 + A6".into(),
+            None,
+        );
+
+        // the formula moves up with its row; its references still point at
+        // A1 (above the deleted row) and the moved A6 (now A5)
+        gc.delete_rows(sheet_id, vec![3], None);
+        gc.rerun_code_cell(SheetPos::new(sheet_id, 1, 9), None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.rendered_value(Pos { x: 1, y: 9 }).unwrap(),
+            "11".to_string()
+        );
+        assert_eq!(
+            sheet
+                .edit_code_value(Pos { x: 1, y: 9 })
+                .unwrap()
+                .code_string,
+            "A1 + A5"
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_column_validation() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.update_validation(
+            Validation {
+                id: Uuid::new_v4(),
+                selection: Selection {
+                    sheet_id,
+                    rects: Some(vec![Rect::new(1, 1, 3, 3)]),
+                    columns: Some(vec![2]),
+                    ..Default::default()
+                },
+                rule: ValidationRule::Logical(Default::default()),
+                message: Default::default(),
+                error: Default::default(),
+            },
+            None,
+        );
+
+        gc.insert_column(sheet_id, 2, true, None).unwrap();
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(sheet.validations.validations.len(), 1);
+
+        assert_eq!(
+            sheet.validations.validations[0].selection.columns,
+            Some(vec![3])
+        );
+        assert_eq!(
+            sheet.validations.validations[0].selection.rects,
+            Some(vec![Rect::new(1, 1, 4, 3)])
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_validation() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.update_validation(
+            Validation {
+                id: Uuid::new_v4(),
+                selection: Selection {
+                    sheet_id,
+                    rects: Some(vec![Rect::new(1, 1, 3, 3)]),
+                    rows: Some(vec![2]),
+                    ..Default::default()
+                },
+                rule: ValidationRule::Logical(Default::default()),
+                message: Default::default(),
+                error: Default::default(),
+            },
+            None,
+        );
+
+        gc.insert_row(sheet_id, 2, true, None).unwrap();
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(sheet.validations.validations.len(), 1);
+
+        assert_eq!(
+            sheet.validations.validations[0].selection.rows,
+            Some(vec![3])
+        );
+        assert_eq!(
+            sheet.validations.validations[0].selection.rects,
+            Some(vec![Rect::new(1, 1, 3, 4)])
+        );
+    }
 
     #[test]
     #[parallel]
@@ -657,6 +1453,17 @@ mod tests {
             sheet.validations.validations[0].selection.rects,
             Some(vec![Rect::new(1, 1, 2, 3)])
         );
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.validations.validations[0].selection.columns,
+            Some(vec![2])
+        );
+        assert_eq!(
+            sheet.validations.validations[0].selection.rects,
+            Some(vec![Rect::new(1, 1, 3, 3)])
+        );
     }
 
     #[test]
@@ -692,6 +1499,447 @@ mod tests {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn delete_row_single_row_validation() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let validation = Validation {
+            id: Uuid::new_v4(),
+            selection: Selection {
+                sheet_id,
+                rects: Some(vec![Rect::new(1, 2, 3, 2)]),
+                ..Default::default()
+            },
+            rule: ValidationRule::Logical(Default::default()),
+            message: Default::default(),
+            error: Default::default(),
+        };
+        gc.update_validation(validation.clone(), None);
+
+        gc.delete_rows(sheet_id, vec![2], None);
+        assert!(gc.sheet(sheet_id).validations.validations.is_empty());
+
+        gc.undo(None);
+        assert_eq!(gc.sheet(sheet_id).validations.validations, vec![validation]);
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_formats_rows_only() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_bold_selection(Selection::rows(&[2], sheet_id), true, None)
+            .unwrap();
+        gc.set_italic_selection(Selection::rows(&[4], sheet_id), true, None)
+            .unwrap();
+        assert!(gc.sheet(sheet_id).bounds(true).is_empty());
+
+        let format = |gc: &GridController, row: i64| {
+            gc.sheet(sheet_id)
+                .formats_rows
+                .get(&row)
+                .map(|(format, _)| (format.bold, format.italic))
+        };
+
+        gc.delete_rows(sheet_id, vec![2], None);
+        assert_eq!(format(&gc, 2), None);
+        assert_eq!(format(&gc, 3), Some((None, Some(true))));
+        assert_eq!(format(&gc, 4), None);
+
+        gc.undo(None);
+        assert_eq!(format(&gc, 2), Some((Some(true), None)));
+        assert_eq!(format(&gc, 3), None);
+        assert_eq!(format(&gc, 4), Some((None, Some(true))));
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_date_time_format() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_date_time_format(
+            Selection::rows(&[2], sheet_id),
+            Some("%Y-%m-%d".to_string()),
+            None,
+        )
+        .unwrap();
+        gc.set_date_time_format(
+            Selection::pos(1, 3, sheet_id),
+            Some("%H:%M".to_string()),
+            None,
+        )
+        .unwrap();
+
+        let date_time = |gc: &GridController, row: i64| {
+            gc.sheet(sheet_id)
+                .formats_rows
+                .get(&row)
+                .and_then(|(format, _)| format.date_time.clone())
+        };
+
+        gc.delete_rows(sheet_id, vec![2], None);
+        assert_eq!(date_time(&gc, 2), None);
+        assert_eq!(
+            gc.sheet(sheet_id).format_cell(1, 2, false).date_time,
+            Some("%H:%M".to_string())
+        );
+
+        gc.undo(None);
+        assert_eq!(date_time(&gc, 2), Some("%Y-%m-%d".to_string()));
+        assert_eq!(date_time(&gc, 3), None);
+        assert_eq!(
+            gc.sheet(sheet_id).format_cell(1, 3, false).date_time,
+            Some("%H:%M".to_string())
+        );
+        assert_eq!(gc.sheet(sheet_id).format_cell(1, 2, false).date_time, None);
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_fill_and_validation() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_fill_color_selection(
+            Selection::rows(&[2], sheet_id),
+            Some("red".to_string()),
+            None,
+        )
+        .unwrap();
+        gc.set_fill_color_selection(
+            Selection::rows(&[3], sheet_id),
+            Some("blue".to_string()),
+            None,
+        )
+        .unwrap();
+        let validation = Validation {
+            id: Uuid::new_v4(),
+            selection: Selection::rows(&[2], sheet_id),
+            rule: ValidationRule::Logical(Default::default()),
+            message: Default::default(),
+            error: Default::default(),
+        };
+        gc.update_validation(validation.clone(), None);
+
+        let fill = |gc: &GridController, row: i64| {
+            gc.sheet(sheet_id)
+                .formats_rows
+                .get(&row)
+                .and_then(|(format, _)| format.fill_color.clone())
+        };
+
+        gc.delete_rows(sheet_id, vec![2], None);
+        assert_eq!(fill(&gc, 2), Some("blue".to_string()));
+        assert_eq!(fill(&gc, 3), None);
+        assert!(gc.sheet(sheet_id).validations.validations.is_empty());
+
+        gc.undo(None);
+        assert_eq!(fill(&gc, 2), Some("red".to_string()));
+        assert_eq!(fill(&gc, 3), Some("blue".to_string()));
+        assert_eq!(gc.sheet(sheet_id).validations.validations, vec![validation]);
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_shift_down() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_values(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            vec![vec!["A"], vec!["B"], vec!["C"], vec!["D"]],
+            None,
+        );
+        gc.set_bold_selection(Selection::pos(1, 1, sheet_id), true, None)
+            .unwrap();
+
+        gc.start_user_transaction(
+            vec![Operation::DeleteRow {
+                sheet_id,
+                row: 3,
+                shift: ShiftDirection::Down,
+                freeze_on_delete: false,
+            }],
+            None,
+            TransactionName::ManipulateColumnRow,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 1 }), None);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Text("B".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 4 }),
+            Some(CellValue::Text("D".to_string()))
+        );
+        assert_eq!(sheet.format_cell(1, 1, false).bold, None);
+        assert_eq!(sheet.format_cell(1, 2, false).bold, Some(true));
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        for (y, value) in ["A", "B", "C", "D"].iter().enumerate() {
+            assert_eq!(
+                sheet.cell_value(Pos {
+                    x: 1,
+                    y: y as i64 + 1
+                }),
+                Some(CellValue::Text(value.to_string()))
+            );
+        }
+        assert_eq!(sheet.format_cell(1, 1, false).bold, Some(true));
+        assert_eq!(sheet.format_cell(1, 2, false).bold, None);
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_shift_down_formulas() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 2), "5".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 6), "7".into(), None);
+        for (x, y, code) in [(3, 3, "B2"), (3, 8, "B2 + B6"), (4, 8, "B4")] {
+            gc.set_code_cell(
+                SheetPos::new(sheet_id, x, y),
+                CodeCellLanguage::Formula,
+                code.into(),
+                None,
+            );
+        }
+
+        // rows 2 and 3 move down into the deleted row 4
+        gc.start_user_transaction(
+            vec![Operation::DeleteRow {
+                sheet_id,
+                row: 4,
+                shift: ShiftDirection::Down,
+                freeze_on_delete: false,
+            }],
+            None,
+            TransactionName::ManipulateColumnRow,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        let code_string = |pos: Pos| sheet.edit_code_value(pos).unwrap().code_string;
+        assert_eq!(code_string(Pos { x: 3, y: 4 }), "B3");
+        assert_eq!(code_string(Pos { x: 3, y: 8 }), "B3 + B6");
+        assert_eq!(code_string(Pos { x: 4, y: 8 }), "B5");
+
+        gc.rerun_code_cell(SheetPos::new(sheet_id, 3, 8), None);
+        assert_eq!(
+            gc.sheet(sheet_id)
+                .rendered_value(Pos { x: 3, y: 8 })
+                .unwrap(),
+            "12".to_string()
+        );
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        let code_string = |pos: Pos| sheet.edit_code_value(pos).unwrap().code_string;
+        assert_eq!(code_string(Pos { x: 3, y: 3 }), "B2");
+        assert_eq!(code_string(Pos { x: 3, y: 8 }), "B2 + B6");
+        assert_eq!(code_string(Pos { x: 4, y: 8 }), "B4");
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_freeze_code_runs() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.sheet_mut(sheet_id)
+            .test_set_code_run_array(1, 2, vec!["1", "2", "3"], true);
+
+        gc.start_user_transaction(
+            vec![Operation::DeleteRow {
+                sheet_id,
+                row: 2,
+                shift: ShiftDirection::Up,
+                freeze_on_delete: true,
+            }],
+            None,
+            TransactionName::ManipulateColumnRow,
+        );
+
+        // the output below the anchor moved up and became static values
+        let sheet = gc.sheet(sheet_id);
+        assert!(sheet.code_runs.is_empty());
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Number(3.into()))
+        );
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 4 }), None);
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        assert!(sheet.code_run(Pos { x: 1, y: 2 }).is_some());
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 3 }), None);
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 4 }),
+            Some(CellValue::Number(3.into()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn row_ops_round_trip() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        let mut ops = vec![Operation::SetCellValues {
+            sheet_pos: SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            values: vec![vec!["A", "B", "C"], vec!["D", "E", "F"]].into(),
+        }];
+        ops.extend(
+            gc.set_borders_selection_operations(
+                Selection::sheet_rect(SheetRect::new(1, 1, 2, 2, sheet_id)),
+                BorderSelection::All,
+                Some(BorderStyle::default()),
+            )
+            .unwrap(),
+        );
+        ops.push(Operation::SetCellFormatsSelection {
+            selection: Selection::rows(&[3], sheet_id),
+            formats: Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        });
+        ops.push(Operation::DeleteRow {
+            sheet_id,
+            row: 2,
+            shift: ShiftDirection::Up,
+            freeze_on_delete: false,
+        });
+        ops.push(Operation::InsertRow {
+            sheet_id,
+            row: 1,
+            copy_formats: CopyFormats::None,
+        });
+        ops.push(Operation::DeleteRow {
+            sheet_id,
+            row: 3,
+            shift: ShiftDirection::Down,
+            freeze_on_delete: false,
+        });
+
+        gc.assert_round_trip(ops);
+    }
+
+    #[test]
+    #[parallel]
+    fn row_ops_missing_sheet() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_cell_value(
+            SheetPos {
+                x: 1,
+                y: 1,
+                sheet_id,
+            },
+            "A".to_string(),
+            None,
+        );
+
+        // operations on a sheet that no longer exists (eg, deleted by another
+        // user) record an error without touching the grid or the undo stack
+        let missing = SheetId::new();
+        let mut transaction = PendingTransaction {
+            operations: vec![
+                Operation::DeleteRow {
+                    sheet_id: missing,
+                    row: 1,
+                    shift: ShiftDirection::Up,
+                    freeze_on_delete: false,
+                },
+                Operation::DeleteRows {
+                    sheet_id: missing,
+                    rows: vec![1, 2],
+                    ref_errors: false,
+                },
+                Operation::InsertRow {
+                    sheet_id: missing,
+                    row: 1,
+                    copy_formats: CopyFormats::None,
+                },
+                Operation::SwapRows {
+                    sheet_id: missing,
+                    row_a: 1,
+                    row_b: 2,
+                },
+                Operation::CopyFormatsRange {
+                    sheet_id: missing,
+                    source: Rect::new(1, 1, 1, 1),
+                    target: Rect::new(1, 2, 1, 2),
+                },
+            ]
+            .into(),
+            ..Default::default()
+        };
+        while !transaction.operations.is_empty() {
+            gc.execute_operation(&mut transaction);
+        }
+        assert!(transaction.forward_operations.is_empty());
+        assert!(transaction.reverse_operations.is_empty());
+        assert_eq!(
+            transaction.errors,
+            vec![CoreError::SheetNotFound(missing.to_string()); 5]
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn row_ops_missing_sheet_sends_errors() {
+        clear_js_calls();
+
+        let mut gc = GridController::test();
+        let missing = SheetId::new();
+        gc.swap_rows(missing, 1, 2, None);
+
+        // the errors are sent to the client when the transaction finalizes
+        let transaction_id = gc.undo_stack.last().unwrap().id;
+        let errors = vec![CoreError::SheetNotFound(missing.to_string())];
+        expect_js_call(
+            "jsTransactionErrors",
+            format!(
+                "{},{}",
+                transaction_id,
+                serde_json::to_string(&errors).unwrap()
+            ),
+            true,
+        );
+    }
+
     #[test]
     #[parallel]
     fn delete_columns() {
@@ -756,6 +2004,59 @@ mod tests {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn swap_rows() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 2), "A".into(), None);
+        gc.set_cell_value(SheetPos::new(sheet_id, 1, 10), "B".into(), None);
+        gc.set_bold_selection(Selection::pos(1, 2, sheet_id), true, None)
+            .unwrap();
+        gc.set_borders_selection(
+            Selection::pos(1, 2, sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.sheet_mut(sheet_id).offsets.set_row_height(10, 50.0);
+
+        gc.swap_rows(sheet_id, 2, 10, None);
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("B".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 10 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+        assert_eq!(sheet.format_cell(1, 2, false).bold, None);
+        assert_eq!(sheet.format_cell(1, 10, false).bold, Some(true));
+        assert!(sheet.borders.update_override(1, 10).top.flatten().is_some());
+        assert!(sheet.borders.update_override(1, 2).top.flatten().is_none());
+        assert_eq!(sheet.offsets.row_height(2), 50.0);
+        assert_eq!(sheet.offsets.row_height(10), DEFAULT_ROW_HEIGHT);
+
+        gc.undo(None);
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 10 }),
+            Some(CellValue::Text("B".to_string()))
+        );
+        assert_eq!(sheet.format_cell(1, 2, false).bold, Some(true));
+        assert_eq!(sheet.format_cell(1, 10, false).bold, None);
+        assert!(sheet.borders.update_override(1, 2).top.flatten().is_some());
+        assert_eq!(sheet.offsets.row_height(10), 50.0);
+    }
+
     #[test]
     #[serial]
     fn insert_column_offsets() {
@@ -764,7 +2065,7 @@ mod tests {
         let mut gc = GridController::test();
         let sheet_id = gc.sheet_ids()[0];
 
-        gc.insert_column(sheet_id, 1, true, None);
+        gc.insert_column(sheet_id, 1, true, None).unwrap();
         expect_js_call_count("jsOffsetsModified", 0, true);
 
         let sheet = gc.sheet_mut(sheet_id);
@@ -772,7 +2073,7 @@ mod tests {
         sheet.offsets.set_column_width(2, 200.0);
         sheet.offsets.set_column_width(4, 400.0);
 
-        gc.insert_column(sheet_id, 2, true, None);
+        gc.insert_column(sheet_id, 2, true, None).unwrap();
         let mut offsets = HashMap::<(Option<i64>, Option<i64>), f64>::new();
         offsets.insert((Some(2), None), DEFAULT_COLUMN_WIDTH);
         offsets.insert((Some(3), None), 200.0);
@@ -824,7 +2125,7 @@ mod tests {
         let mut gc = GridController::test();
         let sheet_id = gc.sheet_ids()[0];
 
-        gc.insert_row(sheet_id, 1, true, None);
+        gc.insert_row(sheet_id, 1, true, None).unwrap();
         expect_js_call_count("jsOffsetsModified", 0, true);
 
         let sheet = gc.sheet_mut(sheet_id);
@@ -832,7 +2133,7 @@ mod tests {
         sheet.offsets.set_row_height(2, 200.0);
         sheet.offsets.set_row_height(4, 400.0);
 
-        gc.insert_row(sheet_id, 2, true, None);
+        gc.insert_row(sheet_id, 2, true, None).unwrap();
         let mut offsets = HashMap::<(Option<i64>, Option<i64>), f64>::new();
         offsets.insert((None, Some(2)), DEFAULT_ROW_HEIGHT);
         offsets.insert((None, Some(3)), 200.0);
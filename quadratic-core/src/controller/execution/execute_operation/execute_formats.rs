@@ -1,8 +1,11 @@
 use crate::controller::active_transactions::pending_transaction::PendingTransaction;
 use crate::controller::operations::operation::Operation;
 use crate::controller::GridController;
+use crate::error_core::CoreError;
+use crate::grid::formats::Formats;
 use crate::grid::formatting::CellFmtArray;
 use crate::grid::*;
+use crate::selection::Selection;
 use formatting::DateTimeFormatting;
 
 impl GridController {
@@ -173,6 +176,75 @@ impl GridController {
             }
         }
     }
+
+    /// Executes ClearFormatsSelection operation.
+    pub fn execute_clear_formats_selection(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        op: Operation,
+    ) {
+        if let Operation::ClearFormatsSelection { selection, borders } = op {
+            let Some(sheet) = self.try_sheet_mut(selection.sheet_id) else {
+                // sheet may have been deleted
+                return;
+            };
+            sheet.clear_formatting(transaction, &selection, borders);
+
+            if !transaction.is_server() {
+                self.send_updated_bounds_selection(&selection, true);
+            }
+
+            transaction.generate_thumbnail |= self.thumbnail_dirty_selection(&selection);
+
+            transaction
+                .forward_operations
+                .push(Operation::ClearFormatsSelection { selection, borders });
+        }
+    }
+
+    /// Executes CopyFormatsRange operation.
+    ///
+    /// The operation is expanded into a SetCellFormatsSelection for the
+    /// target, which is placed at the front of the queue and generates the
+    /// reverse operations. Source is tiled across target.
+    pub fn execute_copy_formats_range(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        op: Operation,
+    ) {
+        if let Operation::CopyFormatsRange {
+            sheet_id,
+            source,
+            target,
+        } = op
+        {
+            let Some(sheet) = self.try_sheet(sheet_id) else {
+                // sheet may have been deleted
+                transaction
+                    .errors
+                    .push(CoreError::SheetNotFound(sheet_id.to_string()));
+                return;
+            };
+            let width = source.width() as i64;
+            let height = source.height() as i64;
+
+            // formats are read in the same column-major order as set_formats_rects
+            let mut formats = Formats::new();
+            for x in target.x_range() {
+                for y in target.y_range() {
+                    let source_x = source.min.x + (x - target.min.x).rem_euclid(width);
+                    let source_y = source.min.y + (y - target.min.y).rem_euclid(height);
+                    formats.push(sheet.format_cell(source_x, source_y, true).to_replace());
+                }
+            }
+            transaction
+                .operations
+                .push_front(Operation::SetCellFormatsSelection {
+                    selection: Selection::rect(target, sheet_id),
+                    formats,
+                });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -180,11 +252,13 @@ mod test {
     use std::collections::HashSet;
 
     use chrono::Utc;
-    use serial_test::serial;
+    use serial_test::{parallel, serial};
 
     use super::*;
+    use crate::controller::active_transactions::transaction_name::TransactionName;
+    use crate::grid::formats::format::Format;
     use crate::wasm_bindings::js::expect_js_call;
-    use crate::{CellValue, CodeCellValue, Pos, SheetRect, Value};
+    use crate::{CellValue, CodeCellValue, Pos, Rect, SheetRect, Value};
 
     #[test]
     #[serial]
@@ -236,4 +310,95 @@ mod test {
         );
         expect_js_call("jsSendImage", args, true);
     }
+
+    #[test]
+    #[parallel]
+    fn execute_copy_formats_range() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let source = Rect::new(1, 1, 3, 1);
+        gc.set_bold_selection(Selection::rect(source, sheet_id), true, None)
+            .unwrap();
+        gc.set_fill_color_selection(
+            Selection::rect(Rect::new(2, 1, 2, 1), sheet_id),
+            Some("red".to_string()),
+            None,
+        )
+        .unwrap();
+
+        let target = Rect::new(1, 2, 3, 4);
+        gc.copy_formats_range(sheet_id, source, target, None);
+
+        let sheet = gc.sheet(sheet_id);
+        for pos in target.iter() {
+            let format = sheet.format_cell(pos.x, pos.y, false);
+            assert_eq!(format.bold, Some(true));
+            let fill_color = (pos.x == 2).then(|| "red".to_string());
+            assert_eq!(format.fill_color, fill_color);
+        }
+        assert_eq!(sheet.format_cell(1, 5, false), Format::default());
+
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        for pos in target.iter() {
+            assert_eq!(sheet.format_cell(pos.x, pos.y, false), Format::default());
+        }
+        assert_eq!(
+            sheet.format_cell(2, 1, false).fill_color,
+            Some("red".to_string())
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn execute_clear_formats_selection() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let rect = Rect::new(1, 1, 3, 3);
+        let selection = Selection::sheet_rect(rect.to_sheet_rect(sheet_id));
+
+        gc.set_bold_selection(selection.clone(), true, None)
+            .unwrap();
+        gc.set_fill_color_selection(selection.clone(), Some("red".to_string()), None)
+            .unwrap();
+        gc.set_borders_selection(
+            selection.clone(),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        gc.start_user_transaction(
+            vec![Operation::ClearFormatsSelection {
+                selection: selection.clone(),
+                borders: true,
+            }],
+            None,
+            TransactionName::SetFormats,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        for pos in rect.iter() {
+            assert_eq!(sheet.format_cell(pos.x, pos.y, false), Format::default());
+            assert!(sheet.borders.get(pos.x, pos.y).is_empty());
+        }
+
+        gc.undo(None);
+
+        let sheet = gc.sheet(sheet_id);
+        for pos in rect.iter() {
+            let format = sheet.format_cell(pos.x, pos.y, false);
+            assert_eq!(format.bold, Some(true));
+            assert_eq!(format.fill_color, Some("red".to_string()));
+            assert!(!sheet.borders.get(pos.x, pos.y).is_empty());
+        }
+
+        gc.redo(None);
+
+        let sheet = gc.sheet(sheet_id);
+        for pos in rect.iter() {
+            assert_eq!(sheet.format_cell(pos.x, pos.y, false), Format::default());
+            assert!(sheet.borders.get(pos.x, pos.y).is_empty());
+        }
+    }
 }
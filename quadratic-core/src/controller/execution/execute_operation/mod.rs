@@ -29,6 +29,12 @@ impl GridController {
                 Operation::SetCellFormatsSelection { .. } => {
                     self.execute_set_cell_formats_selection(transaction, op);
                 }
+                Operation::ClearFormatsSelection { .. } => {
+                    self.execute_clear_formats_selection(transaction, op);
+                }
+                Operation::CopyFormatsRange { .. } => {
+                    self.execute_copy_formats_range(transaction, op);
+                }
                 Operation::SetBorders { .. } => self.execute_set_borders(transaction, op),
                 Operation::SetBordersSelection { .. } => {
                     self.execute_set_borders_selection(transaction, op);
@@ -64,8 +70,10 @@ impl GridController {
 
                 Operation::DeleteColumn { .. } => self.execute_delete_column(transaction, op),
                 Operation::DeleteRow { .. } => self.execute_delete_row(transaction, op),
+                Operation::DeleteRows { .. } => self.execute_delete_rows(transaction, op),
                 Operation::InsertColumn { .. } => self.execute_insert_column(transaction, op),
                 Operation::InsertRow { .. } => self.execute_insert_row(transaction, op),
+                Operation::SwapRows { .. } => self.execute_swap_rows(transaction, op),
             }
 
             if cfg!(target_family = "wasm") || cfg!(test) {
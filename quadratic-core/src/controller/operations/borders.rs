@@ -277,10 +277,64 @@ impl GridController {
         });
     }
 
-    /// Creates border operations. Returns None if selection is empty.
+    /// Splits a rect's updates (in Rect::iter order) into rects that leave out
+    /// the no-op updates. Each rect is returned with its updates, also in
+    /// Rect::iter order. Runs with the same columns in consecutive rows share
+    /// a rect.
+    fn skip_noop_updates(
+        rect: &Rect,
+        updates: &BorderStyleCellUpdates,
+    ) -> Vec<(Rect, Vec<BorderStyleCellUpdate>)> {
+        let mut sparse: Vec<(Rect, Vec<BorderStyleCellUpdate>)> = vec![];
+
+        // indices in sparse of the rects that include the previous row
+        let mut open: Vec<usize> = vec![];
+
+        let mut values = updates.iter_values();
+        for y in rect.min.y..=rect.max.y {
+            let row: Vec<BorderStyleCellUpdate> = (rect.min.x..=rect.max.x)
+                .filter_map(|_| values.next().copied())
+                .collect();
+            let mut next_open = vec![];
+            let mut x = 0;
+            while x < row.len() {
+                if row[x].is_noop() {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < row.len() && !row[x].is_noop() {
+                    x += 1;
+                }
+                let min_x = rect.min.x + start as i64;
+                let max_x = rect.min.x + x as i64 - 1;
+                let run = &row[start..x];
+                match open
+                    .iter()
+                    .find(|&&i| sparse[i].0.min.x == min_x && sparse[i].0.max.x == max_x)
+                {
+                    Some(&i) => {
+                        sparse[i].0.max.y = y;
+                        sparse[i].1.extend_from_slice(run);
+                        next_open.push(i);
+                    }
+                    None => {
+                        next_open.push(sparse.len());
+                        sparse.push((Rect::new(min_x, y, max_x, y), run.to_vec()));
+                    }
+                }
+            }
+            open = next_open;
+        }
+        sparse
+    }
+
+    /// Creates border operations. Returns None if selection is empty. Cells
+    /// in rects that the border selection does not change (eg, the inside of
+    /// an outer border) are left out of the operation.
     pub fn set_borders_selection_operations(
         &self,
-        selection: Selection,
+        mut selection: Selection,
         border_selection: BorderSelection,
         style: Option<BorderStyle>,
     ) -> Option<Vec<Operation>> {
@@ -318,15 +372,24 @@ impl GridController {
                 Self::border_style_sheet(border_selection, style_sheet, &mut borders);
             }
         }
-        if let Some(rects) = selection.rects.as_ref() {
+        if let Some(rects) = selection.rects.take() {
+            let mut sparse_rects = vec![];
             for rect in rects {
+                let mut rect_borders = BorderStyleCellUpdates::default();
                 self.border_style_rect(
                     selection.sheet_id,
                     border_selection,
                     style_rect,
-                    rect,
-                    &mut borders,
+                    &rect,
+                    &mut rect_borders,
                 );
+                for (sparse_rect, updates) in Self::skip_noop_updates(&rect, &rect_borders) {
+                    updates.into_iter().for_each(|update| borders.push(update));
+                    sparse_rects.push(sparse_rect);
+                }
+            }
+            if !sparse_rects.is_empty() {
+                selection.rects = Some(sparse_rects);
             }
         }
         if !borders.is_empty() {
@@ -341,7 +404,10 @@ impl GridController {
 mod tests {
     use serial_test::parallel;
 
-    use crate::grid::sheet::borders::BorderStyleCell;
+    use crate::{
+        controller::active_transactions::transaction_name::TransactionName,
+        grid::sheet::borders::BorderStyleCell,
+    };
 
     use super::*;
 
@@ -730,6 +796,62 @@ mod tests {
         ));
     }
 
+    #[test]
+    #[parallel]
+    fn borders_operations_skip_noop() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let selection = Selection::rect(Rect::new(1, 1, 3, 3), sheet_id);
+        let expected = BorderStyle::default();
+
+        // the center cell of an outer border is not changed, so it is left
+        // out of the operation
+        let ops = gc
+            .set_borders_selection_operations(
+                selection.clone(),
+                BorderSelection::Outer,
+                Some(expected),
+            )
+            .unwrap();
+        assert_eq!(ops.len(), 1);
+        let Operation::SetBordersSelection {
+            selection: selection_op,
+            borders,
+        } = ops[0].clone()
+        else {
+            panic!("Expected SetBordersSelection")
+        };
+        assert_eq!(
+            selection_op.rects,
+            Some(vec![
+                Rect::new(1, 1, 3, 1),
+                Rect::new(1, 2, 1, 2),
+                Rect::new(3, 2, 3, 2),
+                Rect::new(1, 3, 3, 3),
+            ])
+        );
+        assert_eq!(borders.size(), 8);
+        assert!(borders.iter_values().all(|border| !border.is_noop()));
+
+        // the sparse operation sets the same borders
+        gc.start_user_transaction(ops, None, TransactionName::SetBorders);
+        let sheet = gc.sheet(sheet_id);
+        assert!(sheet.borders.get(2, 2).is_empty());
+        assert_eq!(sheet.borders.get(2, 1).top.unwrap().line, expected.line);
+        assert_eq!(sheet.borders.get(1, 2).left.unwrap().line, expected.line);
+        assert_eq!(sheet.borders.get(3, 2).right.unwrap().line, expected.line);
+        assert_eq!(sheet.borders.get(2, 3).bottom.unwrap().line, expected.line);
+
+        // a selection without changes has no operation
+        assert!(gc
+            .set_borders_selection_operations(
+                Selection::rect(Rect::new(5, 5, 5, 5), sheet_id),
+                BorderSelection::Inner,
+                Some(expected),
+            )
+            .is_none());
+    }
+
     #[test]
     #[parallel]
     fn check_sheet() {
@@ -806,4 +928,49 @@ mod tests {
             Some(Some(BorderStyleTimestamp::clear()))
         );
     }
+
+    #[test]
+    #[parallel]
+    fn outer_and_inner_3x3() {
+        let rect = Rect::new(1, 1, 3, 3);
+        let sides = |gc: &GridController, sheet_id: SheetId, x: i64, y: i64| {
+            let cell = gc.sheet(sheet_id).borders.get(x, y);
+            [
+                cell.top.is_some(),
+                cell.bottom.is_some(),
+                cell.left.is_some(),
+                cell.right.is_some(),
+            ]
+        };
+
+        // Outer only sets the perimeter sides
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_borders_selection(
+            Selection::rect(rect, sheet_id),
+            BorderSelection::Outer,
+            Some(BorderStyle::default()),
+            None,
+        );
+        assert_eq!(gc.sheet(sheet_id).borders.count_in_rect(rect), 12);
+        assert_eq!(sides(&gc, sheet_id, 1, 1), [true, false, true, false]);
+        assert_eq!(sides(&gc, sheet_id, 2, 1), [true, false, false, false]);
+        assert_eq!(sides(&gc, sheet_id, 2, 2), [false, false, false, false]);
+        assert_eq!(sides(&gc, sheet_id, 3, 3), [false, true, false, true]);
+
+        // Inner only sets the shared interior edges
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_borders_selection(
+            Selection::rect(rect, sheet_id),
+            BorderSelection::Inner,
+            Some(BorderStyle::default()),
+            None,
+        );
+        assert_eq!(gc.sheet(sheet_id).borders.count_in_rect(rect), 24);
+        assert_eq!(sides(&gc, sheet_id, 1, 1), [false, true, false, true]);
+        assert_eq!(sides(&gc, sheet_id, 2, 1), [false, true, true, true]);
+        assert_eq!(sides(&gc, sheet_id, 2, 2), [true, true, true, true]);
+        assert_eq!(sides(&gc, sheet_id, 3, 3), [true, false, true, false]);
+    }
 }
@@ -10,16 +10,28 @@ use crate::{
         sheet::validations::validation::Validation, CodeRun, Sheet, SheetBorders, SheetId,
     },
     selection::Selection,
-    SheetPos, SheetRect,
+    Rect, SheetPos, SheetRect,
 };
 
 /// Determine whether to copy the formats during an Insert operation from the
-/// column/row before or after (or none).
+/// column/row before or after (or none). Auto copies from whichever neighbor
+/// has formats (preferring Before when both do).
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum CopyFormats {
     Before,
     After,
     None,
+    Auto,
+}
+
+/// Determine which rows fill the gap left by a DeleteRow operation. Up moves
+/// the rows below the deleted row up; Down moves the rows above it down (for
+/// bottom-anchored tables).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum ShiftDirection {
+    #[default]
+    Up,
+    Down,
 }
 
 /// It might be better to Box the SheetSchema to avoid the large enum variant.
@@ -53,6 +65,22 @@ pub enum Operation {
         formats: Formats,
     },
 
+    // Clears all formats (and optionally borders) within the selection.
+    ClearFormatsSelection {
+        selection: Selection,
+
+        #[serde(default)]
+        borders: bool,
+    },
+
+    // Copies the effective formats of source to target (tiling source if
+    // target is larger). Expanded into a SetCellFormatsSelection.
+    CopyFormatsRange {
+        sheet_id: SheetId,
+        source: Rect,
+        target: Rect,
+    },
+
     // Deprecated. Use SetBordersSelection instead.
     SetBorders {
         sheet_rect: SheetRect,
@@ -61,6 +89,7 @@ pub enum Operation {
 
     SetBordersSelection {
         selection: Selection,
+        #[serde(with = "crate::grid::sheet::borders::borders_compact::updates_serde")]
         borders: BorderStyleCellUpdates,
     },
 
@@ -158,6 +187,25 @@ pub enum Operation {
     DeleteRow {
         sheet_id: SheetId,
         row: i64,
+        #[serde(default)]
+        shift: ShiftDirection,
+
+        // code runs anchored in the row leave their remaining output behind
+        // as static values
+        #[serde(default)]
+        freeze_on_delete: bool,
+    },
+
+    // Deletes many rows at once. The reverse operations for the rows' content
+    // are created once for all rows (see Sheet::delete_rows).
+    DeleteRows {
+        sheet_id: SheetId,
+        rows: Vec<i64>,
+
+        // formula references into the deleted rows become #REF! instead of
+        // shifting onto a neighboring row
+        #[serde(default)]
+        ref_errors: bool,
     },
     InsertColumn {
         sheet_id: SheetId,
@@ -169,6 +217,13 @@ pub enum Operation {
         row: i64,
         copy_formats: CopyFormats,
     },
+
+    // Swapping is its own inverse, so this is also its reverse operation.
+    SwapRows {
+        sheet_id: SheetId,
+        row_a: i64,
+        row_b: i64,
+    },
 }
 
 impl fmt::Display for Operation {
@@ -197,6 +252,23 @@ impl fmt::Display for Operation {
                     selection, formats
                 )
             }
+            Operation::ClearFormatsSelection { selection, borders } => {
+                write!(
+                    fmt,
+                    "ClearFormatsSelection {{ selection: {:?} borders: {} }}",
+                    selection, borders
+                )
+            }
+            Operation::CopyFormatsRange {
+                sheet_id,
+                source,
+                target,
+            } => {
+                write!(
+                    fmt,
+                    "CopyFormatsRange {{ sheet_id: {sheet_id}, source: {source:?}, target: {target:?} }}"
+                )
+            }
             Operation::AddSheet { sheet } => write!(fmt, "AddSheet {{ sheet: {} }}", sheet.name),
             Operation::DeleteSheet { sheet_id } => {
                 write!(fmt, "DeleteSheet {{ sheet_id: {} }}", sheet_id)
@@ -304,8 +376,26 @@ impl fmt::Display for Operation {
                     sheet_id, column
                 )
             }
-            Operation::DeleteRow { sheet_id, row } => {
-                write!(fmt, "DeleteRow {{ sheet_id: {}, row: {} }}", sheet_id, row)
+            Operation::DeleteRow {
+                sheet_id,
+                row,
+                shift,
+                freeze_on_delete,
+            } => {
+                write!(
+                    fmt,
+                    "DeleteRow {{ sheet_id: {sheet_id}, row: {row}, shift: {shift:?}, freeze_on_delete: {freeze_on_delete} }}"
+                )
+            }
+            Operation::DeleteRows {
+                sheet_id,
+                rows,
+                ref_errors,
+            } => {
+                write!(
+                    fmt,
+                    "DeleteRows {{ sheet_id: {sheet_id}, rows: {rows:?}, ref_errors: {ref_errors} }}"
+                )
             }
             Operation::InsertColumn {
                 sheet_id,
@@ -327,6 +417,16 @@ impl fmt::Display for Operation {
                     "InsertRow {{ sheet_id: {sheet_id}, row: {row}, copy_formats: {copy_formats:?} }}"
                 )
             }
+            Operation::SwapRows {
+                sheet_id,
+                row_a,
+                row_b,
+            } => {
+                write!(
+                    fmt,
+                    "SwapRows {{ sheet_id: {sheet_id}, row_a: {row_a}, row_b: {row_b} }}"
+                )
+            }
         }
     }
 }
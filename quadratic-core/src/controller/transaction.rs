@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -15,7 +15,12 @@ use crate::compression::{
 pub static SERIALIZATION_FORMAT: SerializationFormat = SerializationFormat::Json;
 pub static COMPRESSION_FORMAT: CompressionFormat = CompressionFormat::Zlib;
 pub static HEADER_SERIALIZATION_FORMAT: SerializationFormat = SerializationFormat::Bincode;
-pub static CURRENT_VERSION: &str = "1.0";
+pub static CURRENT_VERSION: &str = "1.1";
+
+// Versions that decompress_and_deserialize can read. 1.0 serialized the
+// borders of SetBordersSelection in the plain form; 1.1 uses the compact form
+// (see borders_compact::updates_serde, which reads both).
+static SUPPORTED_VERSIONS: [&str; 2] = ["1.0", "1.1"];
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransactionVersion {
@@ -60,17 +65,16 @@ impl Transaction {
     }
 
     /// Decompress and deserialize the transaction's operations, removing the
-    /// version header.
-    ///
-    /// When we start to add different transaction versions, we will need to
-    /// match the version and handle the versions separately.
+    /// version header. Returns an error for versions newer than this code.
     pub fn decompress_and_deserialize<T: DeserializeOwned>(operations: &[u8]) -> Result<T> {
         let (header, data) = remove_header(operations)?;
 
-        // We're currently not doing anything with the transaction version, but will in
-        // the future as we use different serialization and compression methods and/or
-        // different operation types.
-        let _version = deserialize::<TransactionVersion>(&HEADER_SERIALIZATION_FORMAT, header)?;
+        // All supported versions share the serialization and compression
+        // formats; they only differ in how some operations are encoded.
+        let version = deserialize::<TransactionVersion>(&HEADER_SERIALIZATION_FORMAT, header)?;
+        if !SUPPORTED_VERSIONS.contains(&version.version.as_str()) {
+            bail!("Unsupported transaction version {}", version.version);
+        }
 
         decompress_and_deserialize::<T>(&SERIALIZATION_FORMAT, &COMPRESSION_FORMAT, data)
     }
@@ -134,4 +138,44 @@ mod tests {
             Transaction::decompress_and_deserialize::<Vec<Operation>>(&compressed).unwrap();
         assert_eq!(operations, decompressed);
     }
+
+    /// Serializes operations with a version header, as an older or newer
+    /// client would.
+    fn serialize_with_version<T: Serialize>(version: &str, operations: T) -> Vec<u8> {
+        let version = TransactionVersion {
+            version: version.into(),
+        };
+        let header = serialize(&HEADER_SERIALIZATION_FORMAT, &version).unwrap();
+        let compressed =
+            serialize_and_compress(&SERIALIZATION_FORMAT, &COMPRESSION_FORMAT, operations).unwrap();
+        add_header(header, compressed).unwrap()
+    }
+
+    #[test]
+    #[parallel]
+    fn decompress_and_deserialize_versions() {
+        let selection = Selection::new_sheet_pos(1, 1, SheetId::test());
+        let borders = RunLengthEncoding::repeat(BorderStyleCellUpdate::all(), 1);
+        let operations = vec![Operation::SetBordersSelection {
+            selection: selection.clone(),
+            borders: borders.clone(),
+        }];
+
+        // 1.0 transactions have the plain borders form
+        let plain = vec![serde_json::json!({
+            "SetBordersSelection": { "selection": selection, "borders": borders }
+        })];
+        let compressed = serialize_with_version("1.0", &plain);
+        let decompressed =
+            Transaction::decompress_and_deserialize::<Vec<Operation>>(&compressed).unwrap();
+        assert_eq!(decompressed, operations);
+
+        let compressed = serialize_with_version(CURRENT_VERSION, &operations);
+        let decompressed =
+            Transaction::decompress_and_deserialize::<Vec<Operation>>(&compressed).unwrap();
+        assert_eq!(decompressed, operations);
+
+        let compressed = serialize_with_version("2.0", &operations);
+        assert!(Transaction::decompress_and_deserialize::<Vec<Operation>>(&compressed).is_err());
+    }
 }
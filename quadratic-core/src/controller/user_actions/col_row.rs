@@ -1,10 +1,15 @@
+use std::str::FromStr;
+
 use crate::{
     controller::{
         active_transactions::transaction_name::TransactionName,
         operations::operation::{CopyFormats, Operation},
         GridController,
     },
-    grid::SheetId,
+    error_core::{CoreError, Result},
+    grid::{formats::Formats, SheetId},
+    selection::Selection,
+    SheetPos, SheetRect,
 };
 
 impl GridController {
@@ -24,13 +29,28 @@ impl GridController {
         self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
     }
 
+    /// Deletes the columns of a selection's whole-column selections.
+    pub fn delete_selection_columns(&mut self, selection: Selection, cursor: Option<String>) {
+        if let Some(columns) = selection.columns {
+            if !columns.is_empty() {
+                self.delete_columns(selection.sheet_id, columns, cursor);
+            }
+        }
+    }
+
+    /// Inserts a column. Returns an error, without changing the sheet, if
+    /// the sheet does not exist or the insert would move content past
+    /// max_columns.
     pub fn insert_column(
         &mut self,
         sheet_id: SheetId,
         column: i64,
         after: bool,
         cursor: Option<String>,
-    ) {
+    ) -> Result<()> {
+        self.try_sheet(sheet_id)
+            .ok_or_else(|| CoreError::SheetNotFound(sheet_id.to_string()))?
+            .check_insert_column(column)?;
         let ops = vec![Operation::InsertColumn {
             sheet_id,
             column,
@@ -41,23 +61,71 @@ impl GridController {
             },
         }];
         self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
+        Ok(())
     }
 
-    pub fn delete_rows(&mut self, sheet_id: SheetId, mut rows: Vec<i64>, cursor: Option<String>) {
-        rows.sort_unstable();
-        rows.dedup();
-        rows.reverse();
-        let ops = rows
-            .iter()
-            .map(|row| Operation::DeleteRow {
-                sheet_id,
-                row: *row,
-            })
-            .collect();
+    pub fn delete_rows(&mut self, sheet_id: SheetId, rows: Vec<i64>, cursor: Option<String>) {
+        let ops = vec![Operation::DeleteRows {
+            sheet_id,
+            rows,
+            ref_errors: false,
+        }];
         self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
     }
 
-    pub fn insert_row(&mut self, sheet_id: SheetId, row: i64, after: bool, cursor: Option<String>) {
+    /// Deletes rows like delete_rows, but formula references into the
+    /// deleted rows become #REF! errors instead of shifting onto a
+    /// neighboring row. References below the deleted rows shift up.
+    pub fn delete_rows_keeping_formulas(
+        &mut self,
+        sheet_id: SheetId,
+        rows: Vec<i64>,
+        cursor: Option<String>,
+    ) {
+        let ops = vec![Operation::DeleteRows {
+            sheet_id,
+            rows,
+            ref_errors: true,
+        }];
+        self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
+    }
+
+    /// Deletes the rows of a selection's whole-row selections.
+    pub fn delete_selection_rows(&mut self, selection: Selection, cursor: Option<String>) {
+        if let Some(rows) = selection.rows {
+            if !rows.is_empty() {
+                self.delete_rows(selection.sheet_id, rows, cursor);
+            }
+        }
+    }
+
+    /// Inserts a row. If the cursor is a serialized Selection on this sheet,
+    /// returns it adjusted to stay on the same content: selected rows at or
+    /// after the inserted row move down by one.
+    ///
+    /// Returns an error, without changing the sheet, if the sheet does not
+    /// exist or the insert would move content past max_rows.
+    pub fn insert_row(
+        &mut self,
+        sheet_id: SheetId,
+        row: i64,
+        after: bool,
+        cursor: Option<String>,
+    ) -> Result<Option<Selection>> {
+        self.try_sheet(sheet_id)
+            .ok_or_else(|| CoreError::SheetNotFound(sheet_id.to_string()))?
+            .check_insert_rows(row, 1)?;
+        let selection = cursor
+            .as_deref()
+            .and_then(|cursor| Selection::from_str(cursor).ok())
+            .filter(|selection| selection.sheet_id == sheet_id)
+            .map(|mut selection| {
+                selection.inserted_row(row);
+                if selection.y >= row {
+                    selection.y += 1;
+                }
+                selection
+            });
         let ops = vec![Operation::InsertRow {
             sheet_id,
             row,
@@ -68,6 +136,137 @@ impl GridController {
             },
         }];
         self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
+        Ok(selection)
+    }
+
+    /// Inserts a row at the same position in every sheet (eg, for templates
+    /// that share a layout). This is a single transaction, so one undo
+    /// removes the row from all sheets.
+    ///
+    /// Returns an error, without changing any sheet, if the insert would
+    /// move content past max_rows in any sheet.
+    pub fn insert_row_all_sheets(
+        &mut self,
+        row: i64,
+        copy_formats: CopyFormats,
+        cursor: Option<String>,
+    ) -> Result<()> {
+        for sheet in self.grid.sheets() {
+            sheet.check_insert_rows(row, 1)?;
+        }
+        let ops = self
+            .sheet_ids()
+            .into_iter()
+            .map(|sheet_id| Operation::InsertRow {
+                sheet_id,
+                row,
+                copy_formats,
+            })
+            .collect::<Vec<_>>();
+        if !ops.is_empty() {
+            self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
+        }
+        Ok(())
+    }
+
+    pub fn swap_rows(&mut self, sheet_id: SheetId, row_a: i64, row_b: i64, cursor: Option<String>) {
+        let ops = vec![Operation::SwapRows {
+            sheet_id,
+            row_a,
+            row_b,
+        }];
+        self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
+    }
+
+    /// Moves rows from one sheet to another. The rows are removed from
+    /// from_sheet (shifting the rows below up) and inserted together at
+    /// target_row in to_sheet (shifting the rows below down). Cell values,
+    /// formats, borders, and code cells move with the rows (code cells are
+    /// rerun in to_sheet), as do row formats.
+    ///
+    /// This is a single transaction, so one undo restores both sheets.
+    ///
+    /// Returns an error, without changing either sheet, if a sheet does not
+    /// exist or the rows would move content in to_sheet past max_rows.
+    pub fn move_rows_between_sheets(
+        &mut self,
+        from_sheet: SheetId,
+        rows: Vec<i64>,
+        to_sheet: SheetId,
+        target_row: i64,
+        cursor: Option<String>,
+    ) -> Result<()> {
+        if from_sheet == to_sheet {
+            return Ok(());
+        }
+        let sheet = self
+            .try_sheet(from_sheet)
+            .ok_or_else(|| CoreError::SheetNotFound(from_sheet.to_string()))?;
+        let mut rows = rows;
+        rows.sort_unstable();
+        rows.dedup();
+        if rows.is_empty() {
+            return Ok(());
+        }
+        self.try_sheet(to_sheet)
+            .ok_or_else(|| CoreError::SheetNotFound(to_sheet.to_string()))?
+            .check_insert_rows(target_row, rows.len() as i64)?;
+
+        let mut ops = vec![];
+        for _ in &rows {
+            ops.push(Operation::InsertRow {
+                sheet_id: to_sheet,
+                row: target_row,
+                copy_formats: CopyFormats::None,
+            });
+        }
+
+        // runs of adjacent rows are moved together; the rows end up next to
+        // each other in to_sheet
+        let mut runs: Vec<(i64, i64)> = Vec::new();
+        for &row in &rows {
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == row => *end = row,
+                _ => runs.push((row, row)),
+            }
+        }
+        let mut dest_row = target_row;
+        for (start, end) in runs {
+            let mut bounds = sheet.rows_bounds(start, end, false);
+            for row in start..=end {
+                if let Some(rect) = sheet.borders.bounds_row(row, false, false) {
+                    if rect.min.x <= rect.max.x {
+                        bounds = Some(bounds.map_or((rect.min.x, rect.max.x), |(min, max)| {
+                            (min.min(rect.min.x), max.max(rect.max.x))
+                        }));
+                    }
+                }
+            }
+            if let Some((min, max)) = bounds {
+                ops.push(Operation::MoveCells {
+                    source: SheetRect::new(min, start, max, end, from_sheet),
+                    dest: SheetPos::new(to_sheet, min, dest_row),
+                });
+            }
+            dest_row += end - start + 1;
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            if let Some(format) = sheet.try_format_row(*row) {
+                ops.push(Operation::SetCellFormatsSelection {
+                    selection: Selection::rows(&[target_row + i as i64], to_sheet),
+                    formats: Formats::repeat(format.to_replace(), 1),
+                });
+            }
+        }
+
+        ops.push(Operation::DeleteRows {
+            sheet_id: from_sheet,
+            rows,
+            ref_errors: false,
+        });
+        self.start_user_transaction(ops, cursor, TransactionName::ManipulateColumnRow);
+        Ok(())
     }
 }
 
@@ -78,6 +277,7 @@ mod tests {
     use crate::{
         grid::{
             formats::{format::Format, format_update::FormatUpdate, Formats},
+            sheet::borders::{BorderSelection, BorderStyle},
             CodeCellLanguage,
         },
         CellValue, CodeCellValue, Pos, SheetPos,
@@ -85,6 +285,69 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    #[parallel]
+    fn delete_selection_rows() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.sheet_mut(sheet_id)
+            .test_set_values(1, 1, 1, 4, vec!["A", "B", "C", "D"]);
+
+        let selection = Selection {
+            sheet_id,
+            rows: Some(vec![2, 3]),
+            ..Default::default()
+        };
+        gc.delete_selection_rows(selection, None);
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.cell_value(Pos::new(1, 2)),
+            Some(CellValue::Text("D".to_string()))
+        );
+        assert_eq!(sheet.cell_value(Pos::new(1, 3)), None);
+
+        // a single undo restores both rows
+        gc.undo(None);
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.cell_value(Pos::new(1, 2)),
+            Some(CellValue::Text("B".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos::new(1, 3)),
+            Some(CellValue::Text("C".to_string()))
+        );
+        assert!(!gc.has_undo());
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_selection_columns() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.sheet_mut(sheet_id)
+            .test_set_values(1, 1, 4, 1, vec!["A", "B", "C", "D"]);
+
+        let selection = Selection {
+            sheet_id,
+            columns: Some(vec![2, 3]),
+            ..Default::default()
+        };
+        gc.delete_selection_columns(selection, None);
+        assert_eq!(
+            gc.sheet(sheet_id).cell_value(Pos::new(2, 1)),
+            Some(CellValue::Text("D".to_string()))
+        );
+
+        gc.undo(None);
+        assert_eq!(
+            gc.sheet(sheet_id).cell_value(Pos::new(3, 1)),
+            Some(CellValue::Text("C".to_string()))
+        );
+        assert!(!gc.has_undo());
+    }
+
     #[test]
     #[parallel]
     fn delete_row_undo_code() {
@@ -211,7 +474,7 @@ mod tests {
             }
         );
 
-        gc.insert_column(sheet_id, 1, true, None);
+        gc.insert_column(sheet_id, 1, true, None).unwrap();
 
         let sheet = gc.sheet(sheet_id);
 
@@ -284,7 +547,7 @@ mod tests {
             }
         );
 
-        gc.insert_column(sheet_id, 2, false, None);
+        gc.insert_column(sheet_id, 2, false, None).unwrap();
 
         let sheet = gc.sheet(sheet_id);
 
@@ -357,7 +620,7 @@ mod tests {
             }
         );
 
-        gc.insert_row(sheet_id, 1, true, None);
+        gc.insert_row(sheet_id, 1, true, None).unwrap();
 
         let sheet = gc.sheet(sheet_id);
 
@@ -430,7 +693,7 @@ mod tests {
             }
         );
 
-        gc.insert_row(sheet_id, 2, false, None);
+        gc.insert_row(sheet_id, 2, false, None).unwrap();
 
         let sheet = gc.sheet(sheet_id);
 
@@ -467,4 +730,256 @@ mod tests {
         assert!(sheet.format_cell(1, 0, true).is_default());
         assert!(sheet.format_cell(1, 2, true).is_default());
     }
+
+    #[test]
+    #[parallel]
+    fn insert_row_adjusts_selection() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        let selection = Selection::rows(&[3], sheet_id);
+        let cursor = serde_json::to_string(&selection).unwrap();
+        let adjusted = gc
+            .insert_row(sheet_id, 2, false, Some(cursor))
+            .unwrap()
+            .unwrap();
+        assert_eq!(adjusted, Selection::rows(&[4], sheet_id));
+
+        let selection = Selection::sheet_rect(SheetRect::new(1, 3, 2, 3, sheet_id));
+        let cursor = serde_json::to_string(&selection).unwrap();
+        let adjusted = gc
+            .insert_row(sheet_id, 3, false, Some(cursor))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            adjusted,
+            Selection::sheet_rect(SheetRect::new(1, 4, 2, 4, sheet_id))
+        );
+
+        // rows above the insert are unchanged
+        let selection = Selection::rows(&[3], sheet_id);
+        let cursor = serde_json::to_string(&selection).unwrap();
+        let adjusted = gc
+            .insert_row(sheet_id, 4, false, Some(cursor))
+            .unwrap()
+            .unwrap();
+        assert_eq!(adjusted, selection);
+
+        assert_eq!(gc.insert_row(sheet_id, 1, false, None).unwrap(), None);
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_all_sheets() {
+        let mut gc = GridController::test();
+        let sheet_a = gc.sheet_ids()[0];
+        gc.add_sheet(None);
+        let sheet_b = gc.sheet_ids()[1];
+
+        gc.sheet_mut(sheet_a)
+            .test_set_values(1, 1, 1, 5, vec!["1", "2", "3", "4", "5"]);
+        gc.sheet_mut(sheet_b)
+            .test_set_values(1, 1, 1, 2, vec!["1", "2"]);
+
+        gc.insert_row_all_sheets(2, CopyFormats::None, None)
+            .unwrap();
+
+        let a = gc.sheet(sheet_a);
+        assert_eq!(a.display_value(Pos::new(1, 2)), None);
+        assert_eq!(
+            a.display_value(Pos::new(1, 3)),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            a.display_value(Pos::new(1, 6)),
+            Some(CellValue::Number(5.into()))
+        );
+        let b = gc.sheet(sheet_b);
+        assert_eq!(b.display_value(Pos::new(1, 2)), None);
+        assert_eq!(
+            b.display_value(Pos::new(1, 3)),
+            Some(CellValue::Number(2.into()))
+        );
+
+        // a single undo reverts both sheets
+        gc.undo(None);
+        let a = gc.sheet(sheet_a);
+        for y in 1..=5 {
+            assert_eq!(
+                a.display_value(Pos::new(1, y)),
+                Some(CellValue::Number(y.into()))
+            );
+        }
+        assert_eq!(a.display_value(Pos::new(1, 6)), None);
+        let b = gc.sheet(sheet_b);
+        assert_eq!(
+            b.display_value(Pos::new(1, 2)),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(b.display_value(Pos::new(1, 3)), None);
+    }
+
+    #[test]
+    #[parallel]
+    fn move_rows_between_sheets() {
+        let mut gc = GridController::test();
+        let sheet_a = gc.sheet_ids()[0];
+        gc.add_sheet(None);
+        let sheet_b = gc.sheet_ids()[1];
+
+        gc.set_cell_value(SheetPos::new(sheet_a, 1, 1), "A".to_string(), None);
+        gc.set_cell_value(SheetPos::new(sheet_a, 1, 2), "5".to_string(), None);
+        gc.set_code_cell(
+            SheetPos::new(sheet_a, 2, 2),
+            CodeCellLanguage::Formula,
+            "B2 * 2".to_string(),
+            None,
+        );
+        gc.set_cell_value(SheetPos::new(sheet_a, 1, 3), "C".to_string(), None);
+        gc.set_bold_selection(Selection::pos(1, 2, sheet_a), true, None)
+            .unwrap();
+        gc.set_fill_color_selection(
+            Selection::rows(&[2], sheet_a),
+            Some("red".to_string()),
+            None,
+        )
+        .unwrap();
+        gc.set_borders_selection(
+            Selection::pos(1, 2, sheet_a),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_cell_value(SheetPos::new(sheet_b, 1, 1), "X".to_string(), None);
+        gc.set_cell_value(SheetPos::new(sheet_b, 1, 2), "Y".to_string(), None);
+
+        gc.move_rows_between_sheets(sheet_a, vec![2], sheet_b, 2, None)
+            .unwrap();
+
+        let a = gc.sheet(sheet_a);
+        assert_eq!(
+            a.display_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("C".to_string()))
+        );
+        assert!(a.code_runs.is_empty());
+        assert_eq!(a.format_cell(1, 2, false).bold, None);
+        assert_eq!(a.format_row(2).fill_color, None);
+        assert!(a.borders.get(1, 2).top.is_none());
+
+        // the formula still references the moved cell
+        let b = gc.sheet(sheet_b);
+        assert_eq!(
+            b.display_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            b.edit_code_value(Pos { x: 2, y: 2 }).unwrap().code_string,
+            "B2 * 2"
+        );
+        assert_eq!(
+            b.display_value(Pos { x: 2, y: 2 }),
+            Some(CellValue::Number(10.into()))
+        );
+        assert_eq!(
+            b.display_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Text("Y".to_string()))
+        );
+        assert_eq!(b.format_cell(1, 2, false).bold, Some(true));
+        assert_eq!(b.format_row(2).fill_color, Some("red".to_string()));
+        assert_eq!(b.format_row(3).fill_color, None);
+        assert!(b.borders.get(1, 2).top.is_some());
+        assert!(b.borders.get(1, 2).left.is_some());
+
+        // a single undo restores both sheets
+        gc.undo(None);
+        let a = gc.sheet(sheet_a);
+        assert_eq!(
+            a.display_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            a.edit_code_value(Pos { x: 2, y: 2 }).unwrap().code_string,
+            "B2 * 2"
+        );
+        assert_eq!(
+            a.display_value(Pos { x: 2, y: 2 }),
+            Some(CellValue::Number(10.into()))
+        );
+        assert_eq!(
+            a.display_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Text("C".to_string()))
+        );
+        assert_eq!(a.format_cell(1, 2, false).bold, Some(true));
+        assert_eq!(a.format_row(2).fill_color, Some("red".to_string()));
+        assert_eq!(a.format_row(3).fill_color, None);
+        assert!(a.borders.get(1, 2).top.is_some());
+        assert!(a.borders.get(1, 2).left.is_some());
+
+        let b = gc.sheet(sheet_b);
+        assert!(b.code_runs.is_empty());
+        assert_eq!(
+            b.display_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("Y".to_string()))
+        );
+        assert_eq!(b.display_value(Pos { x: 1, y: 3 }), None);
+        assert_eq!(b.format_cell(1, 2, false).bold, None);
+        assert_eq!(b.format_row(2).fill_color, None);
+        assert!(b.borders.get(1, 2).top.is_none());
+        assert!(b.borders.get(1, 2).left.is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn move_rows_between_sheets_limit() {
+        let mut gc = GridController::test();
+        let sheet_a = gc.sheet_ids()[0];
+        gc.add_sheet(None);
+        let sheet_b = gc.sheet_ids()[1];
+
+        gc.set_cell_value(SheetPos::new(sheet_a, 1, 2), "B".to_string(), None);
+        gc.set_cell_value(SheetPos::new(sheet_b, 1, 2), "Y".to_string(), None);
+        gc.sheet_mut(sheet_b).max_rows = Some(2);
+        let original_a = gc.sheet(sheet_a).clone();
+        let original_b = gc.sheet(sheet_b).clone();
+        let undo_len = gc.undo_stack.len();
+
+        // the insert into sheet_b is refused, so nothing is moved or
+        // overwritten
+        assert!(matches!(
+            gc.move_rows_between_sheets(sheet_a, vec![2], sheet_b, 2, None),
+            Err(CoreError::SheetLimitExceeded(_))
+        ));
+        assert_eq!(gc.sheet(sheet_a), &original_a);
+        assert_eq!(gc.sheet(sheet_b), &original_b);
+        assert_eq!(gc.undo_stack.len(), undo_len);
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_all_sheets_limit() {
+        let mut gc = GridController::test();
+        gc.add_sheet(None);
+        gc.add_sheet(None);
+        let sheet_ids = gc.sheet_ids();
+        for (i, sheet_id) in sheet_ids.iter().enumerate() {
+            gc.set_cell_value(SheetPos::new(*sheet_id, 1, 2), format!("{i}"), None);
+        }
+
+        // the second sheet is full, so no sheet gets the row
+        gc.sheet_mut(sheet_ids[1]).max_rows = Some(2);
+        let originals = sheet_ids
+            .iter()
+            .map(|sheet_id| gc.sheet(*sheet_id).clone())
+            .collect::<Vec<_>>();
+        let undo_len = gc.undo_stack.len();
+
+        assert!(matches!(
+            gc.insert_row_all_sheets(2, CopyFormats::None, None),
+            Err(CoreError::SheetLimitExceeded(_))
+        ));
+        for (sheet_id, original) in sheet_ids.iter().zip(originals.iter()) {
+            assert_eq!(gc.sheet(*sheet_id), original);
+        }
+        assert_eq!(gc.undo_stack.len(), undo_len);
+    }
 }
@@ -9,8 +9,11 @@ use crate::controller::operations::operation::Operation;
 use crate::controller::GridController;
 use crate::grid::formats::format_update::FormatUpdate;
 use crate::grid::formats::Formats;
-use crate::grid::{CellAlign, CellVerticalAlign, CellWrap, NumericFormat, NumericFormatKind};
+use crate::grid::{
+    CellAlign, CellVerticalAlign, CellWrap, NumericFormat, NumericFormatKind, SheetId,
+};
 use crate::selection::Selection;
+use crate::Rect;
 
 impl GridController {
     pub(crate) fn clear_format(
@@ -305,6 +308,23 @@ impl GridController {
         self.start_user_transaction(ops, cursor, TransactionName::SetFormats);
         Ok(())
     }
+
+    /// Copies the formats of source to target (eg, fill down of formats
+    /// only). Source is tiled if target is larger.
+    pub fn copy_formats_range(
+        &mut self,
+        sheet_id: SheetId,
+        source: Rect,
+        target: Rect,
+        cursor: Option<String>,
+    ) {
+        let ops = vec![Operation::CopyFormatsRange {
+            sheet_id,
+            source,
+            target,
+        }];
+        self.start_user_transaction(ops, cursor, TransactionName::SetFormats);
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,13 @@
 use uuid::Uuid;
 
 use crate::controller::{execution::TransactionType, GridController};
+#[cfg(test)]
+use crate::{
+    controller::{
+        active_transactions::transaction_name::TransactionName, operations::operation::Operation,
+    },
+    test_util::assert_sheets_match,
+};
 
 impl GridController {
     pub fn has_undo(&self) -> bool {
@@ -23,4 +30,28 @@ impl GridController {
             self.start_undo_transaction(transaction, TransactionType::Redo, cursor);
         }
     }
+
+    /// Applies each operation as its own user transaction, undoes them all,
+    /// and asserts that the sheets match their starting state. Then redoes
+    /// them and asserts that the sheets match the state after the operations.
+    #[cfg(test)]
+    pub fn assert_round_trip(&mut self, ops: Vec<Operation>) {
+        let start = self.grid.sheets().to_vec();
+        let undo_len = self.undo_stack.len();
+        for op in ops {
+            self.start_user_transaction(vec![op], None, TransactionName::Unknown);
+        }
+        let end = self.grid.sheets().to_vec();
+
+        let transactions = self.undo_stack.len() - undo_len;
+        for _ in 0..transactions {
+            self.undo(None);
+        }
+        assert_sheets_match(&start, self.grid.sheets(), "undo");
+
+        for _ in 0..transactions {
+            self.redo(None);
+        }
+        assert_sheets_match(&end, self.grid.sheets(), "redo");
+    }
 }
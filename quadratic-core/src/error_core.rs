@@ -30,6 +30,12 @@ pub enum CoreError {
 
     #[error("CodeCellSheetError: {0}")]
     CodeCellSheetError(String),
+
+    #[error("Sheet limit exceeded: {0}")]
+    SheetLimitExceeded(String),
+
+    #[error("Sheet not found: {0}")]
+    SheetNotFound(String),
 }
 
 impl From<serde_json::Error> for CoreError {
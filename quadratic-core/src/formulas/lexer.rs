@@ -75,6 +75,8 @@ const TOKEN_PATTERNS: &[&str] = &[
     r"//[^\n]*",
     // Start of a block comment (block comment has special handling).
     r"/\*",
+    // Reference to a deleted cell.
+    r"#REF!",
     // Sheet reference.
     UNQUOTED_SHEET_REFERENCE_PATTERN,
     // String literal.
@@ -224,6 +226,8 @@ pub enum Token {
     CellRef,
     #[strum(to_string = "internal cell reference")]
     InternalCellRef,
+    #[strum(to_string = "reference error")]
+    RefError,
     #[strum(to_string = "whitespace")]
     Whitespace,
     #[strum(to_string = "unknown symbol")]
@@ -266,6 +270,7 @@ impl Token {
             "..." => Self::Ellipsis,
             s if s.eq_ignore_ascii_case("false") => Self::False,
             s if s.eq_ignore_ascii_case("true") => Self::True,
+            s if s.eq_ignore_ascii_case("#REF!") => Self::RefError,
 
             // Match a line comment.
             s if s.starts_with("//") => Self::Comment,
@@ -132,6 +132,19 @@ pub fn replace_cell_references_with(
     })
 }
 
+/// Replace all cell references with internal cell references (RC notation) by
+/// applying the function `replace_fn`, which returns `None` for a reference to
+/// cells that no longer exist. Those references are replaced with `#REF!`.
+pub fn replace_cell_references_or_ref_error(
+    source: &str,
+    pos: Pos,
+    replace_fn: impl Fn(RangeRef) -> Option<RangeRef>,
+) -> String {
+    replace_cell_range_references(source, pos, |range_ref| {
+        replace_fn(range_ref).map_or_else(|| "#REF!".to_string(), |range_ref| range_ref.to_string())
+    })
+}
+
 fn replace_cell_range_references(
     source: &str,
     pos: Pos,
@@ -144,6 +144,26 @@ impl SyntaxRule for CellRangeReference {
     }
 }
 
+/// Matches `#REF!`, which replaces a reference to a deleted cell. This always
+/// fails with a bad cell reference error.
+#[derive(Debug, Copy, Clone)]
+pub struct RefErrorExpression;
+impl_display!(for RefErrorExpression, "reference error '#REF!'");
+impl SyntaxRule for RefErrorExpression {
+    type Output = AstNode;
+
+    fn prefix_matches(&self, mut p: Parser<'_>) -> bool {
+        p.next() == Some(Token::RefError)
+    }
+
+    fn consume_match(&self, p: &mut Parser<'_>) -> CodeResult<Self::Output> {
+        if p.next() != Some(Token::RefError) {
+            return p.expected(self);
+        }
+        Err(RunErrorMsg::BadCellReference.with_span(p.span()))
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct BoolExpression;
 impl_display!(for BoolExpression, "boolean such as 'TRUE' or 'FALSE'");
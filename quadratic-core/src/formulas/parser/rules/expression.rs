@@ -197,7 +197,8 @@ impl SyntaxRule for ExpressionWithPrecedence {
                 | Token::UnterminatedStringLiteral
                 | Token::NumericLiteral
                 | Token::CellRef
-                | Token::InternalCellRef => true,
+                | Token::InternalCellRef
+                | Token::RefError => true,
 
                 Token::Whitespace => false,
                 Token::Unknown => false,
@@ -219,6 +220,7 @@ impl SyntaxRule for ExpressionWithPrecedence {
                     NumericLiteral.map(Some),
                     ArrayLiteral.map(Some),
                     BoolExpression.map(Some),
+                    RefErrorExpression.map(Some),
                     ParenExpression.map(Some),
                 ],
             )
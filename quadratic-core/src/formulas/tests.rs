@@ -121,6 +121,14 @@ fn test_formula_circular_array_ref() {
     );
 }
 
+#[test]
+#[parallel]
+fn test_formula_ref_error() {
+    let g = Grid::new();
+    expect_err(&RunErrorMsg::BadCellReference, &g, "#REF!");
+    expect_err(&RunErrorMsg::BadCellReference, &g, "SUM(1, #ref!)");
+}
+
 #[test]
 #[parallel]
 fn test_formula_range_operator() {
@@ -126,6 +126,37 @@ impl Column {
         self.has_data_in_row(y) || self.has_format_in_row(y)
     }
 
+    /// Shifts all values at or after `start` by `delta` in one structural
+    /// pass (instead of removing and inserting each key).
+    ///
+    /// The caller is responsible for ensuring the shifted values do not land
+    /// on existing values (ie, when shifting up, the rows between `start +
+    /// delta` and `start` must already be empty).
+    ///
+    /// Returns the number of values that were moved.
+    pub fn shift_values(&mut self, start: i64, delta: i64) -> usize {
+        if delta == 0 {
+            return 0;
+        }
+        let tail = self.values.split_off(&start);
+        if tail.is_empty() {
+            return 0;
+        }
+        debug_assert!(self
+            .values
+            .last_key_value()
+            .map_or(true, |(y, _)| *y < start + delta));
+
+        // keys remain sorted after the shift, so the map can be rebuilt in bulk
+        let mut shifted: BTreeMap<i64, CellValue> = tail
+            .into_iter()
+            .map(|(y, value)| (y + delta, value))
+            .collect();
+        let count = shifted.len();
+        self.values.append(&mut shifted);
+        count
+    }
+
     /// Gets the Format for a column (which will eventually replace the data structure)
     pub fn format(&self, y: i64) -> Option<Format> {
         let format = Format {
@@ -230,7 +261,11 @@ impl<B: BlockContent> ColumnData<B> {
         match (self.remove_block_containing(y), value) {
             (None, None) => None,
             (None, Some(value)) => {
-                if let Some(block_above) = self.remove_block_containing(y - 1) {
+                // there is no block above i64::MIN
+                let block_above = y
+                    .checked_sub(1)
+                    .and_then(|above| self.remove_block_containing(above));
+                if let Some(block_above) = block_above {
                     // Push to bottom of block above.
                     self.add_blocks(block_above.push_bottom(value));
                     // Try to merge with block below.
@@ -384,8 +419,8 @@ impl<B: BlockContent> ColumnData<B> {
         let mut new_blocks = BTreeMap::new();
 
         for (start, block) in self.0.iter() {
-            // block is before the insertion point, then copy
-            if *start < y && (*start + block.len() as i64) < y {
+            // block ends before the insertion point, then copy
+            if *start + block.len() as i64 <= y {
                 new_blocks.insert(*start, block.clone());
             }
             // block is at or after the insertion point, then shift right
@@ -413,18 +448,30 @@ impl<B: BlockContent> ColumnData<B> {
         changed
     }
 
+    /// Swaps the values at two positions. Returns true if anything changed.
+    pub fn swap(&mut self, a: i64, b: i64) -> bool {
+        let value_a = self.get(a);
+        let value_b = self.get(b);
+        if value_a.is_none() && value_b.is_none() {
+            return false;
+        }
+        self.set(a, value_b);
+        self.set(b, value_a);
+        true
+    }
+
     /// Removes a position and shifts the remaining positions to the left.
     pub fn remove_and_shift_left(&mut self, y: i64) -> bool {
         let mut changed = false;
         let mut new_blocks = BTreeMap::new();
 
         for (start, block) in self.0.iter() {
-            // block is before the removal point, then copy
-            if *start < y && (*start + block.len() as i64) < y {
+            // block ends before the removal point, then copy
+            if *start + block.len() as i64 <= y {
                 new_blocks.insert(*start, block.clone());
             }
             // block contains the removal point
-            else if *start <= y && (*start + block.len() as i64) >= y {
+            else if *start <= y {
                 let [before, after] = block.clone().split(y);
                 if let Some(before) = before {
                     new_blocks.insert(*start, before);
@@ -451,6 +498,48 @@ impl<B: BlockContent> ColumnData<B> {
         changed
     }
 
+    /// Removes the positions in y_range and shifts the positions after it to
+    /// the left by the length of the range.
+    pub fn remove_range_and_shift_left(&mut self, y_range: Range<i64>) -> bool {
+        let len = y_range.end - y_range.start;
+        if len <= 0 {
+            return false;
+        }
+        let mut changed = false;
+        let mut new_blocks = BTreeMap::new();
+
+        for (start, block) in self.0.iter() {
+            // block ends before the removed range, then copy
+            if block.end() <= y_range.start {
+                new_blocks.insert(*start, block.clone());
+            }
+            // block is after the removed range, then shift left
+            else if *start >= y_range.end {
+                let mut new_block = block.clone();
+                new_block.y -= len;
+                new_blocks.insert(*start - len, new_block);
+                changed = true;
+            }
+            // block overlaps the removed range
+            else {
+                let [before, rest] = block.clone().split(y_range.start);
+                if let Some(before) = before {
+                    new_blocks.insert(before.y, before);
+                }
+                if let Some(rest) = rest {
+                    let [_, after] = rest.split(y_range.end);
+                    if let Some(mut after) = after {
+                        after.y -= len;
+                        new_blocks.insert(after.y, after);
+                    }
+                }
+                changed = true;
+            }
+        }
+        self.0 = new_blocks;
+        changed
+    }
+
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
@@ -804,6 +893,38 @@ mod test {
         assert_eq!(cd.get(5), None);
     }
 
+    #[test]
+    #[parallel]
+    fn remove_range_and_shift_left() {
+        let mut cd: ColumnData<SameValue<bool>> = ColumnData::new();
+        assert!(!cd.remove_range_and_shift_left(1..3));
+        cd.set_range(1..4, true);
+        cd.set_range(5..6, false);
+        cd.set_range(8..10, true);
+        assert!(cd.remove_range_and_shift_left(2..6));
+        assert_eq!(cd.get(1), Some(true));
+        assert_eq!(cd.get(2), None);
+        assert_eq!(cd.get(3), None);
+        assert_eq!(cd.get(4), Some(true));
+        assert_eq!(cd.get(5), Some(true));
+        assert_eq!(cd.get(6), None);
+
+        // nothing at or after the range
+        assert!(!cd.remove_range_and_shift_left(6..8));
+        assert_eq!(cd.get(5), Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn shift_after_last_block_unchanged() {
+        let mut cd: ColumnData<SameValue<bool>> = ColumnData::new();
+        cd.set_range(1..4, true);
+        assert!(!cd.insert_and_shift_right(4));
+        assert!(!cd.remove_and_shift_left(4));
+        assert_eq!(cd.get(3), Some(true));
+        assert_eq!(cd.get(4), None);
+    }
+
     #[test]
     #[parallel]
     fn has_format_in_row() {
@@ -854,4 +975,29 @@ mod test {
         cd.set(1, Some(true));
         assert!(!cd.is_empty());
     }
+
+    #[test]
+    #[parallel]
+    fn shift_values() {
+        let mut column = Column::new(0);
+        column.values.insert(1, CellValue::Number(1.into()));
+        column.values.insert(3, CellValue::Number(3.into()));
+        column.values.insert(4, CellValue::Number(4.into()));
+
+        assert_eq!(column.shift_values(3, 2), 2);
+        assert_eq!(
+            column.values.keys().copied().collect::<Vec<_>>(),
+            vec![1, 5, 6]
+        );
+
+        assert_eq!(column.shift_values(5, -3), 2);
+        assert_eq!(
+            column.values.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(column.values.get(&3), Some(&CellValue::Number(4.into())));
+
+        assert_eq!(column.shift_values(10, 1), 0);
+        assert_eq!(column.shift_values(1, 0), 0);
+    }
 }
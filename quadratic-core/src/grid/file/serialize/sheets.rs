@@ -40,6 +40,9 @@ pub fn import_sheet(sheet: current::SheetSchema) -> Result<Sheet> {
         rows_resize: import_rows_size(sheet.rows_resize)?,
 
         borders: import_borders(sheet.borders),
+
+        max_rows: None,
+        max_columns: None,
     };
     new_sheet.recalculate_bounds();
     Ok(new_sheet)
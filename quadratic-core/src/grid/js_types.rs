@@ -161,6 +161,43 @@ pub struct CellFormatSummary {
     pub strike_through: Option<bool>,
 }
 
+/// Tri-state for a boolean format across a range of cells.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, TS)]
+pub enum FormatState {
+    All,
+    #[default]
+    None,
+    Mixed,
+}
+
+impl FormatState {
+    /// Returns the state given the number of cells with the format set and
+    /// the total number of cells.
+    pub fn from_count(count: usize, total: usize) -> Self {
+        if count == 0 {
+            FormatState::None
+        } else if count == total {
+            FormatState::All
+        } else {
+            FormatState::Mixed
+        }
+    }
+}
+
+/// Summary of the formats within a range of cells (used by the formatting
+/// toolbar). Colors are only set if they are the same for every cell.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatSummary {
+    pub bold: FormatState,
+    pub italic: FormatState,
+    pub underline: FormatState,
+    pub strike_through: FormatState,
+
+    pub text_color: Option<String>,
+    pub fill_color: Option<String>,
+}
+
 #[derive(Serialize, PartialEq, Debug)]
 #[cfg_attr(feature = "js", derive(ts_rs::TS))]
 pub struct JsReturnInfo {
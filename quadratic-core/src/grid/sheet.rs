@@ -2,7 +2,7 @@ use std::collections::{btree_map, BTreeMap, HashSet};
 use std::str::FromStr;
 
 use bigdecimal::{BigDecimal, RoundingMode};
-use borders::Borders;
+use borders::{BorderStyleCell, BorderStyleTimestamp, Borders};
 use indexmap::IndexMap;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -90,6 +90,14 @@ pub struct Sheet {
     pub(super) rows_resize: ResizeMap,
 
     pub borders: Borders,
+
+    // optional limits on the rows and columns that insert_row and
+    // insert_column may move content to (eg, to guard against malformed
+    // imports); these are not saved with the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rows: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_columns: Option<i64>,
 }
 impl Sheet {
     /// Constructs a new empty sheet.
@@ -118,6 +126,9 @@ impl Sheet {
             rows_resize: ResizeMap::default(),
 
             borders: Borders::default(),
+
+            max_rows: None,
+            max_columns: None,
         }
     }
 
@@ -203,6 +214,28 @@ impl Sheet {
         self.columns.iter()
     }
 
+    /// Iterates over the populated cells in a row (ignoring code_runs),
+    /// yielding (x, value) in ascending order of x.
+    pub fn row_cells_iter(&self, row: i64) -> impl Iterator<Item = (i64, &CellValue)> {
+        self.columns
+            .iter()
+            .filter_map(move |(x, column)| column.values.get(&row).map(|value| (*x, value)))
+    }
+
+    /// Returns the border that is rendered for a cell, combining the cell,
+    /// column, row, and sheet-wide borders. A cell-level border wins over
+    /// column and row borders, and the newer of a column and row border wins
+    /// over the other. Cleared sides are returned as None.
+    pub fn effective_border(&self, x: i64, y: i64) -> BorderStyleCell {
+        let cell = self.borders.effective(x, y);
+        BorderStyleCell {
+            top: BorderStyleTimestamp::remove_clear(cell.top),
+            bottom: BorderStyleTimestamp::remove_clear(cell.bottom),
+            left: BorderStyleTimestamp::remove_clear(cell.left),
+            right: BorderStyleTimestamp::remove_clear(cell.right),
+        }
+    }
+
     /// Returns the cell_value at a Pos using both column.values and code_runs (i.e., what would be returned if code asked
     /// for it).
     pub fn display_value(&self, pos: Pos) -> Option<CellValue> {
@@ -506,7 +539,9 @@ mod test {
     use crate::controller::GridController;
     use crate::grid::formats::format_update::FormatUpdate;
     use crate::grid::formats::Formats;
-    use crate::grid::{Bold, CodeCellLanguage, Italic, NumericFormat};
+    use crate::grid::{
+        Bold, BorderSelection, BorderStyle, CellBorderLine, CodeCellLanguage, Italic, NumericFormat,
+    };
     use crate::selection::Selection;
     use crate::test_util::print_table;
     use crate::{CodeCellValue, SheetPos};
@@ -1110,4 +1145,96 @@ mod test {
             })
         );
     }
+
+    #[test]
+    #[parallel]
+    fn row_cells_iter() {
+        let mut sheet = Sheet::test();
+        sheet.set_cell_value(Pos { x: 10, y: 1 }, "c");
+        sheet.set_cell_value(Pos { x: -5, y: 1 }, "a");
+        sheet.set_cell_value(Pos { x: 2, y: 1 }, "b");
+        sheet.set_cell_value(Pos { x: 2, y: 2 }, "other row");
+
+        let cells = sheet.row_cells_iter(1).collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            vec![
+                (-5, &CellValue::Text("a".to_string())),
+                (2, &CellValue::Text("b".to_string())),
+                (10, &CellValue::Text("c".to_string())),
+            ]
+        );
+        assert_eq!(sheet.row_cells_iter(3).count(), 0);
+    }
+
+    #[test]
+    #[parallel]
+    fn effective_border() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::columns(&[1], sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle {
+                line: CellBorderLine::Line1,
+                ..Default::default()
+            }),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::pos(1, 2, sheet_id),
+            BorderSelection::Top,
+            Some(BorderStyle {
+                line: CellBorderLine::Line3,
+                ..Default::default()
+            }),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+
+        // the cell-level border wins over the column border
+        let border = sheet.effective_border(1, 2);
+        assert_eq!(border.top.unwrap().line, CellBorderLine::Line3);
+        assert_eq!(border.bottom.unwrap().line, CellBorderLine::Line1);
+
+        // other cells in the column use the column border
+        let border = sheet.effective_border(1, 3);
+        assert_eq!(border.top.unwrap().line, CellBorderLine::Line1);
+
+        // cells outside the column have no border
+        assert_eq!(sheet.effective_border(2, 2), BorderStyleCell::default());
+    }
+
+    #[test]
+    #[parallel]
+    fn effective_border_column_after_cell() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::pos(1, 2, sheet_id),
+            BorderSelection::Top,
+            Some(BorderStyle {
+                line: CellBorderLine::Line3,
+                ..Default::default()
+            }),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::columns(&[1], sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle {
+                line: CellBorderLine::Dashed,
+                ..Default::default()
+            }),
+            None,
+        );
+
+        // setting the column border replaces the cell's border
+        let sheet = gc.sheet(sheet_id);
+        let border = sheet.effective_border(1, 2);
+        assert_eq!(border.top.unwrap().line, CellBorderLine::Dashed);
+    }
 }
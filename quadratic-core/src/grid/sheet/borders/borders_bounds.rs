@@ -1,6 +1,11 @@
-use crate::Rect;
+use std::{collections::HashMap, ops::Range};
 
-use super::Borders;
+use crate::{
+    grid::{block::SameValue, ColumnData},
+    Rect,
+};
+
+use super::{BorderStyleTimestamp, Borders, CellBorderLine};
 
 impl Borders {
     /// Finds the rect that contains borders that would be overwritten by the column.
@@ -201,6 +206,37 @@ impl Borders {
             _ => None,
         }
     }
+
+    /// Returns the number of cell-level bordered sides within the rect.
+    ///
+    /// Each cell stores its own sides, so a line shared by two cells may be
+    /// counted twice (BorderSelection::All on a 2x2 rect sets 16 sides).
+    /// Cleared sides and sheet, column, and row borders are not counted.
+    pub fn count_in_rect(&self, rect: Rect) -> usize {
+        fn count(
+            sides: &HashMap<i64, ColumnData<SameValue<BorderStyleTimestamp>>>,
+            keys: Range<i64>,
+            range: Range<i64>,
+        ) -> usize {
+            sides
+                .iter()
+                .filter(|(key, _)| keys.contains(key))
+                .map(|(_, data)| {
+                    data.blocks_covering_range(range.clone())
+                        .filter(|block| block.content.value.line != CellBorderLine::Clear)
+                        .map(|block| {
+                            (block.end().min(range.end) - block.start().max(range.start)) as usize
+                        })
+                        .sum::<usize>()
+                })
+                .sum()
+        }
+
+        count(&self.left, rect.x_range(), rect.y_range())
+            + count(&self.right, rect.x_range(), rect.y_range())
+            + count(&self.top, rect.y_range(), rect.x_range())
+            + count(&self.bottom, rect.y_range(), rect.x_range())
+    }
 }
 
 #[cfg(test)]
@@ -584,4 +620,35 @@ mod tests {
         );
         assert_eq!(sheet.borders.bounds_row(6, true, true), None);
     }
+
+    #[test]
+    #[parallel]
+    fn count_in_rect() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 2, 2, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(sheet.borders.count_in_rect(Rect::new(1, 1, 2, 2)), 16);
+        assert_eq!(sheet.borders.count_in_rect(Rect::new(2, 2, 3, 3)), 4);
+        assert_eq!(sheet.borders.count_in_rect(Rect::new(5, 5, 10, 10)), 0);
+    }
+
+    #[test]
+    #[parallel]
+    fn count_in_rect_clear() {
+        let mut borders = Borders::default();
+        let clear = BorderStyle {
+            line: CellBorderLine::Clear,
+            ..Default::default()
+        };
+        borders.set(1, 1, Some(clear), Some(BorderStyle::default()), None, None);
+        assert_eq!(borders.count_in_rect(Rect::new(1, 1, 1, 1)), 1);
+    }
 }
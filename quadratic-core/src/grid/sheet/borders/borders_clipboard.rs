@@ -1,10 +1,26 @@
-use crate::selection::Selection;
+use crate::{grid::SheetId, selection::Selection, Pos, Rect};
 
-use super::{BorderStyleCell, BorderStyleCellUpdates, Borders};
+use super::{BorderStyleCell, BorderStyleCellUpdate, BorderStyleCellUpdates, Borders};
+
+/// What an entry of the clipboard borders (see Borders::to_clipboard)
+/// applies to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BordersClipboardEntry {
+    All,
+    Column(i64),
+    Row(i64),
+    Cell(Pos),
+}
 
 impl Borders {
     /// Prepares borders within the selection for copying to the clipboard.
     ///
+    /// Entries are ordered the same way `set_borders_selection` reads them
+    /// (columns, rows, then each rect's cells row by row), so paste can
+    /// reconstruct every rect. A cell that was already covered by an earlier
+    /// rect gets an empty update so its borders are only copied once. Use
+    /// clipboard_entry to find what the entry at an index applies to.
+    ///
     /// Returns `None` if there are no borders to copy.
     pub fn to_clipboard(&self, selection: &Selection) -> Option<BorderStyleCellUpdates> {
         let mut updates = BorderStyleCellUpdates::default();
@@ -31,10 +47,12 @@ impl Borders {
             }
         }
         if let Some(rects) = selection.rects.as_ref() {
-            for rect in rects {
-                for row in rect.min.y..=rect.max.y {
-                    for col in rect.min.x..=rect.max.x {
-                        updates.push(self.update_override(col, row));
+            for (i, rect) in rects.iter().enumerate() {
+                for pos in rect.iter() {
+                    if rects[..i].iter().any(|r| r.contains(pos)) {
+                        updates.push(BorderStyleCellUpdate::default());
+                    } else {
+                        updates.push(self.update_override(pos.x, pos.y));
                     }
                 }
             }
@@ -45,6 +63,114 @@ impl Borders {
             Some(updates)
         }
     }
+
+    /// Prepares the borders of whole rows for copying to the clipboard. This
+    /// includes each row's row-level border and the cell borders within
+    /// x_bounds (inclusive).
+    ///
+    /// The selection is relative to the first row and x_bounds.0 (like
+    /// Clipboard::borders), so it can be translated to where it is pasted.
+    ///
+    /// Returns `None` if there are no rows.
+    pub fn to_clipboard_rows(
+        &self,
+        sheet_id: SheetId,
+        rows: &[i64],
+        x_bounds: (i64, i64),
+    ) -> Option<(Selection, BorderStyleCellUpdates)> {
+        let first = *rows.iter().min()?;
+        let selection = Selection {
+            sheet_id,
+            x: x_bounds.0,
+            y: first,
+            rows: Some(rows.to_vec()),
+            rects: Some(
+                rows.iter()
+                    .map(|&row| Rect::new(x_bounds.0, row, x_bounds.1, row))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let borders = self.to_clipboard(&selection)?;
+        Some((selection.translate(-x_bounds.0, -first), borders))
+    }
+
+    /// Pastes the clipboard borders of a single rect (see to_clipboard) over
+    /// target, repeating the pattern to fill it. Only the size of source (the
+    /// copied rect) is used.
+    ///
+    /// The pattern starts at target's top-left cell. When target is not an
+    /// exact multiple of source, the last tiles are cut off at target's right
+    /// and bottom edges; a target smaller than source gets only the top-left
+    /// part of the pattern.
+    ///
+    /// Returns true if any border changed.
+    pub fn paste_tiled(
+        &mut self,
+        target: Rect,
+        source: Rect,
+        clipboard: &BorderStyleCellUpdates,
+    ) -> bool {
+        let updates = clipboard.iter_values().collect::<Vec<_>>();
+        if updates.len() != source.count() {
+            return false;
+        }
+        let width = source.width() as i64;
+        let height = source.height() as i64;
+        let mut changed = false;
+        for pos in target.iter() {
+            let dx = (pos.x - target.min.x) % width;
+            let dy = (pos.y - target.min.y) % height;
+            let update = updates[(dy * width + dx) as usize];
+            let before = self.get(pos.x, pos.y);
+            self.apply_update(pos.x, pos.y, *update);
+            if self.get(pos.x, pos.y) != before {
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Returns what the entry at `index` of to_clipboard's result for the
+    /// selection applies to. The entries are, in order:
+    ///
+    /// - the whole sheet, if selection.all
+    /// - one entry per selected column, in selection order
+    /// - one entry per selected row, in selection order
+    /// - one entry per cell of each rect, row by row (left to right, then top
+    ///   to bottom), including cells already covered by an earlier rect
+    ///
+    /// Returns None if the index is past the last entry.
+    pub fn clipboard_entry(selection: &Selection, index: usize) -> Option<BordersClipboardEntry> {
+        let mut index = index;
+        if selection.all {
+            if index == 0 {
+                return Some(BordersClipboardEntry::All);
+            }
+            index -= 1;
+        }
+        let columns = selection.columns.as_deref().unwrap_or_default();
+        if let Some(column) = columns.get(index) {
+            return Some(BordersClipboardEntry::Column(*column));
+        }
+        index -= columns.len();
+        let rows = selection.rows.as_deref().unwrap_or_default();
+        if let Some(row) = rows.get(index) {
+            return Some(BordersClipboardEntry::Row(*row));
+        }
+        index -= rows.len();
+        for rect in selection.rects.as_deref().unwrap_or_default() {
+            if index < rect.count() {
+                let width = rect.width() as usize;
+                return Some(BordersClipboardEntry::Cell(Pos {
+                    x: rect.min.x + (index % width) as i64,
+                    y: rect.min.y + (index / width) as i64,
+                }));
+            }
+            index -= rect.count();
+        }
+        None
+    }
 }
 
 #[cfg(test)]
@@ -53,9 +179,10 @@ mod tests {
 
     use super::*;
     use crate::{
+        color::Rgba,
         controller::GridController,
-        grid::{BorderSelection, BorderStyle, CellBorderLine},
-        SheetRect,
+        grid::{BorderSelection, BorderStyle, CellBorderLine, SheetId},
+        Rect, SheetRect,
     };
 
     #[test]
@@ -111,4 +238,307 @@ mod tests {
             CellBorderLine::default()
         );
     }
+
+    #[test]
+    #[parallel]
+    fn to_clipboard_multiple_rects() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 2, 2, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(5, 5, 6, 6, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let selection = Selection {
+            sheet_id,
+            rects: Some(vec![Rect::new(1, 1, 2, 2), Rect::new(5, 5, 6, 6)]),
+            ..Default::default()
+        };
+        let sheet = gc.sheet(sheet_id);
+        let copy = sheet.borders.to_clipboard(&selection).unwrap();
+
+        assert_eq!(copy.size(), 8);
+        for i in 0..8 {
+            let entry = copy.get_at(i).unwrap();
+            assert_eq!(entry.top.unwrap().unwrap().line, CellBorderLine::default());
+            assert_eq!(entry.left.unwrap().unwrap().line, CellBorderLine::default());
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn to_clipboard_overlapping_rects() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 3, 3, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let selection = Selection {
+            sheet_id,
+            rects: Some(vec![Rect::new(1, 1, 2, 2), Rect::new(2, 2, 3, 3)]),
+            ..Default::default()
+        };
+        let sheet = gc.sheet(sheet_id);
+        let copy = sheet.borders.to_clipboard(&selection).unwrap();
+
+        // indices still line up with the selection's cells
+        assert_eq!(copy.size(), 8);
+
+        // (2, 2) was already copied by the first rect
+        assert_eq!(copy.get_at(4), Some(&BorderStyleCellUpdate::default()));
+        assert!(copy.get_at(3).unwrap().top.is_some());
+        assert!(copy.get_at(5).unwrap().top.is_some());
+    }
+
+    #[test]
+    #[parallel]
+    fn clipboard_entry_rect() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let red = BorderStyle {
+            color: Rgba::new(255, 0, 0, 255),
+            line: CellBorderLine::Line2,
+        };
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(3, 2, 3, 2, sheet_id)),
+            BorderSelection::Top,
+            Some(red),
+            None,
+        );
+
+        let selection = Selection::sheet_rect(SheetRect::new(1, 1, 4, 4, sheet_id));
+        let copy = gc.sheet(sheet_id).borders.to_clipboard(&selection).unwrap();
+        assert_eq!(copy.size(), 16);
+
+        // row by row: index 6 is the third cell of the second row
+        assert_eq!(
+            Borders::clipboard_entry(&selection, 0),
+            Some(BordersClipboardEntry::Cell(Pos { x: 1, y: 1 }))
+        );
+        assert_eq!(
+            Borders::clipboard_entry(&selection, 6),
+            Some(BordersClipboardEntry::Cell(Pos { x: 3, y: 2 }))
+        );
+        assert_eq!(
+            Borders::clipboard_entry(&selection, 15),
+            Some(BordersClipboardEntry::Cell(Pos { x: 4, y: 4 }))
+        );
+        assert_eq!(Borders::clipboard_entry(&selection, 16), None);
+
+        for i in 0..copy.size() {
+            let Some(BordersClipboardEntry::Cell(pos)) = Borders::clipboard_entry(&selection, i)
+            else {
+                panic!("Expected a cell entry at {i}");
+            };
+            let top = copy.get_at(i).unwrap().top.unwrap();
+            if pos == (Pos { x: 3, y: 2 }) {
+                assert_eq!(top.unwrap().line, CellBorderLine::Line2);
+            } else {
+                assert_eq!(top, None);
+            }
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn clipboard_entry_column() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_borders_selection(
+            Selection::columns(&[3], sheet_id),
+            BorderSelection::Left,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let selection = Selection::columns(&[2, 3], sheet_id);
+        let copy = gc.sheet(sheet_id).borders.to_clipboard(&selection).unwrap();
+        assert_eq!(copy.size(), 2);
+        assert_eq!(
+            Borders::clipboard_entry(&selection, 0),
+            Some(BordersClipboardEntry::Column(2))
+        );
+        assert_eq!(
+            Borders::clipboard_entry(&selection, 1),
+            Some(BordersClipboardEntry::Column(3))
+        );
+        assert_eq!(Borders::clipboard_entry(&selection, 2), None);
+        assert_eq!(
+            copy.get_at(0).unwrap().left.unwrap().unwrap().line,
+            CellBorderLine::Clear
+        );
+        assert_eq!(
+            copy.get_at(1).unwrap().left.unwrap().unwrap().line,
+            CellBorderLine::default()
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn clipboard_entry_mixed() {
+        let sheet_id = SheetId::test();
+        let selection = Selection {
+            sheet_id,
+            columns: Some(vec![5]),
+            rows: Some(vec![7, 8]),
+            rects: Some(vec![Rect::new(1, 1, 2, 2), Rect::new(4, 4, 4, 5)]),
+            ..Default::default()
+        };
+        let entries = (0..)
+            .map_while(|i| Borders::clipboard_entry(&selection, i))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                BordersClipboardEntry::Column(5),
+                BordersClipboardEntry::Row(7),
+                BordersClipboardEntry::Row(8),
+                BordersClipboardEntry::Cell(Pos { x: 1, y: 1 }),
+                BordersClipboardEntry::Cell(Pos { x: 2, y: 1 }),
+                BordersClipboardEntry::Cell(Pos { x: 1, y: 2 }),
+                BordersClipboardEntry::Cell(Pos { x: 2, y: 2 }),
+                BordersClipboardEntry::Cell(Pos { x: 4, y: 4 }),
+                BordersClipboardEntry::Cell(Pos { x: 4, y: 5 }),
+            ]
+        );
+        assert_eq!(
+            Borders::default().to_clipboard(&selection).unwrap().size(),
+            entries.len()
+        );
+
+        let all = Selection::all(sheet_id);
+        assert_eq!(
+            Borders::clipboard_entry(&all, 0),
+            Some(BordersClipboardEntry::All)
+        );
+        assert_eq!(Borders::clipboard_entry(&all, 1), None);
+    }
+
+    #[test]
+    #[parallel]
+    fn to_clipboard_rows() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 3, 2, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        let red = BorderStyle {
+            color: Rgba::new(255, 0, 0, 255),
+            line: CellBorderLine::Line2,
+        };
+        gc.set_borders_selection(
+            Selection::rows(&[2], sheet_id),
+            BorderSelection::Bottom,
+            Some(red),
+            None,
+        );
+
+        let sheet = gc.sheet_mut(sheet_id);
+        let (selection, borders) = sheet
+            .borders
+            .to_clipboard_rows(sheet_id, &[1, 2], (1, 3))
+            .unwrap();
+        assert_eq!(selection.rows, Some(vec![0, 1]));
+        assert_eq!(
+            selection.rects,
+            Some(vec![Rect::new(0, 0, 2, 0), Rect::new(0, 1, 2, 1)])
+        );
+        assert_eq!(borders.size(), 2 + 6);
+
+        // paste into rows 5 and 6
+        sheet
+            .borders
+            .set_borders(&selection.translate(1, 5), &borders);
+
+        for x in 1..=3 {
+            for y in 5..=6 {
+                let border = sheet.borders.get(x, y);
+                assert_eq!(border.top.unwrap().line, CellBorderLine::default());
+                assert_eq!(border.left.unwrap().line, CellBorderLine::default());
+                assert_eq!(border.right.unwrap().line, CellBorderLine::default());
+            }
+            assert_eq!(
+                sheet.borders.get(x, 5).bottom.unwrap().line,
+                CellBorderLine::default()
+            );
+        }
+        let row = sheet.borders.rows.get(&6).unwrap();
+        assert_eq!(row.bottom.unwrap().color, red.color);
+        assert_eq!(row.bottom.unwrap().line, CellBorderLine::Line2);
+
+        assert!(sheet
+            .borders
+            .to_clipboard_rows(sheet_id, &[], (1, 3))
+            .is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn paste_tiled() {
+        let mut borders = Borders::default();
+        borders.set(1, 1, Some(BorderStyle::default()), None, None, None);
+        let source = Rect::new(1, 1, 1, 1);
+        let clipboard = borders
+            .to_clipboard(&Selection::rect(source, SheetId::test()))
+            .unwrap();
+
+        let mut pasted = Borders::default();
+        let target = Rect::new(5, 5, 7, 7);
+        assert!(pasted.paste_tiled(target, source, &clipboard));
+        for pos in target.iter() {
+            let border = pasted.get(pos.x, pos.y);
+            assert!(border.top.is_some());
+            assert!(border.bottom.is_none());
+            assert!(border.left.is_none());
+            assert!(border.right.is_none());
+        }
+        assert!(pasted.get(8, 5).top.is_none());
+        assert!(pasted.get(5, 8).top.is_none());
+
+        // pasting the same pattern again changes nothing
+        assert!(!pasted.paste_tiled(target, source, &clipboard));
+    }
+
+    #[test]
+    #[parallel]
+    fn paste_tiled_partial() {
+        let mut borders = Borders::default();
+        borders.set(1, 1, Some(BorderStyle::default()), None, None, None);
+        borders.set(2, 1, None, None, Some(BorderStyle::default()), None);
+        let source = Rect::new(1, 1, 2, 1);
+        let clipboard = borders
+            .to_clipboard(&Selection::rect(source, SheetId::test()))
+            .unwrap();
+
+        // a 3-wide target cuts the second tile off after its first column
+        let mut pasted = Borders::default();
+        assert!(pasted.paste_tiled(Rect::new(1, 1, 3, 2), source, &clipboard));
+        for y in 1..=2 {
+            assert!(pasted.get(1, y).top.is_some());
+            assert!(pasted.get(2, y).left.is_some());
+            assert!(pasted.get(3, y).top.is_some());
+            assert!(pasted.get(3, y).left.is_none());
+            assert!(pasted.get(4, y).left.is_none());
+        }
+
+        // the clipboard must match the size of source
+        assert!(!pasted.paste_tiled(Rect::new(1, 1, 3, 3), Rect::new(1, 1, 3, 3), &clipboard));
+    }
 }
@@ -1,9 +1,13 @@
 //! Inserts and removes columns and rows for borders. Also provides fn to get
 //! undo operations for these changes.
 
+use std::collections::HashMap;
+
 use itertools::Itertools;
 
-use crate::{controller::operations::operation::Operation, grid::SheetId, selection::Selection};
+use crate::{
+    controller::operations::operation::Operation, grid::SheetId, selection::Selection, Rect,
+};
 
 use super::{BorderStyleCellUpdates, Borders};
 
@@ -25,8 +29,8 @@ impl Borders {
         // need to work backwards because we're shifting to the right
         for &x in to_increment.iter().rev() {
             if let Some(data) = self.left.remove(&x) {
+                changed |= !data.is_empty();
                 self.left.insert(x + 1, data);
-                changed = true;
             }
         }
 
@@ -41,8 +45,8 @@ impl Borders {
         // need to work backwards because we're shifting to the right
         for &x in to_increment.iter().rev() {
             if let Some(data) = self.right.remove(&x) {
+                changed |= !data.is_empty();
                 self.right.insert(x + 1, data);
-                changed = true;
             }
         }
 
@@ -65,6 +69,8 @@ impl Borders {
     }
 
     /// Inserts a new row at the given coordinate.
+    ///
+    /// Returns true if borders were changed.
     pub fn insert_row(&mut self, row: i64) -> bool {
         let mut changed = false;
 
@@ -79,8 +85,8 @@ impl Borders {
         // increment all rows (backwards because we're shifting down)
         for &y in to_increment.iter().rev() {
             if let Some(data) = self.top.remove(&y) {
+                changed |= !data.is_empty();
                 self.top.insert(y + 1, data);
-                changed = true;
             }
         }
 
@@ -95,8 +101,8 @@ impl Borders {
         // increment all rows (backwards because we're shifting down)
         for &y in to_increment.iter().rev() {
             if let Some(data) = self.bottom.remove(&y) {
+                changed |= !data.is_empty();
                 self.bottom.insert(y + 1, data);
-                changed = true;
             }
         }
 
@@ -118,9 +124,14 @@ impl Borders {
     }
 
     /// Removes a column at the given coordinate.
+    ///
+    /// Returns true if borders were changed.
     pub fn remove_column(&mut self, column: i64) -> bool {
         let mut changed = false;
-        self.left.remove(&column);
+
+        if let Some(data) = self.left.remove(&column) {
+            changed |= !data.is_empty();
+        }
 
         // collect all the columns that need to be decremented
         let to_decrement: Vec<i64> = self
@@ -133,14 +144,13 @@ impl Borders {
         // decrement all columns (forwards because we're shifting left)
         for &x in to_decrement.iter() {
             if let Some(data) = self.left.remove(&x) {
+                changed |= !data.is_empty();
                 self.left.insert(x - 1, data);
-                changed = true;
             }
         }
 
-        if self.right.contains_key(&column) {
-            self.right.remove(&column);
-            changed = true;
+        if let Some(data) = self.right.remove(&column) {
+            changed |= !data.is_empty();
         }
 
         // collect all the columns that need to be decremented
@@ -154,8 +164,8 @@ impl Borders {
         // decrement all columns (forwards because we're shifting left)
         for &x in to_decrement.iter() {
             if let Some(data) = self.right.remove(&x) {
+                changed |= !data.is_empty();
                 self.right.insert(x - 1, data);
-                changed = true;
             }
         }
 
@@ -176,13 +186,41 @@ impl Borders {
         changed
     }
 
+    /// Swaps the borders of two rows. Returns true if any borders changed.
+    pub fn swap_rows(&mut self, a: i64, b: i64) -> bool {
+        fn swap_entries<T>(map: &mut HashMap<i64, T>, a: i64, b: i64) -> bool {
+            let value_a = map.remove(&a);
+            let value_b = map.remove(&b);
+            let changed = value_a.is_some() || value_b.is_some();
+            if let Some(value) = value_a {
+                map.insert(b, value);
+            }
+            if let Some(value) = value_b {
+                map.insert(a, value);
+            }
+            changed
+        }
+
+        let mut changed = swap_entries(&mut self.rows, a, b);
+        changed |= swap_entries(&mut self.top, a, b);
+        changed |= swap_entries(&mut self.bottom, a, b);
+        self.left
+            .values_mut()
+            .for_each(|data| changed |= data.swap(a, b));
+        self.right
+            .values_mut()
+            .for_each(|data| changed |= data.swap(a, b));
+        changed
+    }
+
     /// Removes a row at the given coordinate.
+    ///
+    /// Returns true if borders were changed.
     pub fn remove_row(&mut self, row: i64) -> bool {
         let mut changed = false;
 
-        if self.top.contains_key(&row) {
-            self.top.remove(&row);
-            changed = true;
+        if let Some(data) = self.top.remove(&row) {
+            changed |= !data.is_empty();
         }
 
         // collect all the rows that need to be decremented
@@ -196,14 +234,13 @@ impl Borders {
         // decrement all rows (forwards because we're shifting up)
         for &y in to_decrement.iter() {
             if let Some(data) = self.top.remove(&y) {
+                changed |= !data.is_empty();
                 self.top.insert(y - 1, data);
-                changed = true;
             }
         }
 
-        if self.bottom.contains_key(&row) {
-            self.bottom.remove(&row);
-            changed = true;
+        if let Some(data) = self.bottom.remove(&row) {
+            changed |= !data.is_empty();
         }
 
         // collect all the rows that need to be decremented
@@ -217,8 +254,8 @@ impl Borders {
         // decrement all rows (forwards because we're shifting up)
         for &y in to_decrement.iter() {
             if let Some(data) = self.bottom.remove(&y) {
+                changed |= !data.is_empty();
                 self.bottom.insert(y - 1, data);
-                changed = true;
             }
         }
 
@@ -239,6 +276,48 @@ impl Borders {
         changed
     }
 
+    /// Removes the rows in the inclusive range (start, end) and shifts the
+    /// rows below it up, in a single pass.
+    ///
+    /// Returns true if borders were changed.
+    pub fn remove_row_range(&mut self, start: i64, end: i64) -> bool {
+        if start > end {
+            return false;
+        }
+        let count = end - start + 1;
+        let mut changed = false;
+
+        for data in [&mut self.top, &mut self.bottom] {
+            // collect all the rows that need to be removed or decremented
+            let rows: Vec<i64> = data
+                .keys()
+                .filter(|y| **y >= start)
+                .copied()
+                .sorted()
+                .collect();
+
+            // forwards because we're shifting up
+            for y in rows {
+                if let Some(row_data) = data.remove(&y) {
+                    changed |= !row_data.is_empty();
+                    if y > end {
+                        data.insert(y - count, row_data);
+                    }
+                }
+            }
+        }
+
+        // removes the rows in left and right
+        self.left.values_mut().for_each(|data| {
+            changed |= data.remove_range_and_shift_left(start..end + 1);
+        });
+        self.right.values_mut().for_each(|data| {
+            changed |= data.remove_range_and_shift_left(start..end + 1);
+        });
+
+        changed
+    }
+
     /// Gets an operation to recreate the column's borders.
     pub fn get_column_ops(&self, sheet_id: SheetId, column: i64) -> Vec<Operation> {
         let mut borders = BorderStyleCellUpdates::default();
@@ -286,6 +365,110 @@ impl Borders {
             vec![Operation::SetBordersSelection { selection, borders }]
         }
     }
+
+    /// Gets an operation to recreate the borders of many columns. Adjacent
+    /// columns whose borders overlap or touch share a single rect, so this is
+    /// smaller than calling get_column_ops for each column.
+    pub fn get_columns_ops(&self, sheet_id: SheetId, columns: &[i64]) -> Vec<Operation> {
+        let columns: Vec<i64> = columns.iter().copied().sorted().dedup().collect();
+        let mut borders = BorderStyleCellUpdates::default();
+        let mut selection = Selection::new(sheet_id);
+
+        let bordered: Vec<i64> = columns
+            .iter()
+            .filter(|column| self.columns.contains_key(column))
+            .copied()
+            .collect();
+        if !bordered.is_empty() {
+            for column in bordered.iter() {
+                borders.push(self.columns[column].override_border(false));
+            }
+            selection.columns = Some(bordered);
+        }
+
+        // merge the bounds of adjacent columns whose rows overlap or touch
+        let mut rects: Vec<Rect> = Vec::new();
+        for &column in columns.iter() {
+            if let Some(bounds) = self.bounds_column(column, false, false) {
+                match rects.last_mut() {
+                    Some(rect)
+                        if rect.max.x + 1 == column
+                            && bounds.min.y <= rect.max.y + 1
+                            && rect.min.y <= bounds.max.y + 1 =>
+                    {
+                        *rect = rect.union(&bounds)
+                    }
+                    _ => rects.push(bounds),
+                }
+            }
+        }
+        if !rects.is_empty() {
+            for rect in rects.iter() {
+                for pos in rect.iter() {
+                    borders.push(self.get(pos.x, pos.y).override_border(false));
+                }
+            }
+            selection.rects = Some(rects);
+        }
+
+        if selection.is_empty() {
+            vec![]
+        } else {
+            vec![Operation::SetBordersSelection { selection, borders }]
+        }
+    }
+
+    /// Gets an operation to recreate the borders of many rows. Adjacent rows
+    /// whose borders overlap or touch share a single rect, so this is smaller
+    /// than calling get_row_ops for each row.
+    pub fn get_rows_ops(&self, sheet_id: SheetId, rows: &[i64]) -> Vec<Operation> {
+        let rows: Vec<i64> = rows.iter().copied().sorted().dedup().collect();
+        let mut borders = BorderStyleCellUpdates::default();
+        let mut selection = Selection::new(sheet_id);
+
+        let bordered: Vec<i64> = rows
+            .iter()
+            .filter(|row| self.rows.contains_key(row))
+            .copied()
+            .collect();
+        if !bordered.is_empty() {
+            for row in bordered.iter() {
+                borders.push(self.rows[row].override_border(false));
+            }
+            selection.rows = Some(bordered);
+        }
+
+        // merge the bounds of adjacent rows whose columns overlap or touch
+        let mut rects: Vec<Rect> = Vec::new();
+        for &row in rows.iter() {
+            if let Some(bounds) = self.bounds_row(row, false, false) {
+                match rects.last_mut() {
+                    Some(rect)
+                        if rect.max.y + 1 == row
+                            && bounds.min.x <= rect.max.x + 1
+                            && rect.min.x <= bounds.max.x + 1 =>
+                    {
+                        *rect = rect.union(&bounds)
+                    }
+                    _ => rects.push(bounds),
+                }
+            }
+        }
+        if !rects.is_empty() {
+            for rect in rects.iter() {
+                for pos in rect.iter() {
+                    borders.push(self.get(pos.x, pos.y).override_border(false));
+                }
+            }
+            selection.rects = Some(rects);
+        }
+
+        if selection.is_empty() {
+            vec![]
+        } else {
+            vec![Operation::SetBordersSelection { selection, borders }]
+        }
+    }
 }
 
 #[cfg(test)]
@@ -296,8 +479,10 @@ mod tests {
         color::Rgba,
         controller::GridController,
         grid::{
-            sheet::borders::BorderStyleCellUpdate, BorderSelection, BorderStyle, CellBorderLine,
-            CodeCellLanguage,
+            sheet::borders::{
+                borders_clipboard::BordersClipboardEntry, BorderStyleCell, BorderStyleCellUpdate,
+            },
+            BorderSelection, BorderStyle, CellBorderLine, CodeCellLanguage,
         },
         selection::Selection,
         CellValue, Pos, Rect, SheetPos, SheetRect,
@@ -321,6 +506,34 @@ mod tests {
         assert_eq!(borders, Borders::default());
     }
 
+    #[test]
+    #[parallel]
+    fn col_row_changes_outside_borders() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 3, 3, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet_mut(sheet_id);
+        let original = sheet.borders.clone();
+
+        assert!(!sheet.borders.insert_row(10));
+        assert!(!sheet.borders.insert_column(10));
+        assert!(!sheet.borders.remove_row(10));
+        assert!(!sheet.borders.remove_column(10));
+        assert_eq!(sheet.borders, original);
+
+        assert!(sheet.borders.insert_row(2));
+        assert!(sheet.borders.remove_row(2));
+        assert!(sheet.borders.insert_column(2));
+        assert!(sheet.borders.remove_column(2));
+    }
+
     #[test]
     #[parallel]
     fn insert_column_start() {
@@ -439,6 +652,46 @@ mod tests {
         assert_eq!(sheet.borders, sheet_expected.borders);
     }
 
+    #[test]
+    #[parallel]
+    fn remove_column_min() {
+        let mut borders = Borders::default();
+        let style = Some(BorderStyle::default());
+        borders.set(i64::MIN, 1, style, style, style, style);
+        borders.set(i64::MIN + 1, 1, style, None, style, None);
+
+        assert!(borders.remove_column(i64::MIN));
+        let border = borders.get(i64::MIN, 1);
+        assert!(border.top.is_some());
+        assert!(border.bottom.is_none());
+        assert!(border.left.is_some());
+        assert!(border.right.is_none());
+        assert_eq!(borders.get(i64::MIN + 1, 1), BorderStyleCell::default());
+
+        assert!(borders.remove_column(i64::MIN));
+        assert_eq!(borders.get(i64::MIN, 1), BorderStyleCell::default());
+    }
+
+    #[test]
+    #[parallel]
+    fn remove_row_min() {
+        let mut borders = Borders::default();
+        let style = Some(BorderStyle::default());
+        borders.set(1, i64::MIN, style, style, style, style);
+        borders.set(1, i64::MIN + 1, style, None, style, None);
+
+        assert!(borders.remove_row(i64::MIN));
+        let border = borders.get(1, i64::MIN);
+        assert!(border.top.is_some());
+        assert!(border.bottom.is_none());
+        assert!(border.left.is_some());
+        assert!(border.right.is_none());
+        assert_eq!(borders.get(1, i64::MIN + 1), BorderStyleCell::default());
+
+        assert!(borders.remove_row(i64::MIN));
+        assert_eq!(borders.get(1, i64::MIN), BorderStyleCell::default());
+    }
+
     #[test]
     #[parallel]
     fn remove_column_middle() {
@@ -663,6 +916,38 @@ mod tests {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn remove_row_range() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 10, 10, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet_mut(sheet_id);
+        assert!(sheet.borders.remove_row_range(3, 5));
+        assert!(!sheet.borders.remove_row_range(20, 25));
+
+        let mut gc_expected = GridController::test();
+        let sheet_id = gc_expected.sheet_ids()[0];
+        gc_expected.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 10, 7, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        let sheet_expected = gc_expected.sheet(sheet_id);
+        assert_eq!(
+            sheet.borders.borders_in_sheet(),
+            sheet_expected.borders.borders_in_sheet()
+        );
+    }
+
     #[test]
     #[parallel]
     fn remove_row_end() {
@@ -715,6 +1000,12 @@ mod tests {
             )))
             .unwrap();
 
+        // index 6 is the third cell of the second row of the selection
+        let selection = Selection::sheet_rect(SheetRect::new(0, 0, 3, 3, sheet_id));
+        assert_eq!(
+            Borders::clipboard_entry(&selection, 6),
+            Some(BordersClipboardEntry::Cell(Pos { x: 2, y: 1 }))
+        );
         let entry = clipboard.get_at(6).unwrap();
         assert_eq!(entry.top.unwrap().unwrap().line, CellBorderLine::default());
         assert_eq!(entry.top.unwrap().unwrap().color, Rgba::default());
@@ -763,6 +1054,137 @@ mod tests {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn get_columns_ops() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 10, 10, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        let ops = sheet.borders.get_columns_ops(sheet_id, &[3, 1, 2]);
+        let separate_ops = [1, 2, 3]
+            .iter()
+            .flat_map(|column| sheet.borders.get_column_ops(sheet_id, *column))
+            .collect::<Vec<_>>();
+        assert_eq!(ops.len(), 1);
+        assert!(ops.len() < separate_ops.len());
+
+        // replaying the op reproduces the borders of the columns
+        let mut borders = Borders::default();
+        for op in ops {
+            let Operation::SetBordersSelection {
+                selection,
+                borders: updates,
+            } = op
+            else {
+                panic!("Expected SetBordersSelection");
+            };
+            assert_eq!(selection.rects, Some(vec![Rect::new(1, 1, 3, 10)]));
+            borders.set_borders(&selection, &updates);
+        }
+        for x in 1..=3 {
+            for y in 1..=10 {
+                assert_eq!(borders.get(x, y), sheet.borders.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn get_rows_ops() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 10, 10, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        // rows 2 and 3 are merged; row 5 gets its own rect
+        let sheet = gc.sheet(sheet_id);
+        let ops = sheet.borders.get_rows_ops(sheet_id, &[2, 3, 5]);
+        assert_eq!(ops.len(), 1);
+
+        let mut borders = Borders::default();
+        for op in ops {
+            let Operation::SetBordersSelection {
+                selection,
+                borders: updates,
+            } = op
+            else {
+                panic!("Expected SetBordersSelection");
+            };
+            assert_eq!(
+                selection.rects,
+                Some(vec![Rect::new(1, 2, 10, 3), Rect::new(1, 5, 10, 5)])
+            );
+            borders.set_borders(&selection, &updates);
+        }
+        for y in [2, 3, 5] {
+            for x in 1..=10 {
+                assert_eq!(borders.get(x, y), sheet.borders.get(x, y));
+            }
+        }
+        assert_eq!(borders.get(1, 4), BorderStyleCell::default());
+    }
+
+    #[test]
+    #[parallel]
+    fn get_columns_rows_ops_far_apart() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::pos(1, 1, sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::pos(2, 100, sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::pos(100, 2, sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        // adjacent lines bordered far apart are not merged into one large rect
+        let sheet = gc.sheet(sheet_id);
+        let ops = sheet.borders.get_columns_ops(sheet_id, &[1, 2]);
+        let Operation::SetBordersSelection { selection, borders } = &ops[0] else {
+            panic!("Expected SetBordersSelection");
+        };
+        assert_eq!(
+            selection.rects,
+            Some(vec![Rect::new(1, 1, 1, 1), Rect::new(2, 100, 2, 100)])
+        );
+        assert_eq!(borders.size(), 2);
+
+        let ops = sheet.borders.get_rows_ops(sheet_id, &[1, 2]);
+        let Operation::SetBordersSelection { selection, borders } = &ops[0] else {
+            panic!("Expected SetBordersSelection");
+        };
+        assert_eq!(
+            selection.rects,
+            Some(vec![Rect::new(1, 1, 1, 1), Rect::new(100, 2, 100, 2)])
+        );
+        assert_eq!(borders.size(), 2);
+    }
+
     #[test]
     #[parallel]
     fn get_row_ops() {
@@ -0,0 +1,375 @@
+//! Compact binary encoding of BorderStyleCellUpdates for sending large border
+//! updates over the wire. Operation::SetBordersSelection serializes its
+//! borders in this form, as a base64 string (see updates_serde).
+//!
+//! Layout (all integers are LEB128 varints unless noted):
+//!
+//! - version (u8)
+//! - palette length, followed by each distinct BorderStyleTimestamp as
+//!   red, green, blue, alpha, line (u8 each) and timestamp (u32 LE)
+//! - run count, followed by each run as:
+//!   - run length
+//!   - flags (u8): two bits per side (top, bottom, left, right), where 0 =
+//!     unchanged (None), 1 = remove (Some(None)), 2 = set (Some(Some(style)))
+//!   - a palette index for each side that is set
+
+use anyhow::{bail, Result};
+
+use crate::{color::Rgba, small_timestamp::SmallTimestamp};
+
+use super::{BorderStyleCellUpdate, BorderStyleCellUpdates, BorderStyleTimestamp, CellBorderLine};
+
+const VERSION: u8 = 1;
+
+const FLAG_NONE: u8 = 0;
+const FLAG_REMOVE: u8 = 1;
+const FLAG_SET: u8 = 2;
+
+fn line_to_u8(line: CellBorderLine) -> u8 {
+    match line {
+        CellBorderLine::Line1 => 0,
+        CellBorderLine::Line2 => 1,
+        CellBorderLine::Line3 => 2,
+        CellBorderLine::Dotted => 3,
+        CellBorderLine::Dashed => 4,
+        CellBorderLine::Double => 5,
+        CellBorderLine::Clear => 6,
+    }
+}
+
+fn line_from_u8(line: u8) -> Result<CellBorderLine> {
+    Ok(match line {
+        0 => CellBorderLine::Line1,
+        1 => CellBorderLine::Line2,
+        2 => CellBorderLine::Line3,
+        3 => CellBorderLine::Dotted,
+        4 => CellBorderLine::Dashed,
+        5 => CellBorderLine::Double,
+        6 => CellBorderLine::Clear,
+        _ => bail!("Invalid line style in compact borders"),
+    })
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    index: usize,
+}
+
+impl Reader<'_> {
+    fn u8(&mut self) -> Result<u8> {
+        let Some(byte) = self.bytes.get(self.index) else {
+            bail!("Unexpected end of compact borders");
+        };
+        self.index += 1;
+        Ok(*byte)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut value = [0; 4];
+        for byte in value.iter_mut() {
+            *byte = self.u8()?;
+        }
+        Ok(u32::from_le_bytes(value))
+    }
+
+    fn varint(&mut self) -> Result<usize> {
+        let mut value = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift >= usize::BITS {
+                bail!("Varint overflow in compact borders");
+            }
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+}
+
+fn sides(update: &BorderStyleCellUpdate) -> [Option<Option<BorderStyleTimestamp>>; 4] {
+    [update.top, update.bottom, update.left, update.right]
+}
+
+/// Encodes the updates into the compact binary form.
+pub fn encode_compact(updates: &BorderStyleCellUpdates) -> Vec<u8> {
+    let mut palette: Vec<BorderStyleTimestamp> = Vec::new();
+    let mut runs = Vec::new();
+    let mut run_count = 0;
+
+    for (update, len) in updates.iter_runs() {
+        run_count += 1;
+        write_varint(&mut runs, len);
+
+        let mut flags = 0;
+        let mut indices = Vec::new();
+        for (i, side) in sides(update).into_iter().enumerate() {
+            let flag = match side {
+                None => FLAG_NONE,
+                Some(None) => FLAG_REMOVE,
+                Some(Some(style)) => {
+                    let index = match palette.iter().position(|s| *s == style) {
+                        Some(index) => index,
+                        None => {
+                            palette.push(style);
+                            palette.len() - 1
+                        }
+                    };
+                    indices.push(index);
+                    FLAG_SET
+                }
+            };
+            flags |= flag << (i * 2);
+        }
+        runs.push(flags);
+        indices
+            .into_iter()
+            .for_each(|index| write_varint(&mut runs, index));
+    }
+
+    let mut bytes = vec![VERSION];
+    write_varint(&mut bytes, palette.len());
+    for style in palette {
+        bytes.extend([
+            style.color.red,
+            style.color.green,
+            style.color.blue,
+            style.color.alpha,
+        ]);
+        bytes.push(line_to_u8(style.line));
+        bytes.extend(style.timestamp.value().to_le_bytes());
+    }
+    write_varint(&mut bytes, run_count);
+    bytes.extend(runs);
+    bytes
+}
+
+/// Decodes updates that were encoded with `encode_compact`.
+pub fn decode_compact(bytes: &[u8]) -> Result<BorderStyleCellUpdates> {
+    let mut reader = Reader { bytes, index: 0 };
+
+    let version = reader.u8()?;
+    if version != VERSION {
+        bail!("Unsupported compact borders version {version}");
+    }
+
+    let palette_len = reader.varint()?;
+    let mut palette = Vec::new();
+    for _ in 0..palette_len {
+        let color = Rgba {
+            red: reader.u8()?,
+            green: reader.u8()?,
+            blue: reader.u8()?,
+            alpha: reader.u8()?,
+        };
+        let line = line_from_u8(reader.u8()?)?;
+        let timestamp = SmallTimestamp::new(reader.u32()?);
+        palette.push(BorderStyleTimestamp {
+            color,
+            line,
+            timestamp,
+        });
+    }
+
+    let mut updates = BorderStyleCellUpdates::new();
+    let run_count = reader.varint()?;
+    for _ in 0..run_count {
+        let len = reader.varint()?;
+        let flags = reader.u8()?;
+        let mut sides = [None; 4];
+        for (i, side) in sides.iter_mut().enumerate() {
+            *side = match (flags >> (i * 2)) & 0b11 {
+                FLAG_NONE => None,
+                FLAG_REMOVE => Some(None),
+                FLAG_SET => {
+                    let Some(style) = palette.get(reader.varint()?) else {
+                        bail!("Invalid palette index in compact borders");
+                    };
+                    Some(Some(*style))
+                }
+                _ => bail!("Invalid side flag in compact borders"),
+            };
+        }
+        let [top, bottom, left, right] = sides;
+        updates.push_n(
+            BorderStyleCellUpdate {
+                top,
+                bottom,
+                left,
+                right,
+            },
+            len,
+        );
+    }
+
+    Ok(updates)
+}
+
+/// Serializes BorderStyleCellUpdates in the compact form (as a base64
+/// string). Deserializing also accepts the plain form, which version 1.0
+/// transactions used (see Transaction::decompress_and_deserialize).
+pub(crate) mod updates_serde {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::{decode_compact, encode_compact, BorderStyleCellUpdates};
+
+    pub fn serialize<S: Serializer>(
+        updates: &BorderStyleCellUpdates,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&STANDARD.encode(encode_compact(updates)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<BorderStyleCellUpdates, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Encoded {
+            Plain(BorderStyleCellUpdates),
+            Compact(String),
+        }
+
+        match Encoded::deserialize(d)? {
+            Encoded::Plain(updates) => Ok(updates),
+            Encoded::Compact(encoded) => {
+                let bytes = STANDARD.decode(encoded).map_err(D::Error::custom)?;
+                decode_compact(&bytes).map_err(D::Error::custom)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+    use crate::{
+        controller::{operations::operation::Operation, transaction::Transaction},
+        grid::SheetId,
+        selection::Selection,
+        Rect,
+    };
+
+    #[test]
+    #[parallel]
+    fn compact_round_trip() {
+        let red = BorderStyleTimestamp {
+            color: Rgba::color_from_str("#ff0000").unwrap(),
+            line: CellBorderLine::Dashed,
+            timestamp: SmallTimestamp::new(1234),
+        };
+        let blue = BorderStyleTimestamp {
+            color: Rgba::color_from_str("#0000ff").unwrap(),
+            line: CellBorderLine::Double,
+            timestamp: SmallTimestamp::new(5678),
+        };
+
+        // alternate styles so the run-length encoding can't collapse the region
+        let mut updates = BorderStyleCellUpdates::new();
+        for i in 0..200 {
+            let update = if i % 2 == 0 {
+                BorderStyleCellUpdate {
+                    top: Some(Some(red)),
+                    bottom: Some(Some(blue)),
+                    left: Some(None),
+                    right: None,
+                }
+            } else {
+                BorderStyleCellUpdate {
+                    top: Some(Some(blue)),
+                    bottom: None,
+                    left: Some(Some(red)),
+                    right: Some(Some(BorderStyleTimestamp::clear())),
+                }
+            };
+            updates.push(update);
+        }
+        updates.push_n(BorderStyleCellUpdate::clear(false), 50);
+
+        let compact = encode_compact(&updates);
+        assert_eq!(decode_compact(&compact).unwrap(), updates);
+
+        // compare what is sent: the serialized operation, both before and
+        // after compression
+        let selection = Selection::rect(Rect::new(1, 1, 10, 25), SheetId::test());
+        let op = vec![Operation::SetBordersSelection {
+            selection: selection.clone(),
+            borders: updates.clone(),
+        }];
+        let plain = vec![serde_json::json!({
+            "SetBordersSelection": { "selection": selection, "borders": updates }
+        })];
+        let op_json = serde_json::to_string(&op).unwrap();
+        let plain_json = serde_json::to_string(&plain).unwrap();
+        assert!(op_json.len() * 10 < plain_json.len());
+
+        let op_compressed = Transaction::serialize_and_compress(&op).unwrap();
+        let plain_compressed = Transaction::serialize_and_compress(&plain).unwrap();
+        assert!(op_compressed.len() * 2 < plain_compressed.len());
+    }
+
+    #[test]
+    #[parallel]
+    fn set_borders_selection_serde() {
+        let style = BorderStyleTimestamp::new(Rgba::new(255, 0, 0, 255), CellBorderLine::Line2);
+        let mut borders = BorderStyleCellUpdates::new();
+        borders.push_n(
+            BorderStyleCellUpdate {
+                top: Some(Some(style)),
+                bottom: Some(None),
+                left: None,
+                right: Some(Some(style)),
+            },
+            9,
+        );
+        let selection = Selection::rect(Rect::new(1, 1, 3, 3), SheetId::test());
+        let op = Operation::SetBordersSelection {
+            selection: selection.clone(),
+            borders: borders.clone(),
+        };
+
+        // the operation serializes the compact form
+        let json = serde_json::to_string(&op).unwrap();
+        assert!(!json.contains("color"));
+        assert_eq!(serde_json::from_str::<Operation>(&json).unwrap(), op);
+
+        // operations serialized with the plain form still deserialize
+        let plain = serde_json::json!({
+            "SetBordersSelection": { "selection": selection, "borders": borders }
+        });
+        assert_eq!(serde_json::from_value::<Operation>(plain).unwrap(), op);
+    }
+
+    #[test]
+    #[parallel]
+    fn compact_empty() {
+        let updates = BorderStyleCellUpdates::new();
+        let compact = encode_compact(&updates);
+        assert_eq!(decode_compact(&compact).unwrap(), updates);
+    }
+
+    #[test]
+    #[parallel]
+    fn compact_invalid() {
+        assert!(decode_compact(&[]).is_err());
+        assert!(decode_compact(&[VERSION + 1]).is_err());
+        assert!(decode_compact(&[VERSION, 0, 1, 1, FLAG_SET]).is_err());
+    }
+}
@@ -1,107 +1,54 @@
-use super::{BorderStyleCell, BorderStyleCellUpdate, Borders};
+use crate::Rect;
+
+use super::{
+    BorderStyleCell, BorderStyleCellUpdate, BorderStyleCellUpdates, BorderStyleTimestamp, Borders,
+};
 
 impl Borders {
     /// Gets a BorderStyleCellUpdate for a cell that will override the current
     /// cell. This is called by the clipboard.
     pub fn update_override(&self, x: i64, y: i64) -> BorderStyleCellUpdate {
-        let mut cell = self.all;
-
-        // for columns and rows, we'll have to compare the timestamps to get the correct value
-        let column = self.columns.get(&x);
-        let row = self.rows.get(&y);
-
-        match (column, row) {
-            (Some(column), Some(row)) => {
-                match (column.top, row.top) {
-                    (Some(column_top), Some(row_top)) => {
-                        if column_top.timestamp > row_top.timestamp {
-                            cell.top = Some(column_top);
-                        } else {
-                            cell.top = Some(row_top);
-                        }
-                    }
-                    (Some(column_top), None) => {
-                        cell.top = Some(column_top);
-                    }
-                    (None, Some(row_top)) => {
-                        cell.top = Some(row_top);
-                    }
-                    (None, None) => {}
-                }
-                match (column.bottom, row.bottom) {
-                    (Some(column_bottom), Some(row_bottom)) => {
-                        if column_bottom.timestamp > row_bottom.timestamp {
-                            cell.bottom = Some(column_bottom);
-                        } else {
-                            cell.bottom = Some(row_bottom);
-                        }
-                    }
-                    (Some(column_bottom), None) => {
-                        cell.bottom = Some(column_bottom);
-                    }
-                    (None, Some(row_bottom)) => {
-                        cell.bottom = Some(row_bottom);
-                    }
-                    (None, None) => {}
-                }
-                match (column.left, row.left) {
-                    (Some(column_left), Some(row_left)) => {
-                        if column_left.timestamp > row_left.timestamp {
-                            cell.left = Some(column_left);
-                        } else {
-                            cell.left = Some(row_left);
-                        }
-                    }
-                    (Some(column_left), None) => {
-                        cell.left = Some(column_left);
-                    }
-                    (None, Some(row_left)) => {
-                        cell.left = Some(row_left);
-                    }
-                    (None, None) => {}
-                }
-                match (column.right, row.right) {
-                    (Some(column_right), Some(row_right)) => {
-                        if column_right.timestamp > row_right.timestamp {
-                            cell.right = Some(column_right);
-                        } else {
-                            cell.right = Some(row_right);
-                        }
-                    }
-                    (Some(column_right), None) => {
-                        cell.right = Some(column_right);
-                    }
-                    (None, Some(row_right)) => {
-                        cell.right = Some(row_right);
-                    }
-                    (None, None) => {}
-                }
-            }
-            (Some(column), None) => {
-                cell.top = column.top.or(cell.top);
-                cell.bottom = column.bottom.or(cell.bottom);
-                cell.left = column.left.or(cell.left);
-                cell.right = column.right.or(cell.right);
-            }
-            (None, Some(row)) => {
-                cell.top = row.top.or(cell.top);
-                cell.bottom = row.bottom.or(cell.bottom);
-                cell.left = row.left.or(cell.left);
-                cell.right = row.right.or(cell.right);
+        self.effective(x, y).override_border(false)
+    }
+
+    /// Gets the border style that applies to a cell by resolving the sheet,
+    /// column, row, and cell-level borders. Precedence (per side) is: the
+    /// cell-level border, then the newer of the column and row borders, then
+    /// the sheet-wide border. If the column and row borders have the same
+    /// timestamp, the row border wins.
+    ///
+    /// Note: this may include CellBorderLine::Clear entries.
+    pub fn effective(&self, x: i64, y: i64) -> BorderStyleCell {
+        fn newer(
+            column: Option<BorderStyleTimestamp>,
+            row: Option<BorderStyleTimestamp>,
+        ) -> Option<BorderStyleTimestamp> {
+            match (column, row) {
+                (Some(column), Some(row)) if column.timestamp > row.timestamp => Some(column),
+                (column, row) => row.or(column),
             }
-            (None, None) => {}
         }
 
+        let column = self.columns.get(&x).copied().unwrap_or_default();
+        let row = self.rows.get(&y).copied().unwrap_or_default();
         let c = self.get(x, y);
-        cell.top = c.top.or(cell.top);
-        cell.bottom = c.bottom.or(cell.bottom);
-        cell.left = c.left.or(cell.left);
-        cell.right = c.right.or(cell.right);
 
-        cell.override_border(false)
+        BorderStyleCell {
+            top: c.top.or(newer(column.top, row.top)).or(self.all.top),
+            bottom: c
+                .bottom
+                .or(newer(column.bottom, row.bottom))
+                .or(self.all.bottom),
+            left: c.left.or(newer(column.left, row.left)).or(self.all.left),
+            right: c
+                .right
+                .or(newer(column.right, row.right))
+                .or(self.all.right),
+        }
     }
 
-    /// Gets the border style for a cell.
+    /// Gets the cell-level border style for a cell. This does not fall back
+    /// to the sheet, column, or row borders (see effective).
     pub fn get(&self, x: i64, y: i64) -> BorderStyleCell {
         let top = self.top.get(&y).and_then(|row| row.get(x));
         let bottom = self.bottom.get(&(y)).and_then(|row| row.get(x));
@@ -116,6 +63,16 @@ impl Borders {
         }
     }
 
+    /// Captures the cell-level borders of a rect (in Rect::iter order) as
+    /// updates that restore them exactly, including removing sides that are
+    /// not set. Restore with apply_updates on the same rect.
+    pub fn snapshot_region(&self, rect: Rect) -> BorderStyleCellUpdates {
+        let mut updates = BorderStyleCellUpdates::new();
+        rect.iter()
+            .for_each(|pos| updates.push(self.get(pos.x, pos.y).override_border(false)));
+        updates
+    }
+
     /// Gets an update to undo the border to its current state.
     pub fn try_get_update(&self, x: i64, y: i64) -> Option<BorderStyleCellUpdate> {
         let cell = self.get(x, y);
@@ -137,11 +94,14 @@ mod tests {
 
     use crate::{
         color::Rgba,
-        controller::GridController,
-        grid::{BorderSelection, BorderStyle, CellBorderLine},
+        controller::{operations::operation::Operation, GridController},
+        grid::{BorderSelection, BorderStyle, CellBorderLine, SheetId},
         selection::Selection,
+        small_timestamp::SmallTimestamp,
     };
 
+    use super::*;
+
     #[test]
     #[parallel]
     fn get() {
@@ -232,4 +192,159 @@ mod tests {
         assert_eq!(cell.left.unwrap().line, CellBorderLine::default());
         assert_eq!(cell.right.unwrap().line, CellBorderLine::default());
     }
+
+    fn style(color: &str, timestamp: u32) -> BorderStyleTimestamp {
+        BorderStyleTimestamp {
+            color: Rgba::color_from_str(color).unwrap(),
+            line: CellBorderLine::Line1,
+            timestamp: SmallTimestamp::new(timestamp),
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn effective_row_column_conflict() {
+        let red = style("#ff0000", 10);
+        let blue = style("#0000ff", 10);
+        let green = style("#00ff00", 20);
+
+        let mut borders = Borders::default();
+        borders.columns.insert(
+            1,
+            BorderStyleCell {
+                top: Some(red),
+                left: Some(green),
+                ..Default::default()
+            },
+        );
+        borders.rows.insert(
+            1,
+            BorderStyleCell {
+                top: Some(blue),
+                left: Some(blue),
+                ..Default::default()
+            },
+        );
+
+        // same timestamp: the row wins
+        assert_eq!(borders.effective(1, 1).top, Some(blue));
+        assert_eq!(borders.update_override(1, 1).top, Some(Some(blue)));
+
+        // the newer column border wins
+        assert_eq!(borders.effective(1, 1).left, Some(green));
+
+        // a cell-level border wins over both
+        borders.set(1, 1, Some(BorderStyle::default()), None, None, None);
+        assert_eq!(
+            borders.effective(1, 1).top.unwrap().line,
+            BorderStyle::default().line
+        );
+        assert_eq!(borders.effective(1, 2).top, Some(red));
+    }
+
+    #[test]
+    #[parallel]
+    fn get_column_ops_row_column_conflict() {
+        let sheet_id = SheetId::test();
+        let red = style("#ff0000", 10);
+        let blue = style("#0000ff", 10);
+
+        let mut borders = Borders::default();
+        borders.columns.insert(
+            1,
+            BorderStyleCell {
+                top: Some(red),
+                ..Default::default()
+            },
+        );
+        borders.rows.insert(
+            1,
+            BorderStyleCell {
+                top: Some(blue),
+                ..Default::default()
+            },
+        );
+
+        // the column's own entry is captured (not the resolved style), so
+        // replaying it reproduces the same winner
+        let ops = borders.get_column_ops(sheet_id, 1);
+        assert_eq!(ops.len(), 1);
+        let Operation::SetBordersSelection {
+            selection,
+            borders: updates,
+        } = ops[0].clone()
+        else {
+            panic!("Expected SetBordersSelection");
+        };
+        assert_eq!(selection.columns, Some(vec![1]));
+        assert_eq!(updates.get_at(0).unwrap().top, Some(Some(red)));
+
+        let mut replayed = borders.clone();
+        replayed.columns.clear();
+        replayed.set_borders(&selection, &updates);
+        assert_eq!(replayed.effective(1, 1), borders.effective(1, 1));
+        assert_eq!(replayed.effective(1, 1).top, Some(blue));
+        assert_eq!(replayed.effective(1, 2).top, Some(red));
+    }
+
+    #[test]
+    #[parallel]
+    fn effective_row_fallback() {
+        let red = style("#ff0000", 10);
+
+        let mut borders = Borders::default();
+        borders.rows.insert(
+            2,
+            BorderStyleCell {
+                top: Some(red),
+                ..Default::default()
+            },
+        );
+
+        // no cell-level entry, so only effective sees the row border
+        assert_eq!(borders.get(3, 2).top, None);
+        assert_eq!(borders.effective(3, 2).top, Some(red));
+        assert_eq!(borders.effective(3, 2).bottom, None);
+        assert_eq!(borders.effective(3, 3).top, None);
+    }
+
+    #[test]
+    #[parallel]
+    fn snapshot_region() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_borders_selection(
+            Selection::rect(Rect::new(2, 2, 3, 3), sheet_id),
+            BorderSelection::Outer,
+            Some(BorderStyle::default()),
+            None,
+        );
+        let rect = Rect::new(1, 1, 3, 3);
+        let original = gc.sheet(sheet_id).borders.clone();
+        let snapshot = original.snapshot_region(rect);
+        assert_eq!(snapshot.size(), 9);
+
+        // a multi-side edit that adds, changes, and removes borders
+        gc.set_borders_selection(
+            Selection::rect(rect, sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle {
+                color: Rgba::new(255, 0, 0, 255),
+                line: CellBorderLine::Dotted,
+            }),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::rect(Rect::new(3, 3, 3, 3), sheet_id),
+            BorderSelection::Clear,
+            None,
+            None,
+        );
+
+        let borders = &mut gc.sheet_mut(sheet_id).borders;
+        assert!(borders.apply_updates(rect, &snapshot));
+        for pos in rect.iter() {
+            assert_eq!(borders.get(pos.x, pos.y), original.get(pos.x, pos.y));
+        }
+    }
 }
@@ -0,0 +1,142 @@
+//! Enumerates borders for external rendering and export (eg, to XLSX).
+
+use std::collections::HashMap;
+
+use crate::{
+    grid::{block::SameValue, ColumnData},
+    Pos,
+};
+
+use super::{BorderSide, BorderStyle, BorderStyleCell, BorderStyleTimestamp, Borders};
+
+/// The extent of a border that is not tied to cells.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BorderLine {
+    Sheet,
+    Column(i64),
+    Row(i64),
+}
+
+impl Borders {
+    /// Iterates over every cell-level border segment, in no particular order.
+    ///
+    /// Sheet, column, and row borders are unbounded, so they are not expanded
+    /// here; see iter_lines. Segments may include CellBorderLine::Clear,
+    /// which removes a sheet, column, or row border from that cell side.
+    pub fn iter_segments(&self) -> impl Iterator<Item = (Pos, BorderSide, BorderStyle)> + '_ {
+        fn segments(
+            map: &HashMap<i64, ColumnData<SameValue<BorderStyleTimestamp>>>,
+            side: BorderSide,
+            vertical: bool,
+        ) -> impl Iterator<Item = (Pos, BorderSide, BorderStyle)> + '_ {
+            map.iter().flat_map(move |(&key, data)| {
+                data.blocks().flat_map(move |block| {
+                    let style = BorderStyle::from(block.content.value);
+                    block.range().map(move |i| {
+                        let pos = if vertical {
+                            Pos { x: key, y: i }
+                        } else {
+                            Pos { x: i, y: key }
+                        };
+                        (pos, side, style)
+                    })
+                })
+            })
+        }
+
+        segments(&self.top, BorderSide::Top, false)
+            .chain(segments(&self.bottom, BorderSide::Bottom, false))
+            .chain(segments(&self.left, BorderSide::Left, true))
+            .chain(segments(&self.right, BorderSide::Right, true))
+    }
+
+    /// Iterates over the sheet, column, and row borders as line entries, in
+    /// no particular order. Each applies to the given side of every cell in
+    /// the line unless a cell-level segment overrides it (see effective).
+    pub fn iter_lines(&self) -> impl Iterator<Item = (BorderLine, BorderSide, BorderStyle)> + '_ {
+        fn sides(
+            line: BorderLine,
+            cell: &BorderStyleCell,
+        ) -> impl Iterator<Item = (BorderLine, BorderSide, BorderStyle)> {
+            [
+                (BorderSide::Top, cell.top),
+                (BorderSide::Bottom, cell.bottom),
+                (BorderSide::Left, cell.left),
+                (BorderSide::Right, cell.right),
+            ]
+            .into_iter()
+            .filter_map(move |(side, style)| {
+                style.map(|style| (line, side, BorderStyle::from(style)))
+            })
+        }
+
+        sides(BorderLine::Sheet, &self.all)
+            .chain(
+                self.columns
+                    .iter()
+                    .flat_map(|(&x, cell)| sides(BorderLine::Column(x), cell)),
+            )
+            .chain(
+                self.rows
+                    .iter()
+                    .flat_map(|(&y, cell)| sides(BorderLine::Row(y), cell)),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+    use crate::{color::Rgba, grid::CellBorderLine};
+
+    #[test]
+    #[parallel]
+    fn iter_segments() {
+        let red = BorderStyleTimestamp::new(Rgba::new(255, 0, 0, 255), CellBorderLine::Line1);
+        let blue = BorderStyleTimestamp::new(Rgba::new(0, 0, 255, 255), CellBorderLine::Line2);
+
+        let mut borders = Borders::default();
+        let mut top: ColumnData<SameValue<BorderStyleTimestamp>> = ColumnData::new();
+        top.set_range(1..3, red);
+        borders.top.insert(1, top);
+        let mut left: ColumnData<SameValue<BorderStyleTimestamp>> = ColumnData::new();
+        left.set(2, Some(blue));
+        borders.left.insert(4, left);
+        borders.rows.insert(
+            6,
+            BorderStyleCell {
+                bottom: Some(blue),
+                ..Default::default()
+            },
+        );
+
+        let mut segments = borders.iter_segments().collect::<Vec<_>>();
+        segments.sort_by_key(|(pos, _, _)| *pos);
+        assert_eq!(
+            segments,
+            vec![
+                (Pos { x: 1, y: 1 }, BorderSide::Top, BorderStyle::from(red)),
+                (Pos { x: 2, y: 1 }, BorderSide::Top, BorderStyle::from(red)),
+                (
+                    Pos { x: 4, y: 2 },
+                    BorderSide::Left,
+                    BorderStyle::from(blue)
+                ),
+            ]
+        );
+
+        assert_eq!(
+            borders.iter_lines().collect::<Vec<_>>(),
+            vec![(
+                BorderLine::Row(6),
+                BorderSide::Bottom,
+                BorderStyle::from(blue)
+            )]
+        );
+
+        assert_eq!(Borders::default().iter_segments().count(), 0);
+        assert_eq!(Borders::default().iter_lines().count(), 0);
+    }
+}
@@ -0,0 +1,142 @@
+//! Resolves cell borders that are stored twice for the same edge.
+//!
+//! The edge between two horizontally adjacent cells can be stored as the left
+//! cell's right border and as the right cell's left border (and likewise for
+//! bottom and top). Imported files may set both with different styles.
+
+use std::collections::HashMap;
+
+use crate::grid::{block::SameValue, ColumnData};
+
+use super::{BorderStyleTimestamp, Borders};
+
+type SideMap = HashMap<i64, ColumnData<SameValue<BorderStyleTimestamp>>>;
+
+/// Removes the losing side of every edge stored in both `before` (the right or
+/// bottom of line - 1) and `after` (the left or top of line). The newer
+/// border wins; on a tie, `before` wins. This matches how
+/// horizontal_borders_in_rect and vertical_borders_in_rect pick a border.
+fn normalize_side(before: &mut SideMap, after: &mut SideMap) -> bool {
+    let mut remove_before = vec![];
+    let mut remove_after = vec![];
+    for (&line, data) in before.iter() {
+        let Some(next) = after.get(&(line + 1)) else {
+            continue;
+        };
+        for block in data.blocks() {
+            for i in block.range() {
+                if let Some(other) = next.get(i) {
+                    if other.timestamp > block.content.value.timestamp {
+                        remove_before.push((line, i));
+                    } else {
+                        remove_after.push((line + 1, i));
+                    }
+                }
+            }
+        }
+    }
+
+    let changed = !remove_before.is_empty() || !remove_after.is_empty();
+    for (line, i) in remove_before {
+        if let Some(data) = before.get_mut(&line) {
+            data.set(i, None);
+        }
+    }
+    for (line, i) in remove_after {
+        if let Some(data) = after.get_mut(&line) {
+            data.set(i, None);
+        }
+    }
+    before.retain(|_, data| !data.is_empty());
+    after.retain(|_, data| !data.is_empty());
+    changed
+}
+
+impl Borders {
+    /// Stores each shared cell edge once. When a cell's right (or bottom)
+    /// border and its neighbor's left (or top) border are both set, only the
+    /// one that is rendered is kept: the newer border, or the right (or
+    /// bottom) border if they have the same timestamp. Rendering is
+    /// unchanged, and to_clipboard no longer copies the hidden border.
+    ///
+    /// Returns true if any border was removed.
+    pub fn normalize(&mut self) -> bool {
+        let vertical = normalize_side(&mut self.right, &mut self.left);
+        let horizontal = normalize_side(&mut self.bottom, &mut self.top);
+        vertical || horizontal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+    use crate::{
+        color::Rgba,
+        grid::sheet::borders::{BorderStyle, CellBorderLine},
+        small_timestamp::SmallTimestamp,
+        Rect,
+    };
+
+    fn style(color: Rgba, timestamp: u32) -> BorderStyleTimestamp {
+        BorderStyleTimestamp {
+            color,
+            line: CellBorderLine::Line1,
+            timestamp: SmallTimestamp::new(timestamp),
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn normalize_vertical() {
+        let red = style(Rgba::new(255, 0, 0, 255), 1);
+        let blue = style(Rgba::new(0, 0, 255, 255), 2);
+
+        // (1, 1)'s right is older than (2, 1)'s left; (1, 2)'s right is newer
+        // than (2, 2)'s left
+        let mut borders = Borders::default();
+        borders.right.entry(1).or_default().set(1, Some(red));
+        borders.left.entry(2).or_default().set(1, Some(blue));
+        borders.right.entry(1).or_default().set(2, Some(blue));
+        borders.left.entry(2).or_default().set(2, Some(red));
+
+        let rect = Rect::new(1, 1, 3, 3);
+        let rendered = borders.vertical_borders_in_rect(rect);
+
+        assert!(borders.normalize());
+        assert_eq!(borders.get(1, 1).right, None);
+        assert_eq!(borders.get(2, 1).left, Some(blue));
+        assert_eq!(borders.get(1, 2).right, Some(blue));
+        assert_eq!(borders.get(2, 2).left, None);
+        assert_eq!(borders.vertical_borders_in_rect(rect), rendered);
+
+        // a second pass has nothing to do
+        assert!(!borders.normalize());
+    }
+
+    #[test]
+    #[parallel]
+    fn normalize_horizontal_tie() {
+        let red = style(Rgba::new(255, 0, 0, 255), 1);
+        let blue = style(Rgba::new(0, 0, 255, 255), 1);
+
+        // (1, 1)'s bottom and (1, 2)'s top have the same timestamp
+        let mut borders = Borders::default();
+        borders.bottom.entry(1).or_default().set(1, Some(red));
+        borders.top.entry(2).or_default().set(1, Some(blue));
+
+        // borders that do not share an edge are untouched
+        borders.set(5, 5, Some(BorderStyle::default()), None, None, None);
+
+        let rect = Rect::new(1, 1, 6, 6);
+        let rendered = borders.horizontal_borders_in_rect(rect);
+
+        assert!(borders.normalize());
+        assert_eq!(borders.get(1, 1).bottom, Some(red));
+        assert_eq!(borders.get(1, 2).top, None);
+        assert!(!borders.top.contains_key(&2));
+        assert!(borders.get(5, 5).top.is_some());
+        assert_eq!(borders.horizontal_borders_in_rect(rect), rendered);
+    }
+}
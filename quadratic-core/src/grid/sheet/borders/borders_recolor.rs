@@ -0,0 +1,146 @@
+//! Recolors borders in place (eg, when a sheet's accent color changes).
+
+use crate::{
+    color::Rgba,
+    grid::{block::SameValue, ColumnData},
+};
+
+use super::{BorderStyleCell, BorderStyleTimestamp, Borders};
+
+impl Borders {
+    /// Replaces the color of every border (sheet, column, row, and cell-level)
+    /// of color `from` with `to`. Timestamps are kept, so the precedence
+    /// between column and row borders does not change.
+    ///
+    /// Returns true if any borders changed.
+    pub fn recolor(&mut self, from: Rgba, to: Rgba) -> bool {
+        if from == to {
+            return false;
+        }
+        let mut changed = recolor_cell(&mut self.all, from, to);
+        for cell in self.columns.values_mut().chain(self.rows.values_mut()) {
+            changed |= recolor_cell(cell, from, to);
+        }
+        for data in self
+            .left
+            .values_mut()
+            .chain(self.right.values_mut())
+            .chain(self.top.values_mut())
+            .chain(self.bottom.values_mut())
+        {
+            changed |= recolor_column_data(data, from, to);
+        }
+        changed
+    }
+}
+
+fn recolor_style(style: &mut Option<BorderStyleTimestamp>, from: Rgba, to: Rgba) -> bool {
+    match style {
+        Some(style) if style.color == from => {
+            style.color = to;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn recolor_cell(cell: &mut BorderStyleCell, from: Rgba, to: Rgba) -> bool {
+    // uses | (not ||) so every side is recolored
+    recolor_style(&mut cell.top, from, to)
+        | recolor_style(&mut cell.bottom, from, to)
+        | recolor_style(&mut cell.left, from, to)
+        | recolor_style(&mut cell.right, from, to)
+}
+
+fn recolor_column_data(
+    data: &mut ColumnData<SameValue<BorderStyleTimestamp>>,
+    from: Rgba,
+    to: Rgba,
+) -> bool {
+    let blocks = data
+        .blocks()
+        .filter(|block| block.content.value.color == from)
+        .map(|block| (block.range(), block.content.value))
+        .collect::<Vec<_>>();
+    let changed = !blocks.is_empty();
+    for (range, mut style) in blocks {
+        style.color = to;
+        data.set_range(range, style);
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+    use crate::{
+        controller::GridController,
+        grid::{BorderSelection, BorderStyle},
+        selection::Selection,
+        SheetRect,
+    };
+
+    #[test]
+    #[parallel]
+    fn recolor() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let red = Rgba::new(255, 0, 0, 255);
+        let blue = Rgba::new(0, 0, 255, 255);
+        let green = Rgba::new(0, 255, 0, 255);
+
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 2, 2, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle {
+                color: red,
+                ..Default::default()
+            }),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(4, 4, 4, 4, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle {
+                color: blue,
+                ..Default::default()
+            }),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::rows(&[6], sheet_id),
+            BorderSelection::Top,
+            Some(BorderStyle {
+                color: red,
+                ..Default::default()
+            }),
+            None,
+        );
+
+        let borders = &mut gc.sheet_mut(sheet_id).borders;
+        assert!(borders.recolor(red, green));
+
+        for (x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+            let cell = borders.get(x, y);
+            assert_eq!(cell.top.unwrap().color, green);
+            assert_eq!(cell.bottom.unwrap().color, green);
+            assert_eq!(cell.left.unwrap().color, green);
+            assert_eq!(cell.right.unwrap().color, green);
+        }
+        assert_eq!(borders.get(4, 4).top.unwrap().color, blue);
+        assert_eq!(borders.rows.get(&6).unwrap().top.unwrap().color, green);
+
+        let clipboard = borders
+            .to_clipboard(&Selection::sheet_rect(SheetRect::new(1, 1, 1, 1, sheet_id)))
+            .unwrap();
+        assert_eq!(
+            clipboard.get_at(0).unwrap().top.unwrap().unwrap().color,
+            green
+        );
+
+        // nothing is left to recolor
+        assert!(!borders.recolor(red, green));
+    }
+}
@@ -9,7 +9,9 @@
 //! borders on the sheet. Since this is a one-time cost, (I think) it'll still
 //! be performant.
 
-use super::{Borders, JsBorderHorizontal, JsBorderVertical, JsBordersSheet};
+use std::collections::HashMap;
+
+use super::{BorderStyleCell, Borders, JsBorderHorizontal, JsBorderVertical, JsBordersSheet};
 use crate::{grid::SheetId, wasm_bindings::js::jsBordersSheet, Rect};
 
 impl Borders {
@@ -156,9 +158,60 @@ impl Borders {
         } else {
             (None, None)
         };
+        self.package_borders(
+            self.columns
+                .iter()
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect(),
+            self.rows.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            horizontal,
+            vertical,
+        )
+    }
+
+    /// Gets packaged borders that are visible in a rect (eg, the viewport).
+    /// Cell borders are clipped to the rect, including the lines along its
+    /// bottom and right edges, and only the columns and rows that cross the
+    /// rect are included. The sheet-wide border is always included.
+    pub fn borders_in_rect(&self, rect: Rect) -> Option<JsBordersSheet> {
+        let horizontal = self.horizontal_borders_in_rect(Rect::new(
+            rect.min.x,
+            rect.min.y,
+            rect.max.x,
+            rect.max.y + 1,
+        ));
+        let vertical = self.vertical_borders_in_rect(Rect::new(
+            rect.min.x,
+            rect.min.y,
+            rect.max.x + 1,
+            rect.max.y,
+        ));
+        self.package_borders(
+            self.columns
+                .iter()
+                .filter(|(x, _)| rect.x_range().contains(x))
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect(),
+            self.rows
+                .iter()
+                .filter(|(y, _)| rect.y_range().contains(y))
+                .map(|(k, v)| (k.to_string(), *v))
+                .collect(),
+            horizontal,
+            vertical,
+        )
+    }
+
+    fn package_borders(
+        &self,
+        columns: HashMap<String, BorderStyleCell>,
+        rows: HashMap<String, BorderStyleCell>,
+        horizontal: Option<Vec<JsBorderHorizontal>>,
+        vertical: Option<Vec<JsBorderVertical>>,
+    ) -> Option<JsBordersSheet> {
         if self.all.is_empty()
-            && self.columns.is_empty()
-            && self.rows.is_empty()
+            && columns.is_empty()
+            && rows.is_empty()
             && horizontal.is_none()
             && vertical.is_none()
         {
@@ -170,21 +223,12 @@ impl Borders {
                 } else {
                     Some(self.all)
                 },
-                columns: if self.columns.is_empty() {
+                columns: if columns.is_empty() {
                     None
                 } else {
-                    Some(
-                        self.columns
-                            .iter()
-                            .map(|(k, v)| (k.to_string(), *v))
-                            .collect(),
-                    )
-                },
-                rows: if self.rows.is_empty() {
-                    None
-                } else {
-                    Some(self.rows.iter().map(|(k, v)| (k.to_string(), *v)).collect())
+                    Some(columns)
                 },
+                rows: if rows.is_empty() { None } else { Some(rows) },
 
                 horizontal,
                 vertical,
@@ -483,4 +527,60 @@ mod tests {
         };
         assert_eq!(borders, expected);
     }
+
+    #[test]
+    #[parallel]
+    fn borders_in_rect() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        gc.set_borders_selection(
+            Selection::rows(&[2, 30], sheet_id),
+            BorderSelection::Top,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 5, 1, sheet_id)),
+            BorderSelection::Top,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(20, 20, 20, 20, sheet_id)),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        let borders = sheet
+            .borders
+            .borders_in_rect(Rect::new(3, 0, 10, 5))
+            .unwrap();
+
+        // the line straddling the left edge is clipped and the cell outside
+        // the rect is excluded
+        assert_eq!(
+            borders.horizontal,
+            Some(vec![JsBorderHorizontal {
+                x: 3,
+                y: 1,
+                width: 3,
+                color: Rgba::default(),
+                line: CellBorderLine::default(),
+            }])
+        );
+        assert_eq!(borders.vertical, None);
+        assert_eq!(borders.columns, None);
+        assert_eq!(
+            borders.rows.unwrap().into_keys().collect::<Vec<_>>(),
+            vec!["2".to_string()]
+        );
+
+        assert!(sheet
+            .borders
+            .borders_in_rect(Rect::new(100, 100, 110, 110))
+            .is_none());
+    }
 }
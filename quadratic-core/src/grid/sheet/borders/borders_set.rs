@@ -1,11 +1,18 @@
 //! Functionality to set borders on a selection.
 
+use std::{collections::HashMap, ops::Range};
+
 use crate::{
-    controller::operations::operation::Operation, grid::CellBorderLine, selection::Selection,
-    RunLengthEncoding,
+    controller::operations::operation::Operation,
+    grid::{block::SameValue, CellBorderLine, ColumnData, Sheet},
+    selection::Selection,
+    Rect, RunLengthEncoding,
 };
 
-use super::{BorderStyle, BorderStyleCell, BorderStyleCellUpdate, BorderStyleCellUpdates, Borders};
+use super::{
+    BorderStyle, BorderStyleCell, BorderStyleCellUpdate, BorderStyleCellUpdates,
+    BorderStyleTimestamp, Borders,
+};
 
 impl Borders {
     /// Sets the borders for a selection.
@@ -176,6 +183,47 @@ impl Borders {
         }
     }
 
+    /// Sets the same borders for every cell in a rect. Each side is written
+    /// as a single block per row (top and bottom) or column (left and right),
+    /// so the rect stays coalesced. Returns true if any border changed.
+    pub fn set_rect(
+        &mut self,
+        rect: Rect,
+        top: Option<BorderStyle>,
+        bottom: Option<BorderStyle>,
+        left: Option<BorderStyle>,
+        right: Option<BorderStyle>,
+    ) -> bool {
+        fn set_lines(
+            map: &mut HashMap<i64, ColumnData<SameValue<BorderStyleTimestamp>>>,
+            lines: Range<i64>,
+            range: Range<i64>,
+            style: Option<BorderStyle>,
+        ) -> bool {
+            let Some(style) = style else {
+                return false;
+            };
+            let value = BorderStyleTimestamp::from(style);
+            let len = (range.end - range.start) as usize;
+            let mut changed = false;
+            for line in lines {
+                let removed = map.entry(line).or_default().set_range(range.clone(), value);
+                let unchanged = removed.iter().map(|block| block.len()).sum::<usize>() == len
+                    && removed
+                        .iter()
+                        .all(|block| block.content.value.is_equal_to_border_style(&style));
+                changed |= !unchanged;
+            }
+            changed
+        }
+
+        let top = set_lines(&mut self.top, rect.y_range(), rect.x_range(), top);
+        let bottom = set_lines(&mut self.bottom, rect.y_range(), rect.x_range(), bottom);
+        let left = set_lines(&mut self.left, rect.x_range(), rect.y_range(), left);
+        let right = set_lines(&mut self.right, rect.x_range(), rect.y_range(), right);
+        top || bottom || left || right
+    }
+
     pub fn apply_update(
         &mut self,
         x: i64,
@@ -197,6 +245,41 @@ impl Borders {
         }
         current.override_border(false)
     }
+
+    /// Applies cell-level updates to a rect. Updates are read in the same
+    /// row-major order as `Rect::iter` (and `to_clipboard`), one per cell.
+    /// Returns true if any border changed.
+    pub fn apply_updates(&mut self, rect: Rect, updates: &BorderStyleCellUpdates) -> bool {
+        let mut changed = false;
+        for (pos, update) in rect.iter().zip(updates.iter_values()) {
+            let before = self.get(pos.x, pos.y);
+            self.apply_update(pos.x, pos.y, *update);
+            if self.get(pos.x, pos.y) != before {
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+impl Sheet {
+    /// Sets the borders for a selection. Returns the reverse operations and
+    /// the rects whose borders changed. The rects are None if the selection
+    /// includes the whole sheet, columns, or rows (ie, the change is
+    /// unbounded).
+    pub fn set_borders_selection(
+        &mut self,
+        selection: &Selection,
+        borders: &BorderStyleCellUpdates,
+    ) -> (Vec<Operation>, Option<Vec<Rect>>) {
+        let reverse_operations = self.borders.set_borders(selection, borders);
+        let affected = if selection.all || selection.columns.is_some() || selection.rows.is_some() {
+            None
+        } else {
+            Some(selection.rects.clone().unwrap_or_default())
+        };
+        (reverse_operations, affected)
+    }
 }
 
 #[cfg(test)]
@@ -284,4 +367,99 @@ mod tests {
         assert!(borders.all.top.is_none());
         assert!(borders.all.bottom.is_none());
     }
+
+    #[test]
+    #[parallel]
+    fn apply_updates_round_trip() {
+        let sheet_id = SheetId::test();
+        let mut borders = Borders::default();
+        borders.set_borders(
+            &Selection::sheet_rect(SheetRect::new(1, 1, 2, 2, sheet_id)),
+            &RunLengthEncoding::repeat(BorderStyleCellUpdate::all(), 4),
+        );
+        borders.set_borders(
+            &Selection::sheet_rect(SheetRect::new(3, 2, 3, 2, sheet_id)),
+            &RunLengthEncoding::repeat(BorderStyleCellUpdate::clear(true), 1),
+        );
+
+        let rect = Rect::new(1, 1, 3, 3);
+        let mut updates = BorderStyleCellUpdates::new();
+        rect.iter()
+            .for_each(|pos| updates.push(borders.get(pos.x, pos.y).override_border(false)));
+
+        let mut copy = Borders::default();
+        assert!(copy.apply_updates(rect, &updates));
+        for pos in rect.iter() {
+            assert_eq!(copy.get(pos.x, pos.y), borders.get(pos.x, pos.y));
+        }
+
+        // reapplying the same updates changes nothing
+        assert!(!copy.apply_updates(rect, &updates));
+    }
+
+    #[test]
+    #[parallel]
+    fn set_rect() {
+        let mut borders = Borders::default();
+        let style = BorderStyle::default();
+        let rect = Rect::new(1, 1, 5, 5);
+        assert!(borders.set_rect(rect, Some(style), Some(style), Some(style), Some(style)));
+
+        // one block per row or column for each side
+        for map in [&borders.top, &borders.bottom, &borders.left, &borders.right] {
+            assert_eq!(map.len(), 5);
+            for data in map.values() {
+                let blocks = data.blocks().collect::<Vec<_>>();
+                assert_eq!(blocks.len(), 1);
+                assert_eq!(blocks[0].range(), 1..6);
+            }
+        }
+        for pos in rect.iter() {
+            assert!(BorderStyleCell::is_equal_ignore_timestamp(
+                Some(borders.get(pos.x, pos.y)),
+                Some(BorderStyleCell::all())
+            ));
+        }
+        assert_eq!(borders.get(6, 6), BorderStyleCell::default());
+
+        // setting the same borders again changes nothing
+        assert!(!borders.set_rect(rect, Some(style), Some(style), Some(style), Some(style)));
+
+        // only the given sides are written
+        let red = BorderStyle {
+            color: Rgba::new(255, 0, 0, 255),
+            line: CellBorderLine::Line2,
+        };
+        assert!(borders.set_rect(Rect::new(2, 2, 3, 3), Some(red), None, None, None));
+        assert!(borders
+            .get(2, 2)
+            .top
+            .unwrap()
+            .is_equal_to_border_style(&red));
+        assert!(borders
+            .get(2, 2)
+            .left
+            .unwrap()
+            .is_equal_to_border_style(&style));
+        assert!(!borders.set_rect(rect, None, None, None, None));
+    }
+
+    #[test]
+    #[parallel]
+    fn set_borders_selection_affected() {
+        let mut sheet = Sheet::test();
+        let rect = Rect::new(2, 2, 4, 4);
+        let (reverse_operations, affected) = sheet.set_borders_selection(
+            &Selection::rect(rect, sheet.id),
+            &RunLengthEncoding::repeat(BorderStyleCellUpdate::all(), 9),
+        );
+        assert_eq!(reverse_operations.len(), 1);
+        assert_eq!(affected, Some(vec![rect]));
+
+        let (_, affected) = sheet.set_borders_selection(
+            &Selection::columns(&[1], sheet.id),
+            &RunLengthEncoding::repeat(BorderStyleCellUpdate::all(), 1),
+        );
+        assert_eq!(affected, None);
+    }
 }
@@ -288,6 +288,11 @@ pub struct BorderStyleCellUpdate {
 }
 
 impl BorderStyleCellUpdate {
+    /// Returns true if the update does not change any side of the cell.
+    pub fn is_noop(&self) -> bool {
+        self.top.is_none() && self.bottom.is_none() && self.left.is_none() && self.right.is_none()
+    }
+
     /// Converts the update to a clear update (ie, if a border value is set,
     /// then turns it into Some(None); otherwise None).
     pub fn convert_to_clear(&self) -> BorderStyleCellUpdate {
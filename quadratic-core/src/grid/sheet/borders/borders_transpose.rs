@@ -0,0 +1,106 @@
+//! Transposes cell borders within a rect.
+
+use crate::Rect;
+
+use super::{BorderStyleCell, BorderStyleCellUpdate, Borders};
+
+impl Borders {
+    /// Transposes the cell-level borders within rect so that the border at
+    /// (x, y) (relative to rect.min) moves to (y, x). Top is swapped with left
+    /// and bottom with right, so the borders stay attached to the transposed
+    /// cells. The result fills the transposed rect (same origin, with width and
+    /// height swapped); any existing cell borders there are replaced.
+    ///
+    /// Returns true if any borders changed.
+    pub fn transpose_rect(&mut self, rect: Rect) -> bool {
+        let transposed = Rect::from_numbers(
+            rect.min.x,
+            rect.min.y,
+            rect.height() as i64,
+            rect.width() as i64,
+        );
+
+        let cells = rect
+            .iter()
+            .map(|pos| (pos, self.get(pos.x, pos.y)))
+            .filter(|(_, cell)| *cell != BorderStyleCell::default())
+            .collect::<Vec<_>>();
+
+        let mut changed = false;
+        for pos in rect.iter().chain(transposed.iter()) {
+            if self.try_get_update(pos.x, pos.y).is_some() {
+                self.apply_update(pos.x, pos.y, BorderStyleCellUpdate::clear(false));
+                changed = true;
+            }
+        }
+
+        for (pos, cell) in cells {
+            self.apply_update(
+                rect.min.x + (pos.y - rect.min.y),
+                rect.min.y + (pos.x - rect.min.x),
+                BorderStyleCellUpdate {
+                    top: Some(cell.left),
+                    bottom: Some(cell.right),
+                    left: Some(cell.top),
+                    right: Some(cell.bottom),
+                },
+            );
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::parallel;
+
+    use super::*;
+    use crate::{
+        controller::GridController,
+        grid::{BorderSelection, BorderStyle},
+        selection::Selection,
+        SheetRect,
+    };
+
+    #[test]
+    #[parallel]
+    fn transpose_rect() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        // 2 wide x 3 tall, with borders on the top and right of the rect
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 2, 3, sheet_id)),
+            BorderSelection::Top,
+            Some(BorderStyle::default()),
+            None,
+        );
+        gc.set_borders_selection(
+            Selection::sheet_rect(SheetRect::new(1, 1, 2, 3, sheet_id)),
+            BorderSelection::Right,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet_mut(sheet_id);
+        assert!(sheet.borders.transpose_rect(Rect::new(1, 1, 2, 3)));
+
+        // the result is 3 wide x 2 tall: the top becomes the left and the
+        // right becomes the bottom
+        let borders = &sheet.borders;
+        assert!(borders.get(1, 1).left.is_some());
+        assert!(borders.get(1, 2).left.is_some());
+        assert!(borders.get(1, 1).top.is_none());
+        assert!(borders.get(2, 1).top.is_none());
+        for x in 1..=3 {
+            assert!(borders.get(x, 2).bottom.is_some());
+            assert!(borders.get(x, 2).right.is_none());
+        }
+
+        // nothing is left in the part of the original rect outside the result
+        assert_eq!(borders.get(2, 3), BorderStyleCell::default());
+        assert_eq!(borders.get(1, 3), BorderStyleCell::default());
+    }
+}
@@ -8,13 +8,18 @@ pub mod borders_bounds;
 pub mod borders_clear;
 pub mod borders_clipboard;
 pub mod borders_col_row;
+pub mod borders_compact;
 pub mod borders_get;
+pub mod borders_iter;
+pub mod borders_normalize;
+pub mod borders_recolor;
 pub mod borders_render;
 pub mod borders_set;
 pub mod borders_style;
 #[cfg(test)]
 pub mod borders_test;
 pub mod borders_toggle;
+pub mod borders_transpose;
 pub(crate) mod sides;
 
 #[derive(Default, Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -101,6 +101,44 @@ impl Sheet {
         }
     }
 
+    /// Returns whether the cached bounds can be shifted (instead of
+    /// recalculated) when a row is inserted or deleted. This must be called
+    /// before the row is changed.
+    ///
+    /// A deleted row must be empty, since its content may define an edge of
+    /// the bounds. An inserted row must not cut through a code run's output,
+    /// since the code run stays in place while the cells below it move.
+    pub(crate) fn can_shift_bounds_rows(&self, row: i64, delete: bool) -> bool {
+        if delete {
+            self.row_bounds(row, false).is_none()
+                && !self.validations.validations.iter().any(|validation| {
+                    validation
+                        .selection
+                        .largest_rect()
+                        .is_some_and(|rect| rect.min.y <= row && rect.max.y >= row)
+                })
+        } else {
+            !self.code_runs.iter().any(|(pos, code_run)| {
+                pos.y < row && code_run.output_rect(*pos, false).max.y >= row
+            })
+        }
+    }
+
+    /// Shifts the cached bounds after a row is inserted (delta = 1) or deleted
+    /// (delta = -1). See can_shift_bounds_rows for when this is safe.
+    pub(crate) fn shift_bounds_rows(&mut self, row: i64, delta: i64) {
+        for bounds in [&mut self.data_bounds, &mut self.format_bounds] {
+            if let GridBounds::NonEmpty(rect) = bounds {
+                if rect.min.y >= row {
+                    rect.min.y += delta;
+                }
+                if rect.max.y >= row {
+                    rect.max.y += delta;
+                }
+            }
+        }
+    }
+
     /// Returns whether the sheet is completely empty.
     pub fn is_empty(&self) -> bool {
         self.data_bounds.is_empty() && self.format_bounds.is_empty()
@@ -221,6 +259,22 @@ impl Sheet {
         }
     }
 
+    /// Returns true if the row has no values and no code output (including
+    /// output that spills into the row). Formatting is ignored.
+    pub fn row_is_empty(&self, row: i64) -> bool {
+        self.row_bounds(row, true).is_none()
+    }
+
+    /// Returns true if a cell-level or row-level border touches the row.
+    /// Sheet-wide and column borders are not considered.
+    pub fn row_has_borders(&self, row: i64) -> bool {
+        self.borders.rows.contains_key(&row)
+            || self
+                .borders
+                .bounds_row(row, false, false)
+                .is_some_and(|bounds| bounds.min.x <= bounds.max.x)
+    }
+
     /// Returns the lower and upper bounds of formatting in a row, or `None` if
     /// the row has no formatting.
     pub fn row_bounds_formats(&self, row: i64) -> Option<(i64, i64)> {
@@ -390,7 +444,10 @@ impl Sheet {
 #[cfg(test)]
 mod test {
     use crate::{
-        controller::GridController,
+        controller::{
+            active_transactions::pending_transaction::PendingTransaction,
+            operations::operation::CopyFormats, GridController,
+        },
         grid::{
             formats::format_update::FormatUpdate,
             sheet::validations::{
@@ -979,4 +1036,100 @@ mod test {
         // Check that the data bounds are still empty
         assert_eq!(sheet.data_bounds, GridBounds::Empty);
     }
+
+    #[test]
+    #[parallel]
+    fn insert_delete_row_bounds() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 1, 1, 3, vec!["A", "B", "C"]);
+        let mut transaction = PendingTransaction::default();
+
+        // shifted
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.bounds(true), GridBounds::from(Rect::new(1, 1, 1, 4)));
+        sheet.delete_row(&mut transaction, 2);
+        assert_eq!(sheet.bounds(true), GridBounds::from(Rect::new(1, 1, 1, 3)));
+
+        // deleting a row with content at the edge shrinks the bounds
+        sheet.delete_row(&mut transaction, 3);
+        assert_eq!(sheet.bounds(true), GridBounds::from(Rect::new(1, 1, 1, 2)));
+
+        // rows outside the bounds do not change them
+        sheet
+            .insert_row(&mut transaction, 10, CopyFormats::None)
+            .unwrap();
+        sheet.delete_row(&mut transaction, 10);
+        assert_eq!(sheet.bounds(true), GridBounds::from(Rect::new(1, 1, 1, 2)));
+
+        // a code run that spills across the inserted row stays in place
+        sheet.test_set_code_run_array(3, 1, vec!["1", "2", "3"], true);
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.bounds(true), GridBounds::from(Rect::new(1, 1, 3, 3)));
+
+        let mut expected = sheet.clone();
+        expected.recalculate_bounds();
+        assert_eq!(sheet.bounds(true), expected.bounds(true));
+        assert_eq!(sheet.bounds(false), expected.bounds(false));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_delete_row_row_bounds() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(2, 2, 3, 1, vec!["A", "B", "C"]);
+        sheet.test_set_format(
+            5,
+            2,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        let mut transaction = PendingTransaction::default();
+
+        // row_bounds reads the columns directly, so no calculate_bounds is needed
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.row_bounds(2, true), None);
+        assert_eq!(sheet.row_bounds(3, true), Some((2, 4)));
+        assert_eq!(sheet.row_bounds(3, false), Some((2, 5)));
+        assert_eq!(sheet.row_bounds_formats(3), Some((5, 5)));
+
+        sheet.delete_row(&mut transaction, 2);
+        assert_eq!(sheet.row_bounds(2, true), Some((2, 4)));
+        assert_eq!(sheet.row_bounds_formats(2), Some((5, 5)));
+        assert_eq!(sheet.row_bounds(3, false), None);
+    }
+
+    #[test]
+    #[parallel]
+    fn row_has_borders() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.set_borders_selection(
+            Selection::rows(&[2], sheet_id),
+            BorderSelection::All,
+            Some(BorderStyle::default()),
+            None,
+        );
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet
+            .borders
+            .set(3, 4, None, None, Some(BorderStyle::default()), None);
+
+        // row-level border only
+        assert!(sheet.row_has_borders(2));
+
+        // cell-level border only
+        assert!(sheet.row_has_borders(4));
+
+        assert!(!sheet.row_has_borders(1));
+        assert!(!sheet.row_has_borders(3));
+        assert!(!sheet.row_has_borders(5));
+    }
 }
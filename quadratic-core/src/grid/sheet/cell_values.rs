@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use indexmap::IndexMap;
 
 use crate::{
@@ -6,12 +8,56 @@ use crate::{
         active_transactions::pending_transaction::PendingTransaction,
         operations::operation::Operation,
     },
-    CellValue, Pos,
+    grid::GridBounds,
+    CellValue, Pos, Rect, SheetPos,
 };
 
-use super::Sheet;
+use super::{col_row::MAX_OPERATION_SIZE_COL_ROW, Sheet};
 
 impl Sheet {
+    /// Creates reverse operations that restore the values in a rect (eg, to
+    /// undo clearing a block), broken up by MAX_OPERATION_SIZE_COL_ROW cells.
+    pub fn reverse_values_ops_for_rect(&self, rect: Rect) -> Vec<Operation> {
+        self.reverse_values_ops_for_rect_chunked(rect, MAX_OPERATION_SIZE_COL_ROW)
+    }
+
+    fn reverse_values_ops_for_rect_chunked(&self, rect: Rect, max_cells: i64) -> Vec<Operation> {
+        let mut reverse_operations = Vec::new();
+
+        // each chunk is up to chunk_w columns by chunk_h rows, and its
+        // SetCellValues starts at the chunk's own origin
+        let chunk_w = (rect.width() as i64).min(max_cells);
+        let chunk_h = (max_cells / chunk_w).max(1);
+        let mut x0 = rect.min.x;
+        while x0 <= rect.max.x {
+            let x1 = (x0 + chunk_w - 1).min(rect.max.x);
+            let mut y0 = rect.min.y;
+            while y0 <= rect.max.y {
+                let y1 = (y0 + chunk_h - 1).min(rect.max.y);
+                let mut values = CellValues::new((x1 - x0) as u32 + 1, (y1 - y0) as u32 + 1);
+                let mut has_values = false;
+                for x in x0..=x1 {
+                    if let Some(column) = self.columns.get(&x) {
+                        for (y, value) in column.values.range(y0..=y1) {
+                            values.set((x - x0) as u32, (y - y0) as u32, value.clone());
+                            has_values = true;
+                        }
+                    }
+                }
+                if has_values {
+                    reverse_operations.push(Operation::SetCellValues {
+                        sheet_pos: SheetPos::new(self.id, x0, y0),
+                        values,
+                    });
+                }
+                y0 = y1 + 1;
+            }
+            x0 = x1 + 1;
+        }
+
+        reverse_operations
+    }
+
     /// Replace cell_values with CellValues.
     ///
     /// Returns the old CellValues.
@@ -105,6 +151,86 @@ impl Sheet {
         old
     }
 
+    /// Writes runs of repeated values into a row (eg, from imported
+    /// run-length data) without building a CellValues for every cell.
+    /// CellValue::Blank clears its range. Each run gets one reverse operation
+    /// that restores the prior values, which only stores the cells that had a
+    /// value. Code cells are written but not run.
+    pub fn set_row_values_runs(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        runs: Vec<(Range<i64>, CellValue)>,
+    ) {
+        let mut cleared = false;
+        for (range, value) in runs {
+            if range.is_empty() {
+                continue;
+            }
+            let mut old = CellValues::new((range.end - range.start) as u32, 1);
+            for x in range.clone() {
+                let column = self.get_or_create_column(x);
+                let old_value = if value == CellValue::Blank {
+                    column.values.remove(&row)
+                } else {
+                    column.values.insert(row, value.clone())
+                };
+                if let Some(old_value) = old_value {
+                    old.set((x - range.start) as u32, 0, old_value);
+                }
+            }
+
+            let rect = Rect::new(range.start, row, range.end - 1, row);
+            if value == CellValue::Blank {
+                cleared = true;
+            } else {
+                self.recalculate_add_bounds(rect, false);
+            }
+            transaction.add_dirty_hashes_from_sheet_rect(rect.to_sheet_rect(self.id));
+            if transaction.is_user_undo_redo() {
+                transaction
+                    .reverse_operations
+                    .push(Operation::SetCellValues {
+                        sheet_pos: SheetPos::new(self.id, range.start, row),
+                        values: old,
+                    });
+            }
+        }
+        if cleared {
+            self.recalculate_bounds();
+        }
+    }
+
+    /// Writes values into the row below the last row with data, starting at
+    /// the first column with data (or at 1, 1 for an empty sheet). Nothing is
+    /// shifted, so the only reverse operation clears the written cells. Code
+    /// cells are written but not run.
+    ///
+    /// Returns the row that the values were written to.
+    pub fn append_row(&mut self, transaction: &mut PendingTransaction, values: CellValues) -> i64 {
+        let (x, y) = match self.bounds(true) {
+            GridBounds::NonEmpty(bounds) => (bounds.min.x, bounds.max.y + 1),
+            GridBounds::Empty => (1, 1),
+        };
+        if values.w == 0 || values.h == 0 {
+            return y;
+        }
+
+        let old = self.merge_cell_values(transaction, Pos { x, y }, &values, false);
+        let rect = Rect::from_numbers(x, y, values.w as i64, values.h as i64);
+        self.recalculate_add_bounds(rect, false);
+        transaction.add_dirty_hashes_from_sheet_rect(rect.to_sheet_rect(self.id));
+        if transaction.is_user_undo_redo() {
+            transaction
+                .reverse_operations
+                .push(Operation::SetCellValues {
+                    sheet_pos: SheetPos::new(self.id, x, y),
+                    values: old,
+                });
+        }
+        y
+    }
+
     /// Returns the rendered value of the cell at the given position. This is
     /// different from calling CellValue.to_display() since it properly formats
     /// numbers. (We no longer format numbers in Rust because the client needs to
@@ -130,6 +256,7 @@ mod test {
     use std::str::FromStr;
 
     use crate::{
+        controller::{active_transactions::transaction_name::TransactionName, GridController},
         grid::{
             formats::format_update::FormatUpdate,
             sheet::validations::{validation::Validation, validation_rules::ValidationRule},
@@ -250,4 +377,150 @@ mod test {
             true,
         );
     }
+
+    #[test]
+    #[parallel]
+    fn reverse_values_ops_for_rect() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let rect = Rect::new(2, 1, 4, 30);
+        let value = |x: i64, y: i64| CellValue::Text(format!("{x},{y}"));
+
+        let sheet = gc.sheet_mut(sheet_id);
+        for pos in rect.iter() {
+            sheet.set_cell_value(pos, value(pos.x, pos.y));
+        }
+        sheet.recalculate_bounds();
+
+        assert_eq!(sheet.reverse_values_ops_for_rect(rect).len(), 1);
+
+        // 10 cells per chunk is 3 rows of the 3-wide rect
+        let ops = sheet.reverse_values_ops_for_rect_chunked(rect, 10);
+        assert_eq!(ops.len(), 10);
+        for (i, op) in ops.iter().enumerate() {
+            let Operation::SetCellValues { sheet_pos, .. } = op else {
+                panic!("Expected SetCellValues");
+            };
+            assert_eq!(sheet_pos.x, 2);
+            assert_eq!(sheet_pos.y, 1 + 3 * i as i64);
+        }
+
+        for pos in rect.iter() {
+            sheet.set_cell_value(pos, CellValue::Blank);
+        }
+        gc.start_user_transaction(ops, None, TransactionName::Unknown);
+
+        let sheet = gc.sheet(sheet_id);
+        for pos in rect.iter() {
+            assert_eq!(sheet.cell_value(pos), Some(value(pos.x, pos.y)));
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn set_row_values_runs() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet.set_cell_value(Pos { x: 5, y: 2 }, CellValue::Text("x".to_string()));
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet.set_row_values_runs(
+            &mut transaction,
+            2,
+            vec![(1..101, CellValue::Number(7.into()))],
+        );
+        for x in 1..101 {
+            assert_eq!(
+                sheet.cell_value(Pos { x, y: 2 }),
+                Some(CellValue::Number(7.into()))
+            );
+        }
+        assert_eq!(sheet.cell_value(Pos { x: 101, y: 2 }), None);
+
+        // one reverse operation that only stores the overwritten cell
+        assert_eq!(transaction.reverse_operations.len(), 1);
+        let Operation::SetCellValues { sheet_pos, values } = &transaction.reverse_operations[0]
+        else {
+            panic!("Expected SetCellValues");
+        };
+        assert_eq!(*sheet_pos, SheetPos::new(sheet_id, 1, 2));
+        assert_eq!((values.w, values.h), (100, 1));
+        assert_eq!(values.into_iter().count(), 1);
+
+        gc.start_user_transaction(
+            transaction.to_undo_transaction().operations,
+            None,
+            TransactionName::Unknown,
+        );
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 5, y: 2 }),
+            Some(CellValue::Text("x".to_string()))
+        );
+        for x in (1..101).filter(|x| *x != 5) {
+            assert_eq!(sheet.cell_value(Pos { x, y: 2 }), None);
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn append_row() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet.set_cell_value(Pos { x: 2, y: 1 }, CellValue::Text("a".to_string()));
+        sheet.set_cell_value(Pos { x: 3, y: 4 }, CellValue::Text("b".to_string()));
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        let first = sheet.append_row(
+            &mut transaction,
+            CellValues::from(vec![vec!["1"], vec!["2"]]),
+        );
+        let second = sheet.append_row(
+            &mut transaction,
+            CellValues::from(vec![vec!["3"], vec!["4"]]),
+        );
+        assert_eq!((first, second), (5, 6));
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 5 }),
+            Some(CellValue::Text("1".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 3, y: 6 }),
+            Some(CellValue::Text("4".to_string()))
+        );
+        assert_eq!(sheet.bounds(true).last_row(), Some(6));
+
+        // one reverse operation per row that clears it
+        assert_eq!(transaction.reverse_operations.len(), 2);
+        gc.start_user_transaction(
+            transaction.to_undo_transaction().operations,
+            None,
+            TransactionName::Unknown,
+        );
+        let sheet = gc.sheet(sheet_id);
+        for y in [5, 6] {
+            assert_eq!(sheet.cell_value(Pos { x: 2, y }), None);
+            assert_eq!(sheet.cell_value(Pos { x: 3, y }), None);
+        }
+        assert_eq!(
+            sheet.cell_value(Pos { x: 3, y: 4 }),
+            Some(CellValue::Text("b".to_string()))
+        );
+
+        // an empty sheet starts at 1, 1
+        let mut sheet = Sheet::test();
+        let mut transaction = PendingTransaction::default();
+        let row = sheet.append_row(&mut transaction, CellValues::from(vec![vec!["x"]]));
+        assert_eq!(row, 1);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Text("x".to_string()))
+        );
+    }
 }
@@ -71,6 +71,22 @@ impl Sheet {
         }
     }
 
+    /// Returns whether inserting or deleting the row would move a code run
+    /// (ie, a code run is anchored or outputs at or after the row).
+    pub fn row_op_affects_code(&self, row: i64) -> bool {
+        self.code_runs
+            .iter()
+            .any(|(pos, code_run)| code_run.output_rect(*pos, false).max.y >= row)
+    }
+
+    /// Returns whether inserting or deleting the column would move a code run
+    /// (ie, a code run is anchored or outputs at or after the column).
+    pub fn column_op_affects_code(&self, column: i64) -> bool {
+        self.code_runs
+            .iter()
+            .any(|(pos, code_run)| code_run.output_rect(*pos, false).max.x >= column)
+    }
+
     /// Returns the CellValue for a CodeRun (if it exists) at the Pos.
     ///
     /// Note: spill error will return a CellValue::Blank to ensure calculations can continue.
@@ -452,4 +468,23 @@ mod test {
         assert_eq!(sheet.code_rows_bounds(2, 5), Some(3..6));
         assert_eq!(sheet.code_rows_bounds(10, 10), None);
     }
+
+    #[test]
+    #[parallel]
+    fn op_affects_code() {
+        let mut sheet = Sheet::test();
+        assert!(!sheet.row_op_affects_code(0));
+        assert!(!sheet.column_op_affects_code(0));
+
+        // outputs to (2, 5) and (2, 6)
+        sheet.test_set_code_run_array(2, 5, vec!["A", "B"], true);
+
+        assert!(sheet.row_op_affects_code(3));
+        assert!(sheet.row_op_affects_code(6));
+        assert!(!sheet.row_op_affects_code(7));
+
+        assert!(sheet.column_op_affects_code(1));
+        assert!(sheet.column_op_affects_code(2));
+        assert!(!sheet.column_op_affects_code(3));
+    }
 }
@@ -4,6 +4,7 @@ use crate::{
         active_transactions::pending_transaction::PendingTransaction,
         operations::operation::{CopyFormats, Operation},
     },
+    error_core::{CoreError, Result},
     grid::{formats::Formats, Sheet},
     selection::Selection,
     Pos, Rect, SheetPos,
@@ -30,7 +31,7 @@ impl Sheet {
                     }
                 }
                 reverse_operations.push(Operation::SetCellValues {
-                    sheet_pos: crate::SheetPos::new(self.id, column, min),
+                    sheet_pos: crate::SheetPos::new(self.id, column, current_min),
                     values,
                 });
                 current_min = current_max + 1;
@@ -104,6 +105,9 @@ impl Sheet {
     }
 
     /// Deletes columns and returns the operations to undo the deletion.
+    ///
+    /// As with rows, the coordinate space is unbounded, so deleting the only
+    /// populated column leaves an empty (but valid) sheet.
     pub fn delete_column(&mut self, transaction: &mut PendingTransaction, column: i64) {
         // create undo operations for the deleted column (only when needed since
         // it's a bit expensive)
@@ -124,6 +128,10 @@ impl Sheet {
 
         self.delete_column_offset(transaction, column);
 
+        // the validations' reverse operations must run after the column is
+        // reinserted during an undo (otherwise the reinsert shifts them)
+        self.validations.remove_column(transaction, self.id, column);
+
         if transaction.is_user_undo_redo() {
             // reverse operation to create the column (this will also shift all impacted columns)
             transaction
@@ -194,35 +202,12 @@ impl Sheet {
         }
 
         // update the indices of all code_runs impacted by the deletion
-        let mut code_runs_to_move = Vec::new();
-        for (pos, _) in self.code_runs.iter() {
-            if pos.x > column {
-                code_runs_to_move.push(*pos);
-            }
-        }
-        for old_pos in code_runs_to_move {
-            if let Some(code_run) = self.code_runs.shift_remove(&old_pos) {
-                let new_pos = Pos {
-                    x: old_pos.x - 1,
-                    y: old_pos.y,
-                };
-
-                // signal html and image cells to update
-                if code_run.is_html() {
-                    transaction.add_html_cell(self.id, old_pos);
-                    transaction.add_html_cell(self.id, new_pos);
-                } else if code_run.is_image() {
-                    transaction.add_image_cell(self.id, old_pos);
-                    transaction.add_image_cell(self.id, new_pos);
-                }
-
-                self.code_runs.insert(new_pos, code_run);
-
-                // signal client to update the code runs
-                transaction.add_code_cell(self.id, old_pos);
-                transaction.add_code_cell(self.id, new_pos);
-            }
-        }
+        self.shift_code_runs(transaction, |pos| {
+            (pos.x > column).then_some(Pos {
+                x: pos.x - 1,
+                y: pos.y,
+            })
+        });
 
         // update the indices of all column-based formats impacted by the deletion
         let mut formats_to_update = Vec::new();
@@ -239,8 +224,31 @@ impl Sheet {
 
         // mark hashes of new columns dirty
         transaction.add_dirty_hashes_from_sheet_columns(self, column, None);
+    }
 
-        self.validations.remove_column(transaction, self.id, column);
+    /// Returns true if the column has any column or cell formatting.
+    fn column_has_formats(&self, column: i64) -> bool {
+        self.formats_columns.contains_key(&column)
+            || self
+                .columns
+                .get(&column)
+                .is_some_and(|c| c.format_range().is_some())
+    }
+
+    /// Resolves CopyFormats::Auto for an inserted column to the neighbor that
+    /// has formats (preferring the column to the left). This must be called
+    /// after the columns were shifted to make room for the new column.
+    fn resolve_copy_column_formats(&self, column: i64, copy_formats: CopyFormats) -> CopyFormats {
+        if copy_formats != CopyFormats::Auto {
+            return copy_formats;
+        }
+        if self.column_has_formats(column - 1) {
+            CopyFormats::Before
+        } else if self.column_has_formats(column + 1) {
+            CopyFormats::After
+        } else {
+            CopyFormats::None
+        }
     }
 
     /// Copies column formats to the new column.
@@ -256,7 +264,8 @@ impl Sheet {
         let delta = match copy_direction {
             CopyFormats::After => 1,
             CopyFormats::Before => -1,
-            CopyFormats::None => return,
+            // Auto is resolved by resolve_copy_column_formats before this is called
+            CopyFormats::None | CopyFormats::Auto => return,
         };
         if let Some(format) = self.try_format_column(column + delta) {
             self.set_formats_columns(&[column], &Formats::repeat(format.to_replace(), 1));
@@ -277,12 +286,41 @@ impl Sheet {
         }
     }
 
+    /// Returns an error if inserting a column at column would move content (or
+    /// place the new column) past max_columns.
+    pub(crate) fn check_insert_column(&self, column: i64) -> Result<()> {
+        let Some(max_columns) = self.max_columns else {
+            return Ok(());
+        };
+        let mut last = self.bounds(false).last_column();
+        if let Some(bounds) = self.borders.bounds() {
+            last = last.max(Some(bounds.max.x));
+        }
+        if let Some((column, _)) = self.formats_columns.last_key_value() {
+            last = last.max(Some(*column));
+        }
+        let new_last = match last {
+            Some(last) if last >= column => last + 1,
+            _ => column,
+        };
+        if new_last > max_columns {
+            return Err(CoreError::SheetLimitExceeded(format!(
+                "inserting column {column} would move content to column {new_last}, past the maximum of {max_columns}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Inserts a column. Returns an error, without changing the sheet, if
+    /// this would move content past max_columns.
     pub fn insert_column(
         &mut self,
         transaction: &mut PendingTransaction,
         column: i64,
         copy_formats: CopyFormats,
-    ) {
+    ) -> Result<()> {
+        self.check_insert_column(column)?;
+
         // create undo operations for the inserted column
         if transaction.is_user_undo_redo() {
             // reverse operation to delete the column (this will also shift all impacted columns)
@@ -313,35 +351,12 @@ impl Sheet {
         }
 
         // update the indices of all code_runs impacted by the insertion
-        let mut code_runs_to_move = Vec::new();
-        for (pos, _) in self.code_runs.iter() {
-            if pos.x >= column {
-                code_runs_to_move.push(*pos);
-            }
-        }
-        code_runs_to_move.reverse();
-        for old_pos in code_runs_to_move {
-            let new_pos = Pos {
-                x: old_pos.x + 1,
-                y: old_pos.y,
-            };
-            if let Some(code_run) = self.code_runs.shift_remove(&old_pos) {
-                // signal html and image cells to update
-                if code_run.is_html() {
-                    transaction.add_html_cell(self.id, old_pos);
-                    transaction.add_html_cell(self.id, new_pos);
-                } else if code_run.is_image() {
-                    transaction.add_image_cell(self.id, old_pos);
-                    transaction.add_image_cell(self.id, new_pos);
-                }
-
-                self.code_runs.insert(new_pos, code_run);
-
-                // signal the client to updates to the code cells (to draw the code arrays)
-                transaction.add_code_cell(self.id, old_pos);
-                transaction.add_code_cell(self.id, new_pos);
-            }
-        }
+        self.shift_code_runs(transaction, |pos| {
+            (pos.x >= column).then_some(Pos {
+                x: pos.x + 1,
+                y: pos.y,
+            })
+        });
 
         // update the indices of all column-based formats impacted by the deletion
         let mut formats_to_update = Vec::new();
@@ -367,6 +382,7 @@ impl Sheet {
 
         self.validations.insert_column(transaction, self.id, column);
 
+        let copy_formats = self.resolve_copy_column_formats(column, copy_formats);
         self.copy_column_formats(transaction, column, copy_formats);
 
         let changes = self.offsets.insert_column(column);
@@ -375,6 +391,38 @@ impl Sheet {
                 transaction.offsets_modified(self.id, Some(*index), None, Some(*size));
             });
         }
+        Ok(())
+    }
+
+    /// Inserts a column and fills it with values, starting at row. Only the
+    /// first column of values is used, and exactly values.h rows are written.
+    /// The DeleteColumn reverse operation from insert_column also removes the
+    /// values. Returns an error, without changing the sheet, if insert_column
+    /// would.
+    pub fn insert_column_with_values(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        column: i64,
+        row: i64,
+        values: &CellValues,
+        copy_formats: CopyFormats,
+    ) -> Result<()> {
+        self.insert_column(transaction, column, copy_formats)?;
+
+        let mut column_values = CellValues::new(1, values.h);
+        for y in 0..values.h {
+            if let Some(value) = values.get_except_blank(0, y) {
+                column_values.set(0, y, value.clone());
+            }
+        }
+        self.merge_cell_values(
+            transaction,
+            Pos { x: column, y: row },
+            &column_values,
+            false,
+        );
+        self.recalculate_bounds();
+        Ok(())
     }
 }
 
@@ -383,7 +431,10 @@ mod tests {
     use serial_test::parallel;
 
     use crate::{
-        controller::execution::TransactionType,
+        controller::{
+            active_transactions::transaction_name::TransactionName, execution::TransactionType,
+            GridController,
+        },
         grid::{
             formats::{format::Format, format_update::FormatUpdate},
             BorderStyle, CellBorderLine, CellWrap,
@@ -393,6 +444,47 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    #[parallel]
+    fn reverse_values_ops_for_column_chunks() {
+        let rows = [1, 500, 1500, 2600];
+        let mut sheet = Sheet::test();
+        for y in rows {
+            sheet.set_cell_value(Pos { x: 1, y }, y.to_string());
+        }
+        let ops = sheet.reverse_values_ops_for_column(1);
+        assert_eq!(ops.len(), 3);
+
+        // each chunk covers its own rows, so the chunks can be applied in any
+        // order (and more than once)
+        for order in [
+            vec![0, 1, 2],
+            vec![2, 1, 0],
+            vec![1, 2, 0],
+            vec![0, 1, 2, 1, 0],
+        ] {
+            let mut restored = Sheet::test();
+            for i in order {
+                let Operation::SetCellValues { sheet_pos, values } = &ops[i] else {
+                    panic!("Expected SetCellValues");
+                };
+                restored.merge_cell_values(
+                    &mut PendingTransaction::default(),
+                    (*sheet_pos).into(),
+                    values,
+                    false,
+                );
+            }
+            for y in rows {
+                assert_eq!(
+                    restored.cell_value(Pos { x: 1, y }),
+                    Some(CellValue::Text(y.to_string()))
+                );
+            }
+            assert_eq!(restored.columns[&1].values.len(), rows.len());
+        }
+    }
+
     #[test]
     #[parallel]
     fn delete_column() {
@@ -509,7 +601,9 @@ mod tests {
 
         let mut transaction = PendingTransaction::default();
 
-        sheet.insert_column(&mut transaction, 1, CopyFormats::None);
+        sheet
+            .insert_column(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
 
         assert_eq!(sheet.display_value(Pos { x: 1, y: 1 }), None);
         assert_eq!(
@@ -549,7 +643,9 @@ mod tests {
 
         let mut transaction = PendingTransaction::default();
 
-        sheet.insert_column(&mut transaction, 2, CopyFormats::None);
+        sheet
+            .insert_column(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
 
         assert_eq!(
             sheet.display_value(Pos { x: 1, y: 1 }),
@@ -574,7 +670,9 @@ mod tests {
 
         let mut transaction = PendingTransaction::default();
 
-        sheet.insert_column(&mut transaction, 3, CopyFormats::None);
+        sheet
+            .insert_column(&mut transaction, 3, CopyFormats::None)
+            .unwrap();
 
         assert_eq!(
             sheet.display_value(Pos { x: 1, y: 1 }),
@@ -606,7 +704,9 @@ mod tests {
         sheet.offsets.set_column_width(4, 400.0);
 
         let mut transaction = PendingTransaction::default();
-        sheet.insert_column(&mut transaction, 2, CopyFormats::None);
+        sheet
+            .insert_column(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
         assert_eq!(sheet.offsets.column_width(1), 100.0);
         assert_eq!(sheet.offsets.column_width(2), DEFAULT_COLUMN_WIDTH);
         assert_eq!(sheet.offsets.column_width(3), 200.0);
@@ -628,4 +728,142 @@ mod tests {
         assert_eq!(sheet.offsets.column_width(2), DEFAULT_COLUMN_WIDTH);
         assert_eq!(sheet.offsets.column_width(3), 400.0);
     }
+
+    #[test]
+    #[parallel]
+    fn insert_column_max_columns() {
+        let mut sheet = Sheet::test();
+        sheet.set_cell_value(Pos { x: 3, y: 1 }, "a");
+        sheet.recalculate_bounds();
+        sheet.formats_columns.insert(
+            4,
+            (
+                Format {
+                    bold: Some(true),
+                    ..Default::default()
+                },
+                0,
+            ),
+        );
+        sheet.max_columns = Some(4);
+
+        // the column format would move past the limit
+        let original = sheet.clone();
+        let mut transaction = PendingTransaction::default();
+        assert!(matches!(
+            sheet.insert_column(&mut transaction, 1, CopyFormats::None),
+            Err(CoreError::SheetLimitExceeded(_))
+        ));
+        assert_eq!(sheet, original);
+        assert!(transaction.reverse_operations.is_empty());
+
+        sheet.max_columns = Some(5);
+        sheet
+            .insert_column(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+        assert_eq!(
+            sheet.cell_value(Pos { x: 4, y: 1 }),
+            Some(CellValue::Text("a".to_string()))
+        );
+        assert_eq!(sheet.format_column(5).bold, Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_column_with_values() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.sheet_mut(sheet_id).test_set_values(
+            1,
+            1,
+            2,
+            4,
+            vec!["A", "B", "C", "D", "E", "F", "G", "H"],
+        );
+        let original = gc.sheet(sheet_id).clone();
+
+        let mut transaction = PendingTransaction {
+            transaction_type: TransactionType::User,
+            ..Default::default()
+        };
+        let values = CellValues::from(vec![vec!["X", "Y", "Z"]]);
+        gc.sheet_mut(sheet_id)
+            .insert_column_with_values(&mut transaction, 2, 1, &values, CopyFormats::None)
+            .unwrap();
+
+        let sheet = gc.sheet(sheet_id);
+        for (y, value) in ["X", "Y", "Z"].iter().enumerate() {
+            assert_eq!(
+                sheet.cell_value(Pos {
+                    x: 2,
+                    y: y as i64 + 1
+                }),
+                Some(CellValue::Text(value.to_string()))
+            );
+        }
+        // exactly values.h rows are written
+        assert_eq!(sheet.cell_value(Pos { x: 2, y: 4 }), None);
+        // the old column 2 moved right
+        assert_eq!(
+            sheet.cell_value(Pos { x: 3, y: 1 }),
+            Some(CellValue::Text("B".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 3, y: 4 }),
+            Some(CellValue::Text("H".to_string()))
+        );
+
+        gc.start_user_transaction(
+            transaction.to_undo_transaction().operations,
+            None,
+            TransactionName::Unknown,
+        );
+        let sheet = gc.sheet(sheet_id);
+        for x in 1..=3 {
+            for y in 1..=4 {
+                let pos = Pos { x, y };
+                assert_eq!(sheet.cell_value(pos), original.cell_value(pos));
+            }
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn column_ops_code_runs_out_of_order() {
+        // code_runs is in insertion order, so the run on the right is added
+        // first to make the shifts visit the runs out of position order
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_single(3, 1, CellValue::Number(3.into()));
+        sheet.test_set_code_run_single(2, 1, CellValue::Number(2.into()));
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_column(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.code_runs.len(), 2);
+        assert_eq!(
+            sheet.display_value(Pos { x: 3, y: 1 }),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 4, y: 1 }),
+            Some(CellValue::Number(3.into()))
+        );
+
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_single(4, 1, CellValue::Number(4.into()));
+        sheet.test_set_code_run_single(3, 1, CellValue::Number(3.into()));
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_column(&mut transaction, 2);
+        assert_eq!(sheet.code_runs.len(), 2);
+        assert_eq!(
+            sheet.display_value(Pos { x: 2, y: 1 }),
+            Some(CellValue::Number(3.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 3, y: 1 }),
+            Some(CellValue::Number(4.into()))
+        );
+    }
 }
@@ -1,4 +1,75 @@
+use crate::{controller::active_transactions::pending_transaction::PendingTransaction, Pos};
+
+use super::Sheet;
+
 pub mod column;
 pub mod row;
 
 pub const MAX_OPERATION_SIZE_COL_ROW: i64 = 1000;
+
+/// Selects what a new row inherits from the neighbor chosen by its
+/// CopyFormats. The default copies only formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CopyOptions {
+    pub values: bool,
+    pub formats: bool,
+    pub borders: bool,
+    pub height: bool,
+}
+
+impl Default for CopyOptions {
+    fn default() -> Self {
+        CopyOptions {
+            values: false,
+            formats: true,
+            borders: false,
+            height: false,
+        }
+    }
+}
+
+impl Sheet {
+    /// Moves each code run to the position returned by new_pos (None leaves
+    /// the run where it is), and signals the client to update the old and new
+    /// positions. new_pos must not move two runs to the same position, or a
+    /// run onto one that stays.
+    ///
+    /// The runs keep their order in code_runs, so the indices stored in
+    /// SetCodeRun reverse operations still match once the shift is undone.
+    ///
+    /// Returns the number of runs that moved.
+    fn shift_code_runs(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        new_pos: impl Fn(Pos) -> Option<Pos>,
+    ) -> usize {
+        let sheet_id = self.id;
+        let mut moved = 0;
+        let code_runs = std::mem::take(&mut self.code_runs);
+        self.code_runs = code_runs
+            .into_iter()
+            .map(|(old_pos, code_run)| {
+                let Some(pos) = new_pos(old_pos) else {
+                    return (old_pos, code_run);
+                };
+                moved += 1;
+
+                // signal html and image cells to update
+                if code_run.is_html() {
+                    transaction.add_html_cell(sheet_id, old_pos);
+                    transaction.add_html_cell(sheet_id, pos);
+                } else if code_run.is_image() {
+                    transaction.add_image_cell(sheet_id, old_pos);
+                    transaction.add_image_cell(sheet_id, pos);
+                }
+
+                // signal the client to update the code cells (to draw the code arrays)
+                transaction.add_code_cell(sheet_id, old_pos);
+                transaction.add_code_cell(sheet_id, pos);
+
+                (pos, code_run)
+            })
+            .collect();
+        moved
+    }
+}
@@ -4,36 +4,56 @@ use crate::{
     cell_values::CellValues,
     controller::{
         active_transactions::pending_transaction::PendingTransaction,
-        operations::operation::{CopyFormats, Operation},
+        operations::{
+            clipboard::{Clipboard, ClipboardOrigin, ClipboardSheetFormats},
+            operation::{CopyFormats, Operation, ShiftDirection},
+        },
     },
-    grid::{formats::Formats, GridBounds, Sheet},
+    error_core::{CoreError, Result},
+    grid::{formats::Formats, BorderStyle, CodeRun, GridBounds, Sheet},
     selection::Selection,
-    Pos, Rect, SheetPos,
+    CellValue, Pos, Rect, SheetPos,
 };
 
-use super::MAX_OPERATION_SIZE_COL_ROW;
+use super::{CopyOptions, MAX_OPERATION_SIZE_COL_ROW};
+
+/// The content of a row removed by Sheet::cut_row.
+#[derive(Debug)]
+pub struct RowSnapshot {
+    /// The row's values, formats, and borders (as used by paste_rows_insert).
+    pub clipboard: Clipboard,
+
+    pub height: f64,
+
+    /// Code runs anchored in the row, with their column.
+    pub code_runs: Vec<(i64, CodeRun)>,
+}
 
 impl Sheet {
     // create reverse operations for values in the row broken up by MAX_OPERATION_SIZE
     fn reverse_values_ops_for_row(&self, row: i64) -> Vec<Operation> {
         let mut reverse_operations = Vec::new();
 
-        if let Some((min, max)) = self.row_bounds(row, true) {
-            let mut current_min = min;
-            while current_min <= max {
-                let current_max = (current_min + MAX_OPERATION_SIZE_COL_ROW).min(max);
-                let mut values = CellValues::new((current_max - current_min) as u32 + 1, 1);
-                for x in current_min..=current_max {
-                    if let Some(cell) = self.cell_value(Pos { x, y: row }) {
-                        values.set((x - current_min) as u32, 0, cell);
-                    }
-                }
-                reverse_operations.push(Operation::SetCellValues {
-                    sheet_pos: SheetPos::new(self.id, min, row),
-                    values,
-                });
-                current_min = current_max + 1;
+        // only populated cells are visited, so each chunk starts at the next
+        // populated cell instead of covering blank runs
+        let cells = self.row_cells_iter(row).collect::<Vec<_>>();
+        let mut start = 0;
+        while start < cells.len() {
+            let min = cells[start].0;
+            let mut end = start;
+            while end + 1 < cells.len() && cells[end + 1].0 <= min + MAX_OPERATION_SIZE_COL_ROW {
+                end += 1;
             }
+            let max = cells[end].0;
+            let mut values = CellValues::new((max - min) as u32 + 1, 1);
+            for (x, cell) in &cells[start..=end] {
+                values.set((x - min) as u32, 0, (*cell).clone());
+            }
+            reverse_operations.push(Operation::SetCellValues {
+                sheet_pos: SheetPos::new(self.id, min, row),
+                values,
+            });
+            start = end + 1;
         }
 
         reverse_operations
@@ -41,6 +61,12 @@ impl Sheet {
 
     /// Creates reverse operations for cell formatting within the row.
     fn reverse_formats_ops_for_row(&self, row: i64) -> Vec<Operation> {
+        // format_bounds covers all cell formats, so a sheet without row or
+        // cell formats has nothing to restore
+        if self.formats_rows.is_empty() && self.format_bounds.is_empty() {
+            return vec![];
+        }
+
         let mut formats = Formats::new();
         let mut selection = Selection::new(self.id);
 
@@ -84,30 +110,15 @@ impl Sheet {
     }
 
     /// Removes any value at row and shifts the remaining values up by 1.
-    fn delete_and_shift_values(&mut self, row: i64) {
+    fn delete_and_shift_values(&mut self, transaction: &mut PendingTransaction, row: i64) {
         // use the sheet bounds to determine the approximate bounds for the impacted range
         if let GridBounds::NonEmpty(bounds) = self.bounds(true) {
             for x in bounds.min.x..=bounds.max.x {
                 if let Some(column) = self.columns.get_mut(&x) {
-                    if column.values.contains_key(&row) {
-                        column.values.remove(&row);
-                    }
-
-                    let mut keys_to_move: Vec<i64> = column
-                        .values
-                        .keys()
-                        .filter(|&key| *key > row)
-                        .cloned()
-                        .collect();
+                    column.values.remove(&row);
 
-                    keys_to_move.sort_unstable();
-
-                    // Move up remaining values
-                    for key in keys_to_move {
-                        if let Some(value) = column.values.remove(&key) {
-                            column.values.insert(key - 1, value);
-                        }
-                    }
+                    // move up remaining values
+                    transaction.metrics.cells_shifted += column.shift_values(row + 1, -1);
                 }
             }
         }
@@ -118,22 +129,28 @@ impl Sheet {
         if let GridBounds::NonEmpty(bounds) = self.bounds(false) {
             for x in bounds.min.x..=bounds.max.x {
                 if let Some(column) = self.columns.get_mut(&x) {
-                    column.align.remove_and_shift_left(row);
-                    column.vertical_align.remove_and_shift_left(row);
-                    column.wrap.remove_and_shift_left(row);
-                    column.numeric_format.remove_and_shift_left(row);
-                    column.numeric_decimals.remove_and_shift_left(row);
-                    column.numeric_commas.remove_and_shift_left(row);
-                    column.bold.remove_and_shift_left(row);
-                    column.italic.remove_and_shift_left(row);
-                    column.text_color.remove_and_shift_left(row);
-                    if column.fill_color.remove_and_shift_left(row) {
+                    let fill_color = column.fill_color.remove_and_shift_left(row);
+                    if fill_color {
                         transaction.fill_cells.insert(self.id);
                     }
-                    column.render_size.remove_and_shift_left(row);
-                    column.date_time.remove_and_shift_left(row);
-                    column.underline.remove_and_shift_left(row);
-                    column.strike_through.remove_and_shift_left(row);
+                    let shifted = [
+                        column.align.remove_and_shift_left(row),
+                        column.vertical_align.remove_and_shift_left(row),
+                        column.wrap.remove_and_shift_left(row),
+                        column.numeric_format.remove_and_shift_left(row),
+                        column.numeric_decimals.remove_and_shift_left(row),
+                        column.numeric_commas.remove_and_shift_left(row),
+                        column.bold.remove_and_shift_left(row),
+                        column.italic.remove_and_shift_left(row),
+                        column.text_color.remove_and_shift_left(row),
+                        fill_color,
+                        column.render_size.remove_and_shift_left(row),
+                        column.date_time.remove_and_shift_left(row),
+                        column.underline.remove_and_shift_left(row),
+                        column.strike_through.remove_and_shift_left(row),
+                    ];
+                    transaction.metrics.formats_shifted +=
+                        shifted.iter().filter(|shifted| **shifted).count();
                 }
             }
         }
@@ -161,6 +178,34 @@ impl Sheet {
         }
     }
 
+    /// Deletes the offsets of many rows at once (see delete_row_offset).
+    fn delete_rows_offsets(&mut self, transaction: &mut PendingTransaction, rows: &[i64]) {
+        let (changed, removed) = self.offsets.delete_rows(rows);
+
+        // pushed from the largest row down to match deleting the rows one at
+        // a time, so each resize runs after its row is reinserted on undo
+        for (row, new_size) in removed.into_iter().rev() {
+            transaction.reverse_operations.push(Operation::ResizeRow {
+                sheet_id: self.id,
+                row,
+                new_size,
+                client_resized: false,
+            });
+        }
+        if !changed.is_empty() && !transaction.is_server() {
+            let offsets_modified = transaction.offsets_modified.entry(self.id).or_default();
+            changed.iter().for_each(|(index, size)| {
+                offsets_modified.insert((None, Some(*index)), *size);
+            });
+        }
+    }
+
+    /// Deletes a row and shifts all rows below it up by 1.
+    ///
+    /// The sheet's coordinate space is unbounded, so deleting a row can never
+    /// remove the last remaining row: an empty row always takes the place of
+    /// the row at the end of the sheet. No guard is needed, and deleting the
+    /// only populated row leaves an empty (but valid) sheet.
     pub fn delete_row(&mut self, transaction: &mut PendingTransaction, row: i64) {
         // create undo operations for the deleted column (only when needed since
         // it's a bit expensive)
@@ -180,6 +225,14 @@ impl Sheet {
         }
 
         self.delete_row_offset(transaction, row);
+        self.delete_row_and_shift(transaction, row);
+    }
+
+    /// Deletes a row's content (values, formats, code runs, and borders) and
+    /// shifts the rows below it up. The caller is responsible for the reverse
+    /// operations for that content and for the row offsets.
+    fn delete_row_and_shift(&mut self, transaction: &mut PendingTransaction, row: i64) {
+        let shift_bounds = self.can_shift_bounds_rows(row, true);
 
         // remove the row's code runs from the sheet
         self.code_runs.retain(|pos, code_run| {
@@ -211,45 +264,35 @@ impl Sheet {
         // remove the column's borders from the sheet
         if self.borders.remove_row(row) {
             transaction.sheet_borders.insert(self.id);
+            transaction.metrics.borders_shifted += 1;
         }
 
         // update all cells that were impacted by the deletion
-        self.delete_and_shift_values(row);
+        #[cfg(feature = "bench")]
+        let start = std::time::Instant::now();
+        self.delete_and_shift_values(transaction, row);
+        #[cfg(feature = "bench")]
+        transaction.record_timing("delete_and_shift_values", start);
 
         // update the indices of all code_runs impacted by the deletion
-        let mut code_runs_to_move = Vec::new();
-        for (pos, _) in self.code_runs.iter() {
-            if pos.y > row {
-                code_runs_to_move.push(*pos);
-            }
-        }
-        code_runs_to_move.sort_unstable();
-        for old_pos in code_runs_to_move {
-            if let Some(code_run) = self.code_runs.shift_remove(&old_pos) {
-                let new_pos = Pos {
-                    x: old_pos.x,
-                    y: old_pos.y - 1,
-                };
-
-                // signal html and image cells to update
-                if code_run.is_html() {
-                    transaction.add_html_cell(self.id, old_pos);
-                    transaction.add_html_cell(self.id, new_pos);
-                } else if code_run.is_image() {
-                    transaction.add_image_cell(self.id, old_pos);
-                    transaction.add_image_cell(self.id, new_pos);
-                }
-
-                self.code_runs.insert(new_pos, code_run);
-
-                // signal client to update the code runs
-                transaction.add_code_cell(self.id, old_pos);
-                transaction.add_code_cell(self.id, new_pos);
-            }
-        }
+        #[cfg(feature = "bench")]
+        let start = std::time::Instant::now();
+        let moved = self.shift_code_runs(transaction, |pos| {
+            (pos.y > row).then_some(Pos {
+                x: pos.x,
+                y: pos.y - 1,
+            })
+        });
+        transaction.metrics.code_runs_reindexed += moved;
+        #[cfg(feature = "bench")]
+        transaction.record_timing("delete_row_code_runs", start);
 
         // update the indices of all column-based formats impacted by the deletion
+        #[cfg(feature = "bench")]
+        let start = std::time::Instant::now();
         self.formats_remove_and_shift_up(transaction, row);
+        #[cfg(feature = "bench")]
+        transaction.record_timing("formats_remove_and_shift_up", start);
 
         // update the indices of all row-based formats impacted by the deletion
         let mut formats_to_update = Vec::new();
@@ -270,6 +313,10 @@ impl Sheet {
         // mark hashes of new rows dirty
         transaction.add_dirty_hashes_from_sheet_rows(self, row, None);
 
+        // the validations' reverse operations must run after the row is
+        // reinserted during an undo (otherwise the reinsert shifts them)
+        self.validations.remove_row(transaction, self.id, row);
+
         // reverse operation to create the column (this will also shift all impacted columns)
         transaction.reverse_operations.push(Operation::InsertRow {
             sheet_id: self.id,
@@ -277,30 +324,508 @@ impl Sheet {
             copy_formats: CopyFormats::None,
         });
 
-        self.validations.remove_row(transaction, self.id, row);
+        if shift_bounds {
+            self.shift_bounds_rows(row, -1);
+        } else {
+            self.recalculate_bounds();
+        }
     }
 
-    /// Removes any value at row and shifts the remaining values up by 1.
+    /// Creates reverse operations for the content of many rows. Adjacent rows
+    /// share a single set of operations, so this is smaller than calling the
+    /// single-row builders for each row.
+    fn reverse_content_ops_for_rows(&self, rows: &[i64]) -> Vec<Operation> {
+        // runs of adjacent rows (rows is sorted and deduplicated)
+        let mut runs: Vec<(i64, i64)> = Vec::new();
+        for &row in rows {
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == row => *end = row,
+                _ => runs.push((row, row)),
+            }
+        }
+
+        let mut reverse_operations = Vec::new();
+
+        // values, broken up by MAX_OPERATION_SIZE like the single-row version
+        for &(start, end) in runs.iter() {
+            let mut columns: Vec<i64> = (start..=end)
+                .flat_map(|row| self.row_cells_iter(row).map(|(x, _)| x))
+                .collect();
+            columns.sort_unstable();
+            columns.dedup();
+            let mut i = 0;
+            while i < columns.len() {
+                let min = columns[i];
+                let mut j = i;
+                while j + 1 < columns.len() && columns[j + 1] <= min + MAX_OPERATION_SIZE_COL_ROW {
+                    j += 1;
+                }
+                let max = columns[j];
+                let mut values = CellValues::new((max - min) as u32 + 1, (end - start) as u32 + 1);
+                for x in min..=max {
+                    if let Some(column) = self.columns.get(&x) {
+                        for (y, value) in column.values.range(start..=end) {
+                            values.set((x - min) as u32, (y - start) as u32, value.clone());
+                        }
+                    }
+                }
+                reverse_operations.push(Operation::SetCellValues {
+                    sheet_pos: SheetPos::new(self.id, min, start),
+                    values,
+                });
+                i = j + 1;
+            }
+        }
+
+        // cell formats (rects are filled column by column)
+        let mut formats = Formats::new();
+        let mut rects = Vec::new();
+        for &(start, end) in runs.iter() {
+            let bounds = (start..=end)
+                .filter_map(|row| self.row_bounds_formats(row))
+                .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)));
+            if let Some((min, max)) = bounds {
+                for x in min..=max {
+                    for y in start..=end {
+                        formats.push(self.format_cell(x, y, false).to_replace());
+                    }
+                }
+                rects.push(Rect::new(min, start, max, end));
+            }
+        }
+        if !rects.is_empty() {
+            let mut selection = Selection::new(self.id);
+            selection.rects = Some(rects);
+            reverse_operations.push(Operation::SetCellFormatsSelection { selection, formats });
+        }
+
+        // row formats are pushed after the cell formats so they are restored
+        // first during an undo
+        let mut formats = Formats::new();
+        let mut formatted_rows = Vec::new();
+        for &row in rows {
+            if let Some(format) = self.try_format_row(row) {
+                formats.push(format.to_replace());
+                formatted_rows.push(row);
+            }
+        }
+        if !formatted_rows.is_empty() {
+            let mut selection = Selection::new(self.id);
+            selection.rows = Some(formatted_rows);
+            reverse_operations.push(Operation::SetCellFormatsSelection { selection, formats });
+        }
+
+        self.code_runs
+            .iter()
+            .enumerate()
+            .for_each(|(index, (pos, code_run))| {
+                if rows.binary_search(&pos.y).is_ok() {
+                    reverse_operations.push(Operation::SetCodeRun {
+                        sheet_pos: SheetPos::new(self.id, pos.x, pos.y),
+                        code_run: Some(code_run.clone()),
+                        index,
+                    });
+                }
+            });
+
+        reverse_operations.extend(self.borders.get_rows_ops(self.id, rows));
+
+        reverse_operations
+    }
+
+    /// Deletes many rows, shifting the rows below each of them up. The reverse
+    /// operations for the rows' content are created once for all rows.
+    pub fn delete_rows(&mut self, transaction: &mut PendingTransaction, rows: &[i64]) {
+        let mut rows = rows.to_vec();
+        rows.sort_unstable();
+        rows.dedup();
+
+        if transaction.is_user_undo_redo() {
+            transaction
+                .reverse_operations
+                .extend(self.reverse_content_ops_for_rows(&rows));
+        }
+
+        // the offsets are shifted once for all rows (their reverse operations
+        // must come before the rows' InsertRow reverse operations)
+        self.delete_rows_offsets(transaction, &rows);
+
+        // delete from the bottom so the remaining rows keep their positions
+        for &row in rows.iter().rev() {
+            self.delete_row_and_shift(transaction, row);
+        }
+    }
+
+    /// Deletes the rows in the inclusive range (start, end), shifting the rows
+    /// below it up. Unlike delete_rows, the content below the range is
+    /// shifted once by the size of the range (instead of once per row). The
+    /// reverse operations for the range's content are created once and undo
+    /// restores the whole range.
+    pub fn delete_row_range(&mut self, transaction: &mut PendingTransaction, start: i64, end: i64) {
+        if start > end {
+            return;
+        }
+        let rows = (start..=end).collect::<Vec<_>>();
+        let count = end - start + 1;
+
+        if transaction.is_user_undo_redo() {
+            transaction
+                .reverse_operations
+                .extend(self.reverse_content_ops_for_rows(&rows));
+        }
+
+        // the offsets' reverse operations must come before the rows' InsertRow
+        // reverse operations
+        self.delete_rows_offsets(transaction, &rows);
+
+        // remove the range's code runs from the sheet
+        self.code_runs.retain(|pos, code_run| {
+            if (start..=end).contains(&pos.y) {
+                transaction.add_code_cell(self.id, *pos);
+
+                // signal that html and image cells are removed
+                if code_run.is_html() {
+                    transaction.add_html_cell(self.id, *pos);
+                } else if code_run.is_image() {
+                    transaction.add_image_cell(self.id, *pos);
+                }
+                false
+            } else {
+                true
+            }
+        });
+
+        // mark hashes of existing rows dirty
+        transaction.add_dirty_hashes_from_sheet_rows(self, start, None);
+
+        // remove the range's borders and shift the borders below it up
+        if self.borders.remove_row_range(start, end) {
+            transaction.sheet_borders.insert(self.id);
+            transaction.metrics.borders_shifted += 1;
+        }
+
+        // remove the range's values and formats and shift the ones below it up
+        for column in self.columns.values_mut() {
+            let removed = column
+                .values
+                .range(start..=end)
+                .map(|(y, _)| *y)
+                .collect::<Vec<_>>();
+            for y in removed {
+                column.values.remove(&y);
+            }
+            transaction.metrics.cells_shifted += column.shift_values(end + 1, -count);
+
+            let range = start..end + 1;
+            let fill_color = column.fill_color.remove_range_and_shift_left(range.clone());
+            if fill_color {
+                transaction.fill_cells.insert(self.id);
+            }
+            let shifted = [
+                column.align.remove_range_and_shift_left(range.clone()),
+                column
+                    .vertical_align
+                    .remove_range_and_shift_left(range.clone()),
+                column.wrap.remove_range_and_shift_left(range.clone()),
+                column
+                    .numeric_format
+                    .remove_range_and_shift_left(range.clone()),
+                column
+                    .numeric_decimals
+                    .remove_range_and_shift_left(range.clone()),
+                column
+                    .numeric_commas
+                    .remove_range_and_shift_left(range.clone()),
+                column.bold.remove_range_and_shift_left(range.clone()),
+                column.italic.remove_range_and_shift_left(range.clone()),
+                column.text_color.remove_range_and_shift_left(range.clone()),
+                fill_color,
+                column
+                    .render_size
+                    .remove_range_and_shift_left(range.clone()),
+                column.date_time.remove_range_and_shift_left(range.clone()),
+                column.underline.remove_range_and_shift_left(range.clone()),
+                column.strike_through.remove_range_and_shift_left(range),
+            ];
+            transaction.metrics.formats_shifted +=
+                shifted.iter().filter(|shifted| **shifted).count();
+        }
+
+        // update the indices of all code_runs impacted by the deletion
+        let moved = self.shift_code_runs(transaction, |pos| {
+            (pos.y > end).then_some(Pos {
+                x: pos.x,
+                y: pos.y - count,
+            })
+        });
+        transaction.metrics.code_runs_reindexed += moved;
+
+        // remove the range's row formats and shift the row formats below it up
+        // (forwards because we're shifting up)
+        let formats_to_update = self
+            .formats_rows
+            .range(start..)
+            .map(|(r, _)| *r)
+            .collect::<Vec<_>>();
+        for row in formats_to_update {
+            if let Some(format) = self.formats_rows.remove(&row) {
+                if format.0.fill_color.is_some() {
+                    transaction.fill_cells.insert(self.id);
+                }
+                if row > end {
+                    self.formats_rows.insert(row - count, format);
+                }
+            }
+        }
+
+        // mark hashes of new rows dirty
+        transaction.add_dirty_hashes_from_sheet_rows(self, start, None);
+
+        // the validations' reverse operations must run after the rows are
+        // reinserted during an undo (otherwise the reinsert shifts them)
+        self.validations
+            .remove_row_range(transaction, self.id, start, end);
+
+        // reverse operations to recreate the rows (these also shift the rows
+        // below the range down)
+        for _ in &rows {
+            transaction.reverse_operations.push(Operation::InsertRow {
+                sheet_id: self.id,
+                row: start,
+                copy_formats: CopyFormats::None,
+            });
+        }
+
+        self.recalculate_bounds();
+    }
+
+    /// Deletes every empty row (see row_is_empty) in the inclusive range,
+    /// shifting the content below each one up. Uses delete_rows, so undo
+    /// restores all of the rows together.
+    pub fn compact_rows(&mut self, transaction: &mut PendingTransaction, range: (i64, i64)) {
+        let rows = (range.0..=range.1)
+            .filter(|row| self.row_is_empty(*row))
+            .collect::<Vec<_>>();
+        if !rows.is_empty() {
+            self.delete_rows(transaction, &rows);
+        }
+    }
+
+    /// Returns the first row with content at or above row (or row if nothing
+    /// is above it). Deleting row with ShiftDirection::Down moves the rows
+    /// from there to row - 1 down by 1.
+    pub fn shift_down_top(&self, row: i64) -> i64 {
+        let mut top = row;
+        if let GridBounds::NonEmpty(bounds) = self.bounds(false) {
+            top = top.min(bounds.min.y);
+        }
+        if let Some(bounds) = self.borders.bounds() {
+            top = top.min(bounds.min.y);
+        }
+        if let Some((first, _)) = self.formats_rows.first_key_value() {
+            top = top.min(*first);
+        }
+        top
+    }
+
+    /// Deletes a row and shifts all rows above it down by 1 (see
+    /// ShiftDirection::Down).
+    ///
+    /// This is a delete_row followed by an insert_row at the first row with
+    /// content (or at row if nothing is above it), so the rows below row end
+    /// up where they started. Each step pushes its own reverse operations.
+    pub fn delete_row_shift_down(&mut self, transaction: &mut PendingTransaction, row: i64) {
+        let top = self.shift_down_top(row);
+
+        // content only moves back into the deleted row, so max_rows does not
+        // need to be checked
+        self.delete_row(transaction, row);
+        self.insert_row_and_shift(
+            transaction,
+            top,
+            CopyFormats::None,
+            CopyOptions::default(),
+            true,
+        );
+    }
+
+    /// Deletes a row (shifting in the given direction), but code runs anchored
+    /// in the row leave the rest of their output behind as static values
+    /// instead of removing it.
+    pub fn delete_row_freeze_code_runs(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        shift: ShiftDirection,
+    ) {
+        // rows below the deleted row only move when shifting up
+        let delta = match shift {
+            ShiftDirection::Up => -1,
+            ShiftDirection::Down => 0,
+        };
+        let frozen: Vec<(Pos, CellValues)> = self
+            .code_runs
+            .iter()
+            .filter(|(pos, code_run)| pos.y == row && !code_run.spill_error)
+            .filter_map(|(pos, code_run)| {
+                let output = code_run.output_rect(*pos, false);
+                if output.max.y <= row {
+                    return None;
+                }
+                let w = output.width();
+                let h = (output.max.y - row) as u32;
+                let mut values = CellValues::new(w, h);
+                for x in 0..w {
+                    for y in 0..h {
+                        if let Some(value) = code_run.cell_value_at(x, y + 1) {
+                            values.set(x, y, value);
+                        }
+                    }
+                }
+                Some((
+                    Pos {
+                        x: pos.x,
+                        y: row + 1 + delta,
+                    },
+                    values,
+                ))
+            })
+            .collect();
+
+        match shift {
+            ShiftDirection::Up => self.delete_row(transaction, row),
+            ShiftDirection::Down => self.delete_row_shift_down(transaction, row),
+        }
+
+        if frozen.is_empty() {
+            return;
+        }
+
+        // the reverse operations run before the row is reinserted during an
+        // undo, so they clear the values at their shifted positions
+        for (pos, values) in frozen {
+            let old_values = self.merge_cell_values(transaction, pos, &values, false);
+            if transaction.is_user_undo_redo() {
+                transaction
+                    .reverse_operations
+                    .push(Operation::SetCellValues {
+                        sheet_pos: pos.to_sheet_pos(self.id),
+                        values: old_values,
+                    });
+            }
+        }
+        self.recalculate_bounds();
+    }
+
+    /// Deletes the values and code runs within rect and shifts the values and
+    /// code runs below it up, but only within the rect's columns. Other
+    /// columns are left untouched.
+    pub fn delete_cells_shift_up(&mut self, transaction: &mut PendingTransaction, rect: Rect) {
+        // the last value in the rect's columns bounds the impacted region
+        let Some(max_y) = rect
+            .x_range()
+            .filter_map(|x| self.columns.get(&x).and_then(|c| c.values_range()))
+            .map(|range| range.end - 1)
+            .max()
+        else {
+            return;
+        };
+        if max_y < rect.min.y {
+            return;
+        }
+        let impacted = Rect::new(rect.min.x, rect.min.y, rect.max.x, max_y);
+
+        // restoring the original values over the impacted region undoes the shift
+        if transaction.is_user_undo_redo() {
+            let mut values = CellValues::new(impacted.width(), impacted.height());
+            for x in impacted.x_range() {
+                if let Some(column) = self.columns.get(&x) {
+                    for (y, value) in column.values.range(impacted.y_range()) {
+                        values.set(
+                            (x - impacted.min.x) as u32,
+                            (y - impacted.min.y) as u32,
+                            value.clone(),
+                        );
+                    }
+                }
+            }
+            transaction
+                .reverse_operations
+                .push(Operation::SetCellValues {
+                    sheet_pos: SheetPos::new(self.id, impacted.min.x, impacted.min.y),
+                    values,
+                });
+
+            // reverse operations run last-first: the shifted code runs are
+            // removed, then the original ones are restored in index order
+            let code_runs = self
+                .code_runs
+                .iter()
+                .enumerate()
+                .filter(|(_, (pos, _))| rect.x_range().contains(&pos.x) && pos.y >= rect.min.y)
+                .collect::<Vec<_>>();
+            for (index, (pos, code_run)) in code_runs.iter().rev() {
+                transaction.reverse_operations.push(Operation::SetCodeRun {
+                    sheet_pos: pos.to_sheet_pos(self.id),
+                    code_run: Some((*code_run).clone()),
+                    index: *index,
+                });
+            }
+            for (index, (pos, _)) in code_runs {
+                if pos.y > rect.max.y {
+                    transaction.reverse_operations.push(Operation::SetCodeRun {
+                        sheet_pos: SheetPos::new(self.id, pos.x, pos.y - rect.height() as i64),
+                        code_run: None,
+                        index,
+                    });
+                }
+            }
+        }
+
+        // remove the code runs within the rect
+        self.code_runs.retain(|pos, code_run| {
+            if rect.contains(*pos) {
+                transaction.add_code_cell(self.id, *pos);
+                if code_run.is_html() {
+                    transaction.add_html_cell(self.id, *pos);
+                } else if code_run.is_image() {
+                    transaction.add_image_cell(self.id, *pos);
+                }
+                false
+            } else {
+                true
+            }
+        });
+
+        for x in rect.x_range() {
+            if let Some(column) = self.columns.get_mut(&x) {
+                let mut below = column.values.split_off(&rect.min.y);
+                let mut remaining = below.split_off(&(rect.max.y + 1));
+                column.values.append(&mut remaining);
+                column.shift_values(rect.max.y + 1, -(rect.height() as i64));
+            }
+        }
+
+        // move the code runs below the rect up with their values
+        self.shift_code_runs(transaction, |pos| {
+            (rect.x_range().contains(&pos.x) && pos.y > rect.max.y).then_some(Pos {
+                x: pos.x,
+                y: pos.y - rect.height() as i64,
+            })
+        });
+
+        transaction.add_dirty_hashes_from_sheet_rect(impacted.to_sheet_rect(self.id));
+        self.recalculate_bounds();
+    }
+
+    /// Shifts all values at or after row down by 1.
     fn insert_and_shift_values(&mut self, row: i64) {
         // use the sheet bounds to determine the approximate bounds for the impacted range
         if let GridBounds::NonEmpty(bounds) = self.bounds(true) {
             for x in bounds.min.x..=bounds.max.x {
                 if let Some(column) = self.columns.get_mut(&x) {
-                    let mut keys_to_move: Vec<i64> = column
-                        .values
-                        .keys()
-                        .filter(|&key| *key >= row)
-                        .cloned()
-                        .collect();
-
-                    keys_to_move.sort_unstable_by(|a, b| b.cmp(a));
-
-                    // Move down values
-                    for key in keys_to_move {
-                        if let Some(value) = column.values.remove(&key) {
-                            column.values.insert(key + 1, value);
-                        }
-                    }
+                    // move down values
+                    column.shift_values(row, 1);
                 }
             }
         }
@@ -332,8 +857,32 @@ impl Sheet {
         }
     }
 
+    /// Returns true if the row has any row or cell formatting.
+    fn row_has_formats(&self, row: i64) -> bool {
+        self.formats_rows.contains_key(&row) || self.row_bounds_formats(row).is_some()
+    }
+
+    /// Resolves CopyFormats::Auto for an inserted row to the neighbor that has
+    /// formats (preferring the row above). This must be called after the rows
+    /// were shifted to make room for the new row.
+    fn resolve_copy_row_formats(&self, row: i64, copy_formats: CopyFormats) -> CopyFormats {
+        if copy_formats != CopyFormats::Auto {
+            return copy_formats;
+        }
+        if self.row_has_formats(row - 1) {
+            CopyFormats::Before
+        } else if self.row_has_formats(row + 1) {
+            CopyFormats::After
+        } else {
+            CopyFormats::None
+        }
+    }
+
     /// Copies row formats to the new row.
     ///
+    /// Rows are unbounded (negative rows are valid), so CopyFormats::Before
+    /// at the top of the data reads an empty row and copies nothing.
+    ///
     /// We don't need reverse operations since the updated column will be
     /// deleted during an undo.
     fn copy_row_formats(
@@ -345,7 +894,8 @@ impl Sheet {
         let delta = match copy_formats {
             CopyFormats::After => 1,
             CopyFormats::Before => -1,
-            CopyFormats::None => return,
+            // Auto is resolved by resolve_copy_row_formats before this is called
+            CopyFormats::None | CopyFormats::Auto => return,
         };
         if let Some((min, max)) = self.row_bounds_formats(row + delta) {
             for x in min..=max {
@@ -356,6 +906,8 @@ impl Sheet {
                     self.set_format_cell(Pos { x, y: row }, &format.to_replace(), false);
                 }
             }
+            self.format_bounds.add(Pos { x: min, y: row });
+            self.format_bounds.add(Pos { x: max, y: row });
         }
         if let Some((format, _)) = self.formats_rows.get(&(row + delta)) {
             if format.fill_color.is_some() {
@@ -366,64 +918,175 @@ impl Sheet {
         }
     }
 
-    pub fn insert_row(
+    /// Copies the values, borders, and height of the neighboring row (per
+    /// copy_formats) to the new row, as selected by options. Formats are
+    /// copied by copy_row_formats.
+    ///
+    /// We don't need reverse operations since the new row will be deleted
+    /// during an undo.
+    fn copy_row_options(
         &mut self,
         transaction: &mut PendingTransaction,
         row: i64,
         copy_formats: CopyFormats,
+        options: CopyOptions,
     ) {
-        // create undo operations for the inserted column
-        if transaction.is_user_undo_redo() {
-            // reverse operation to delete the row (this will also shift all impacted rows)
-            transaction.reverse_operations.push(Operation::DeleteRow {
-                sheet_id: self.id,
-                row,
-            });
-        }
-
-        // mark hashes of existing rows dirty
-        transaction.add_dirty_hashes_from_sheet_rows(self, row, None);
-
-        self.insert_and_shift_values(row);
-
-        // update the indices of all code_runs impacted by the insertion
-        let mut code_runs_to_move = Vec::new();
-        for (pos, _) in self.code_runs.iter() {
-            if pos.y >= row {
-                code_runs_to_move.push(*pos);
+        let source = match copy_formats {
+            CopyFormats::After => row + 1,
+            CopyFormats::Before => row - 1,
+            CopyFormats::None | CopyFormats::Auto => return,
+        };
+        if options.values {
+            if let Some((min, max)) = self.row_bounds(source, true) {
+                for x in min..=max {
+                    // code cells are not copied since they would need to be run
+                    match self.cell_value(Pos { x, y: source }) {
+                        Some(CellValue::Code(_)) | None => (),
+                        Some(value) => {
+                            self.set_cell_value(Pos { x, y: row }, value);
+                        }
+                    }
+                }
+                self.recalculate_add_bounds(Rect::new(min, row, max, row), false);
             }
         }
-        code_runs_to_move.reverse();
-
-        for old_pos in code_runs_to_move {
-            let new_pos = Pos {
-                x: old_pos.x,
-                y: old_pos.y + 1,
-            };
-            if let Some(code_run) = self.code_runs.shift_remove(&old_pos) {
-                // signal html and image cells to update
-                if code_run.is_html() {
-                    transaction.add_html_cell(self.id, old_pos);
-                    transaction.add_html_cell(self.id, new_pos);
-                } else if code_run.is_image() {
-                    transaction.add_image_cell(self.id, old_pos);
-                    transaction.add_image_cell(self.id, new_pos);
+        if options.borders {
+            let mut changed = false;
+            if let Some(bounds) = self.borders.bounds_row(source, false, false) {
+                for x in bounds.min.x..=bounds.max.x {
+                    let border = self.borders.get(x, source);
+                    if !border.is_empty() {
+                        self.borders.apply_update(x, row, border.into());
+                        changed = true;
+                    }
+                }
+            }
+            if let Some(border) = self.borders.rows.get(&source).copied() {
+                self.borders.rows.insert(row, border);
+                changed = true;
+            }
+            if changed {
+                transaction.sheet_borders.insert(self.id);
+            }
+        }
+        if options.height {
+            let height = self.offsets.row_height(source);
+            if height != self.offsets.row_height(row) {
+                self.offsets.set_row_height(row, height);
+                if !transaction.is_server() {
+                    transaction.offsets_modified(self.id, None, Some(row), Some(height));
                 }
+            }
+        }
+    }
 
-                self.code_runs.insert(new_pos, code_run);
+    /// Returns an error if inserting count rows at row would move content (or
+    /// place the new rows) past max_rows.
+    pub(crate) fn check_insert_rows(&self, row: i64, count: i64) -> Result<()> {
+        let Some(max_rows) = self.max_rows else {
+            return Ok(());
+        };
+        let mut last = self.bounds(false).last_row();
+        if let Some(bounds) = self.borders.bounds() {
+            last = last.max(Some(bounds.max.y));
+        }
+        if let Some((row, _)) = self.formats_rows.last_key_value() {
+            last = last.max(Some(*row));
+        }
+        let new_last = match last {
+            Some(last) if last >= row => last + count,
+            _ => row + count - 1,
+        };
+        if new_last > max_rows {
+            return Err(CoreError::SheetLimitExceeded(format!(
+                "inserting {count} row(s) at {row} would move content to row {new_last}, past the maximum of {max_rows}"
+            )));
+        }
+        Ok(())
+    }
 
-                // signal the client to updates to the code cells (to draw the code arrays)
-                transaction.add_code_cell(self.id, old_pos);
-                transaction.add_code_cell(self.id, new_pos);
-            }
+    /// Inserts a row. Returns an error, without changing the sheet, if this
+    /// would move content past max_rows.
+    pub fn insert_row(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        copy_formats: CopyFormats,
+    ) -> Result<()> {
+        self.insert_row_with_options(transaction, row, copy_formats, CopyOptions::default())
+    }
+
+    /// Inserts a row, copying what options selects from the neighboring row
+    /// chosen by copy_formats. Returns an error, without changing the sheet,
+    /// if this would move content past max_rows.
+    pub fn insert_row_with_options(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        copy_formats: CopyFormats,
+        options: CopyOptions,
+    ) -> Result<()> {
+        self.check_insert_rows(row, 1)?;
+        self.insert_row_and_shift(transaction, row, copy_formats, options, true);
+        Ok(())
+    }
+
+    /// Inserts a row and shifts the rows below it down. If shift_offsets is
+    /// false, the caller shifts the row offsets.
+    fn insert_row_and_shift(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        copy_formats: CopyFormats,
+        options: CopyOptions,
+        shift_offsets: bool,
+    ) {
+        let shift_bounds = self.can_shift_bounds_rows(row, false);
+
+        // create undo operations for the inserted column
+        if transaction.is_user_undo_redo() {
+            // reverse operation to delete the row (this will also shift all impacted rows)
+            transaction.reverse_operations.push(Operation::DeleteRow {
+                sheet_id: self.id,
+                row,
+                shift: ShiftDirection::Up,
+                freeze_on_delete: false,
+            });
         }
 
+        // mark hashes of existing rows dirty
+        transaction.add_dirty_hashes_from_sheet_rows(self, row, None);
+
+        #[cfg(feature = "bench")]
+        let start = std::time::Instant::now();
+        self.insert_and_shift_values(row);
+        #[cfg(feature = "bench")]
+        transaction.record_timing("insert_and_shift_values", start);
+
+        // update the indices of all code_runs impacted by the insertion
+        #[cfg(feature = "bench")]
+        let start = std::time::Instant::now();
+        let moved = self.shift_code_runs(transaction, |pos| {
+            (pos.y >= row).then_some(Pos {
+                x: pos.x,
+                y: pos.y + 1,
+            })
+        });
+        transaction.metrics.code_runs_reindexed += moved;
+        #[cfg(feature = "bench")]
+        transaction.record_timing("insert_row_code_runs", start);
+
         // update the indices of all column-based formats impacted by the deletion
+        #[cfg(feature = "bench")]
+        let start = std::time::Instant::now();
         self.formats_insert_and_shift_down(row, transaction);
+        #[cfg(feature = "bench")]
+        transaction.record_timing("formats_insert_and_shift_down", start);
 
         // signal client to update the borders for changed columns
         if self.borders.insert_row(row) {
             transaction.sheet_borders.insert(self.id);
+            transaction.metrics.borders_shifted += 1;
         }
 
         // update the indices of all column-based formats impacted by the deletion
@@ -440,19 +1103,389 @@ impl Sheet {
             }
         }
 
+        self.validations.insert_row(transaction, self.id, row);
+
+        if shift_bounds {
+            self.shift_bounds_rows(row, 1);
+        } else {
+            self.recalculate_bounds();
+        }
+
         // mark hashes of new rows dirty
         transaction.add_dirty_hashes_from_sheet_rows(self, row, None);
 
-        self.validations.insert_row(transaction, self.id, row);
-
-        self.copy_row_formats(transaction, row, copy_formats);
+        let copy_formats = self.resolve_copy_row_formats(row, copy_formats);
+        if options.formats {
+            self.copy_row_formats(transaction, row, copy_formats);
+        }
 
-        let changes = self.offsets.insert_row(row);
-        if !changes.is_empty() {
+        if shift_offsets {
+            let changes = self.offsets.insert_row(row);
             changes.iter().for_each(|(index, size)| {
                 transaction.offsets_modified(self.id, None, Some(*index), Some(*size));
             });
         }
+
+        self.copy_row_options(transaction, row, copy_formats, options);
+    }
+
+    /// Inserts `heights.len()` rows starting at row, and sets each new row's
+    /// height to the corresponding entry in heights. Returns an error,
+    /// without changing the sheet, if this would move content past max_rows.
+    pub fn insert_rows_with_heights(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        heights: &[f64],
+        copy_formats: CopyFormats,
+    ) -> Result<()> {
+        self.check_insert_rows(row, heights.len() as i64)?;
+
+        let changes = self.offsets.insert_rows(row, heights.len() as i64);
+        changes.iter().for_each(|(index, size)| {
+            transaction.offsets_modified(self.id, None, Some(*index), Some(*size));
+        });
+
+        for (i, height) in heights.iter().enumerate() {
+            let new_row = row + i as i64;
+            self.insert_row_and_shift(
+                transaction,
+                new_row,
+                copy_formats,
+                CopyOptions::default(),
+                false,
+            );
+            self.offsets.set_row_height(new_row, *height);
+            if !transaction.is_server() {
+                transaction.offsets_modified(self.id, None, Some(new_row), Some(*height));
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts `clipboard.h` rows at row and pastes the clipboard's values,
+    /// formats, and borders into the new rows (keeping the clipboard's
+    /// columns). This is used to insert copied rows.
+    ///
+    /// The DeleteRow reverse operations from insert_row also remove the
+    /// pasted content, so no other reverse operations are needed. Code cells
+    /// are pasted but not run. Returns an error, without changing the sheet,
+    /// if this would move content past max_rows.
+    pub fn paste_rows_insert(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        clipboard: &Clipboard,
+    ) -> Result<()> {
+        if clipboard.h == 0 {
+            return Ok(());
+        }
+        let h = clipboard.h as i64;
+        self.check_insert_rows(row, h)?;
+
+        for i in 0..h {
+            self.insert_row_and_shift(
+                transaction,
+                row + i,
+                CopyFormats::None,
+                CopyOptions::default(),
+                true,
+            );
+        }
+
+        let pos = Pos {
+            x: clipboard.origin.x,
+            y: row,
+        };
+        if clipboard.w > 0 {
+            self.merge_cell_values(transaction, pos, &clipboard.cells, false);
+
+            let rect = Rect::from_numbers(pos.x, pos.y, clipboard.w as i64, h);
+            self.set_formats_selection(&Selection::rect(rect, self.id), &clipboard.formats);
+        }
+        for (r, format) in clipboard.sheet_formats.rows.iter() {
+            if (0..h).contains(r) {
+                self.set_formats_rows(&[row + r], &Formats::repeat(format.clone().into(), 1));
+            }
+        }
+
+        if let Some((selection, borders)) = clipboard.borders.as_ref() {
+            self.borders
+                .set_borders(&selection.translate(pos.x, pos.y), borders);
+            transaction.sheet_borders.insert(self.id);
+        }
+
+        self.recalculate_bounds();
+        Ok(())
+    }
+
+    /// Deletes a row (shifting the rows below it up) and returns its content,
+    /// so it can be placed elsewhere with paste_row_snapshot.
+    pub fn cut_row(&mut self, transaction: &mut PendingTransaction, row: i64) -> RowSnapshot {
+        let mut bounds = GridBounds::default();
+        if let Some((min, max)) = self.row_bounds(row, false) {
+            bounds.add(Pos { x: min, y: row });
+            bounds.add(Pos { x: max, y: row });
+        }
+        if let Some(rect) = self.borders.bounds_row(row, false, false) {
+            bounds.add_rect(rect);
+        }
+
+        let mut origin = ClipboardOrigin {
+            y: row,
+            ..Default::default()
+        };
+        let clipboard = if let GridBounds::NonEmpty(rect) = bounds {
+            origin.x = rect.min.x;
+            let w = rect.width();
+            let mut cells = CellValues::new(w, 1);
+            let mut values = CellValues::new(w, 1);
+            for (i, x) in rect.x_range().enumerate() {
+                let pos = Pos { x, y: row };
+                if let Some(value) = self.cell_value(pos) {
+                    cells.set(i as u32, 0, value);
+                }
+                if let Some(value) = self.display_value(pos) {
+                    values.set(i as u32, 0, value);
+                }
+            }
+            let selection = Selection::rect(rect, self.id);
+            let borders = self.borders.to_clipboard(&selection).map(|borders| {
+                (
+                    Selection::rect(Rect::new(0, 0, w as i64 - 1, 0), self.id),
+                    borders,
+                )
+            });
+            Clipboard {
+                w,
+                h: 1,
+                cells,
+                values,
+                formats: self.override_cell_formats(rect, None),
+                sheet_formats: ClipboardSheetFormats {
+                    rows: self
+                        .try_format_row(row)
+                        .map(|format| (0, format))
+                        .into_iter()
+                        .collect(),
+                    ..Default::default()
+                },
+                borders,
+                origin,
+                selection: None,
+                validations: None,
+            }
+        } else {
+            Clipboard {
+                w: 0,
+                h: 1,
+                cells: CellValues::default(),
+                values: CellValues::default(),
+                formats: Formats::default(),
+                sheet_formats: ClipboardSheetFormats::default(),
+                borders: None,
+                origin,
+                selection: None,
+                validations: None,
+            }
+        };
+
+        let code_runs = self
+            .code_runs
+            .iter()
+            .filter(|(pos, _)| pos.y == row)
+            .map(|(pos, code_run)| (pos.x, code_run.clone()))
+            .collect();
+        let height = self.offsets.row_height(row);
+
+        self.delete_row(transaction, row);
+
+        RowSnapshot {
+            clipboard,
+            height,
+            code_runs,
+        }
+    }
+
+    /// Inserts a row at row with the content of a RowSnapshot. A single
+    /// DeleteRow undoes this. Returns an error, without changing the sheet,
+    /// if this would move content past max_rows.
+    pub fn paste_row_snapshot(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        row: i64,
+        snapshot: &RowSnapshot,
+    ) -> Result<()> {
+        self.paste_rows_insert(transaction, row, &snapshot.clipboard)?;
+
+        self.offsets.set_row_height(row, snapshot.height);
+        if !transaction.is_server() {
+            transaction.offsets_modified(self.id, None, Some(row), Some(snapshot.height));
+        }
+
+        for (x, code_run) in snapshot.code_runs.iter() {
+            let pos = Pos { x: *x, y: row };
+            if code_run.is_html() {
+                transaction.add_html_cell(self.id, pos);
+            } else if code_run.is_image() {
+                transaction.add_image_cell(self.id, pos);
+            }
+            self.code_runs.insert(pos, code_run.clone());
+            transaction.add_code_cell(self.id, pos);
+        }
+
+        self.recalculate_bounds();
+        Ok(())
+    }
+
+    /// Swaps the formats of two rows within each column.
+    fn formats_swap_rows(&mut self, transaction: &mut PendingTransaction, a: i64, b: i64) {
+        for column in self.columns.values_mut() {
+            column.align.swap(a, b);
+            column.vertical_align.swap(a, b);
+            column.wrap.swap(a, b);
+            column.numeric_format.swap(a, b);
+            column.numeric_decimals.swap(a, b);
+            column.numeric_commas.swap(a, b);
+            column.bold.swap(a, b);
+            column.italic.swap(a, b);
+            column.text_color.swap(a, b);
+            if column.fill_color.swap(a, b) {
+                transaction.fill_cells.insert(self.id);
+            }
+            column.render_size.swap(a, b);
+            column.date_time.swap(a, b);
+            column.underline.swap(a, b);
+            column.strike_through.swap(a, b);
+        }
+
+        let format_a = self.formats_rows.remove(&a);
+        let format_b = self.formats_rows.remove(&b);
+        for (row, format) in [(b, format_a), (a, format_b)] {
+            if let Some(format) = format {
+                if format.0.fill_color.is_some() {
+                    transaction.fill_cells.insert(self.id);
+                }
+                self.formats_rows.insert(row, format);
+            }
+        }
+    }
+
+    /// Swaps two rows, including values, formats, borders, row heights, and
+    /// code runs. A swap is its own inverse, so the reverse operation is the
+    /// same swap.
+    ///
+    /// Code runs are moved by their anchor. A code run whose output spills
+    /// past its row keeps the same output shape at its new anchor; the output
+    /// is not split between the two rows.
+    pub fn swap_rows(&mut self, transaction: &mut PendingTransaction, a: i64, b: i64) {
+        if a == b {
+            return;
+        }
+
+        // mark hashes of the old rows dirty
+        transaction.add_dirty_hashes_from_sheet_rows(self, a, Some(a));
+        transaction.add_dirty_hashes_from_sheet_rows(self, b, Some(b));
+
+        for column in self.columns.values_mut() {
+            let value_a = column.values.remove(&a);
+            let value_b = column.values.remove(&b);
+            if let Some(value) = value_a {
+                column.values.insert(b, value);
+            }
+            if let Some(value) = value_b {
+                column.values.insert(a, value);
+            }
+        }
+
+        self.formats_swap_rows(transaction, a, b);
+
+        if self.borders.swap_rows(a, b) {
+            transaction.sheet_borders.insert(self.id);
+        }
+
+        let height_a = self.offsets.row_height(a);
+        let height_b = self.offsets.row_height(b);
+        if height_a != height_b {
+            self.offsets.set_row_height(a, height_b);
+            self.offsets.set_row_height(b, height_a);
+            if !transaction.is_server() {
+                transaction.offsets_modified(self.id, None, Some(a), Some(height_b));
+                transaction.offsets_modified(self.id, None, Some(b), Some(height_a));
+            }
+        }
+
+        // move the code runs anchored in either row (keeping their order)
+        self.shift_code_runs(transaction, |pos| {
+            if pos.y == a {
+                Some(Pos { x: pos.x, y: b })
+            } else if pos.y == b {
+                Some(Pos { x: pos.x, y: a })
+            } else {
+                None
+            }
+        });
+
+        // mark hashes of the new rows dirty
+        transaction.add_dirty_hashes_from_sheet_rows(self, a, Some(a));
+        transaction.add_dirty_hashes_from_sheet_rows(self, b, Some(b));
+
+        if transaction.is_user_undo_redo() {
+            transaction.reverse_operations.push(Operation::SwapRows {
+                sheet_id: self.id,
+                row_a: a,
+                row_b: b,
+            });
+        }
+    }
+
+    /// Serializes a row for export as
+    /// `{ "row", "height", "cells": [{ "x", "value", "format", "borders" }] }`.
+    /// Cells are listed in ascending x and only when they have a value, a
+    /// non-default effective format, or a rendered border. Borders are
+    /// returned without timestamps so the output is stable.
+    pub fn row_to_json(&self, row: i64) -> serde_json::Value {
+        let mut range = self.row_bounds(row, false);
+        if let Some(bounds) = self.borders.bounds_row(row, false, false) {
+            if bounds.min.x <= bounds.max.x {
+                range = Some(match range {
+                    Some((min, max)) => (min.min(bounds.min.x), max.max(bounds.max.x)),
+                    None => (bounds.min.x, bounds.max.x),
+                });
+            }
+        }
+
+        let mut cells = vec![];
+        if let Some((min, max)) = range {
+            for x in min..=max {
+                let value = self.display_value(Pos { x, y: row });
+                let format = self.format_cell(x, row, true);
+                let border = self.effective_border(x, row);
+                let borders = [border.top, border.bottom, border.left, border.right]
+                    .map(|side| side.map(BorderStyle::from));
+                if value.is_none() && format.is_default() && borders.iter().all(Option::is_none) {
+                    continue;
+                }
+                let [top, bottom, left, right] = borders;
+                cells.push(serde_json::json!({
+                    "x": x,
+                    "value": value,
+                    "format": format,
+                    "borders": {
+                        "top": top,
+                        "bottom": bottom,
+                        "left": left,
+                        "right": right,
+                    },
+                }));
+            }
+        }
+
+        serde_json::json!({
+            "row": row,
+            "height": self.offsets.row_height(row),
+            "cells": cells,
+        })
     }
 }
 
@@ -461,12 +1494,16 @@ mod test {
     use serial_test::parallel;
 
     use crate::{
-        controller::execution::TransactionType,
+        controller::{
+            active_transactions::pending_transaction::TransactionMetrics,
+            active_transactions::transaction_name::TransactionName, execution::TransactionType,
+            GridController,
+        },
         grid::{
             formats::{format::Format, format_update::FormatUpdate},
-            BorderStyle, CellBorderLine, CellWrap,
+            BorderSelection, BorderStyle, CellBorderLine, CellWrap, RenderSize,
         },
-        CellValue, DEFAULT_ROW_HEIGHT,
+        CellValue, SheetRect, DEFAULT_ROW_HEIGHT,
     };
 
     use super::*;
@@ -485,7 +1522,7 @@ mod test {
             ],
         );
         sheet.calculate_bounds();
-        sheet.delete_and_shift_values(1);
+        sheet.delete_and_shift_values(&mut PendingTransaction::default(), 1);
         assert_eq!(
             sheet.cell_value(Pos { x: 1, y: 1 }),
             Some(CellValue::Text("E".to_string()))
@@ -494,81 +1531,381 @@ mod test {
 
     #[test]
     #[parallel]
-    fn delete_row() {
-        // will delete row 1
+    fn delete_cells_shift_up() {
         let mut sheet = Sheet::test();
         sheet.test_set_values(
             1,
             1,
-            4,
-            4,
-            vec![
-                "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P",
-            ],
-        );
-        sheet.test_set_format(
-            1,
-            2,
-            FormatUpdate {
-                fill_color: Some(Some("red".to_string())),
-                ..Default::default()
-            },
-        );
-        sheet.test_set_format(
-            2,
-            2,
-            FormatUpdate {
-                wrap: Some(Some(CellWrap::Clip)),
-                ..Default::default()
-            },
-        );
-        sheet.test_set_format(
             3,
-            2,
-            FormatUpdate {
-                fill_color: Some(Some("blue".to_string())),
-                ..Default::default()
-            },
+            3,
+            vec!["A", "B", "C", "D", "E", "F", "G", "H", "I"],
         );
-        sheet.test_set_code_run_array(1, 3, vec!["=A1", "=A2"], false);
-        sheet.test_set_code_run_array(1, 4, vec!["=A1", "=A2"], false);
+        sheet.calculate_bounds();
 
-        sheet.set_formats_rows(
-            &[1],
-            &Formats::repeat(
-                FormatUpdate {
-                    bold: Some(Some(true)),
-                    italic: Some(Some(true)),
-                    ..Default::default()
-                },
-                1,
-            ),
-        );
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_cells_shift_up(&mut transaction, Rect::new(1, 1, 2, 1));
 
-        sheet.set_formats_rows(
-            &[2],
-            &Formats::repeat(
-                FormatUpdate {
-                    bold: Some(Some(false)),
-                    italic: Some(Some(false)),
-                    ..Default::default()
-                },
-                1,
-            ),
+        // columns 1 and 2 shifted up
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Text("D".to_string()))
         );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 2 }),
+            Some(CellValue::Text("H".to_string()))
+        );
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 3 }), None);
+        assert_eq!(sheet.cell_value(Pos { x: 2, y: 3 }), None);
 
-        sheet.calculate_bounds();
+        // column 3 is untouched
+        assert_eq!(
+            sheet.cell_value(Pos { x: 3, y: 1 }),
+            Some(CellValue::Text("C".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 3, y: 3 }),
+            Some(CellValue::Text("I".to_string()))
+        );
 
-        let mut transaction = PendingTransaction {
-            transaction_type: TransactionType::User,
-            ..Default::default()
+        // reverse operation restores the original values
+        assert_eq!(transaction.reverse_operations.len(), 1);
+        let Operation::SetCellValues { sheet_pos, values } =
+            transaction.reverse_operations[0].clone()
+        else {
+            panic!("Expected SetCellValues");
         };
-        sheet.delete_row(&mut transaction, 1);
-        assert_eq!(transaction.reverse_operations.len(), 3);
-
+        sheet.merge_cell_values(&mut transaction, sheet_pos.into(), &values, false);
         assert_eq!(
             sheet.cell_value(Pos { x: 1, y: 1 }),
-            Some(CellValue::Text("E".to_string()))
+            Some(CellValue::Text("A".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 3 }),
+            Some(CellValue::Text("H".to_string()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_cells_shift_up_code_runs() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(
+            1,
+            1,
+            3,
+            3,
+            vec!["A", "B", "C", "D", "E", "F", "G", "H", "I"],
+        );
+        sheet.test_set_code_run_array(1, 5, vec!["1"], false);
+        sheet.test_set_code_run_array(2, 1, vec!["2"], false);
+        sheet.test_set_code_run_array(3, 5, vec!["3"], false);
+        sheet.calculate_bounds();
+        let below = sheet.code_run(Pos { x: 1, y: 5 }).unwrap().clone();
+        let inside = sheet.code_run(Pos { x: 2, y: 1 }).unwrap().clone();
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_cells_shift_up(&mut transaction, Rect::new(1, 1, 2, 1));
+
+        // the code run below the rect moves up with its code cell
+        assert!(sheet.code_run(Pos { x: 1, y: 5 }).is_none());
+        assert_eq!(sheet.code_run(Pos { x: 1, y: 4 }), Some(&below));
+        assert!(matches!(
+            sheet.cell_value(Pos { x: 1, y: 4 }),
+            Some(CellValue::Code(_))
+        ));
+
+        // the code run in the rect is removed; the one outside the rect's
+        // columns stays put, and the order is kept
+        assert!(sheet.code_run(Pos { x: 2, y: 1 }).is_none());
+        assert_eq!(
+            sheet.code_runs.keys().copied().collect::<Vec<_>>(),
+            vec![Pos { x: 1, y: 4 }, Pos { x: 3, y: 5 }]
+        );
+
+        // undo removes the moved run and restores both runs at their indices
+        assert_eq!(
+            transaction.reverse_operations[1..],
+            [
+                Operation::SetCodeRun {
+                    sheet_pos: SheetPos::new(sheet.id, 2, 1),
+                    code_run: Some(inside),
+                    index: 1,
+                },
+                Operation::SetCodeRun {
+                    sheet_pos: SheetPos::new(sheet.id, 1, 5),
+                    code_run: Some(below),
+                    index: 0,
+                },
+                Operation::SetCodeRun {
+                    sheet_pos: SheetPos::new(sheet.id, 1, 4),
+                    code_run: None,
+                    index: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn swap_rows_code_runs_order() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_array(1, 2, vec!["1"], false);
+        sheet.test_set_code_run_array(1, 5, vec!["2"], false);
+        sheet.test_set_code_run_array(1, 10, vec!["3"], false);
+
+        let mut transaction = PendingTransaction::default();
+        sheet.swap_rows(&mut transaction, 2, 10);
+        assert_eq!(
+            transaction.reverse_operations,
+            vec![Operation::SwapRows {
+                sheet_id: sheet.id,
+                row_a: 2,
+                row_b: 10,
+            }]
+        );
+
+        // the runs swap anchors but keep their indices in code_runs
+        assert_eq!(
+            sheet.code_runs.keys().copied().collect::<Vec<_>>(),
+            vec![Pos { x: 1, y: 10 }, Pos { x: 1, y: 5 }, Pos { x: 1, y: 2 }]
+        );
+
+        // no reverse operations outside of user, undo, and redo transactions
+        let mut transaction = PendingTransaction {
+            transaction_type: TransactionType::Server,
+            ..Default::default()
+        };
+        sheet.swap_rows(&mut transaction, 2, 10);
+        assert!(transaction.reverse_operations.is_empty());
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_metrics() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 1, 2, 3, vec!["A", "B", "C", "D", "E", "F"]);
+        sheet.test_set_format(
+            1,
+            3,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_code_run_array(3, 3, vec!["1"], false);
+        sheet.calculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 2);
+        assert_eq!(
+            transaction.metrics(),
+            TransactionMetrics {
+                // E and F, plus the code cell's value
+                cells_shifted: 3,
+                // bold in column 1
+                formats_shifted: 1,
+                code_runs_reindexed: 1,
+                borders_shifted: 0,
+            }
+        );
+    }
+
+    #[cfg(feature = "bench")]
+    #[test]
+    #[parallel]
+    fn insert_row_timings() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 1, 2, 3, vec!["A", "B", "C", "D", "E", "F"]);
+        sheet.test_set_format(
+            1,
+            3,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_code_run_array(3, 3, vec!["1"], false);
+        sheet.calculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        let names = transaction
+            .timings()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "insert_and_shift_values",
+                "insert_row_code_runs",
+                "formats_insert_and_shift_down",
+            ]
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 2);
+        let names = transaction
+            .timings()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "delete_and_shift_values",
+                "delete_row_code_runs",
+                "formats_remove_and_shift_up",
+            ]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn shift_values_dense_column() {
+        let mut sheet = Sheet::test();
+        for y in 1..=10_000 {
+            sheet.set_cell_value(Pos { x: 1, y }, CellValue::Number(y.into()));
+        }
+        sheet.calculate_bounds();
+
+        sheet.delete_and_shift_values(&mut PendingTransaction::default(), 5_000);
+        let column = sheet.columns.get(&1).unwrap();
+        assert_eq!(column.values.len(), 9_999);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 4_999 }),
+            Some(CellValue::Number(4_999.into()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 5_000 }),
+            Some(CellValue::Number(5_001.into()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 9_999 }),
+            Some(CellValue::Number(10_000.into()))
+        );
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 10_000 }), None);
+
+        sheet.insert_and_shift_values(1);
+        let column = sheet.columns.get(&1).unwrap();
+        assert_eq!(column.values.len(), 9_999);
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 1 }), None);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Number(1.into()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 10_000 }),
+            Some(CellValue::Number(10_000.into()))
+        );
+        for (y, value) in column.values.iter() {
+            let expected = if *y <= 5_000 { *y - 1 } else { *y };
+            assert_eq!(value, &CellValue::Number(expected.into()));
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_only_row() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 1, 3, 1, vec!["A", "B", "C"]);
+        sheet.offsets.set_row_height(1, 100.0);
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 1);
+        sheet.recalculate_bounds();
+
+        assert_eq!(sheet.bounds(false), GridBounds::Empty);
+        assert_eq!(sheet.offsets.row_height(1), DEFAULT_ROW_HEIGHT);
+
+        // the row is still usable after the delete
+        sheet.set_cell_value(Pos { x: 1, y: 1 }, "D");
+        sheet.recalculate_bounds();
+        assert_eq!(
+            sheet.bounds(false),
+            GridBounds::NonEmpty(Rect::new(1, 1, 1, 1))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row() {
+        // will delete row 1
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(
+            1,
+            1,
+            4,
+            4,
+            vec![
+                "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P",
+            ],
+        );
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                fill_color: Some(Some("red".to_string())),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_format(
+            2,
+            2,
+            FormatUpdate {
+                wrap: Some(Some(CellWrap::Clip)),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_format(
+            3,
+            2,
+            FormatUpdate {
+                fill_color: Some(Some("blue".to_string())),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_code_run_array(1, 3, vec!["=A1", "=A2"], false);
+        sheet.test_set_code_run_array(1, 4, vec!["=A1", "=A2"], false);
+
+        sheet.set_formats_rows(
+            &[1],
+            &Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    italic: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+
+        sheet.set_formats_rows(
+            &[2],
+            &Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(false)),
+                    italic: Some(Some(false)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+
+        sheet.calculate_bounds();
+
+        let mut transaction = PendingTransaction {
+            transaction_type: TransactionType::User,
+            ..Default::default()
+        };
+        sheet.delete_row(&mut transaction, 1);
+        assert_eq!(transaction.reverse_operations.len(), 3);
+
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Text("E".to_string()))
         );
         assert_eq!(
             sheet.format_cell(3, 1, false),
@@ -616,7 +1953,9 @@ mod test {
 
         let mut transaction = PendingTransaction::default();
 
-        sheet.insert_row(&mut transaction, 1, CopyFormats::None);
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
 
         assert_eq!(sheet.display_value(Pos { x: 1, y: 1 }), None);
         assert_eq!(
@@ -656,6 +1995,55 @@ mod test {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn insert_row_start_formats_rows() {
+        let mut sheet = Sheet::test();
+        sheet.set_formats_rows(
+            &[1],
+            &Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+        sheet.set_formats_rows(
+            &[2],
+            &Formats::repeat(
+                FormatUpdate {
+                    italic: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+
+        let mut prepended = sheet.clone();
+        let mut transaction = PendingTransaction::default();
+        prepended
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+
+        assert!(!prepended.formats_rows.contains_key(&1));
+        assert_eq!(prepended.formats_rows.get(&2).unwrap().0.bold, Some(true));
+        assert_eq!(prepended.formats_rows.get(&3).unwrap().0.italic, Some(true));
+        assert_eq!(prepended.format_cell(1, 1, true), Format::default());
+
+        // with CopyFormats::After, the new row 1 takes the formats of the
+        // row that was pushed down to row 2
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::After)
+            .unwrap();
+
+        assert_eq!(sheet.formats_rows.get(&1).unwrap().0.bold, Some(true));
+        assert_eq!(sheet.formats_rows.get(&2).unwrap().0.bold, Some(true));
+        assert_eq!(sheet.formats_rows.get(&3).unwrap().0.italic, Some(true));
+        assert_eq!(sheet.formats_rows.get(&3).unwrap().0.bold, None);
+    }
+
     #[test]
     #[parallel]
     fn insert_row_middle() {
@@ -664,7 +2052,9 @@ mod test {
 
         let mut transaction = PendingTransaction::default();
 
-        sheet.insert_row(&mut transaction, 2, CopyFormats::None);
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
 
         assert_eq!(
             sheet.display_value(Pos { x: 1, y: 1 }),
@@ -689,7 +2079,9 @@ mod test {
 
         let mut transaction = PendingTransaction::default();
 
-        sheet.insert_row(&mut transaction, 3, CopyFormats::None);
+        sheet
+            .insert_row(&mut transaction, 3, CopyFormats::None)
+            .unwrap();
 
         assert_eq!(
             sheet.display_value(Pos { x: 1, y: 1 }),
@@ -713,32 +2105,1141 @@ mod test {
 
     #[test]
     #[parallel]
-    fn insert_row_offset() {
+    fn values_ops_for_sparse_row() {
         let mut sheet = Sheet::test();
-        sheet.offsets.set_row_height(1, 100.0);
-        sheet.offsets.set_row_height(2, 200.0);
-        sheet.offsets.set_row_height(4, 400.0);
+        sheet.set_cell_value(Pos { x: 1, y: 1 }, "a");
+        sheet.set_cell_value(Pos { x: 3, y: 1 }, "b");
+        sheet.set_cell_value(Pos { x: 5_000, y: 1 }, "c");
+        sheet.calculate_bounds();
 
-        let mut transaction = PendingTransaction::default();
-        sheet.insert_row(&mut transaction, 2, CopyFormats::None);
-        assert_eq!(sheet.offsets.row_height(1), 100.0);
-        assert_eq!(sheet.offsets.row_height(2), DEFAULT_ROW_HEIGHT);
-        assert_eq!(sheet.offsets.row_height(3), 200.0);
-        assert_eq!(sheet.offsets.row_height(5), 400.0);
+        let ops = sheet.reverse_values_ops_for_row(1);
+        assert_eq!(ops.len(), 2);
+        let Operation::SetCellValues { sheet_pos, values } = &ops[0] else {
+            panic!("Expected SetCellValues");
+        };
+        assert_eq!(*sheet_pos, SheetPos::new(sheet.id, 1, 1));
+        assert_eq!(values.w, 3);
+        let Operation::SetCellValues { sheet_pos, values } = &ops[1] else {
+            panic!("Expected SetCellValues");
+        };
+        assert_eq!(*sheet_pos, SheetPos::new(sheet.id, 5_000, 1));
+        assert_eq!(values.w, 1);
     }
 
     #[test]
     #[parallel]
-    fn delete_column_offset() {
+    fn insert_row_auto_copy_formats_above() {
         let mut sheet = Sheet::test();
-        sheet.offsets.set_row_height(1, 100.0);
-        sheet.offsets.set_row_height(2, 200.0);
-        sheet.offsets.set_row_height(4, 400.0);
+        sheet.test_set_format(
+            1,
+            1,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
 
         let mut transaction = PendingTransaction::default();
-        sheet.delete_row(&mut transaction, 2);
-        assert_eq!(sheet.offsets.row_height(1), 100.0);
-        assert_eq!(sheet.offsets.row_height(2), DEFAULT_ROW_HEIGHT);
-        assert_eq!(sheet.offsets.row_height(3), 400.0);
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::Auto)
+            .unwrap();
+        assert_eq!(sheet.format_cell(1, 2, false).bold, Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_auto_copy_formats_below() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                italic: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::Auto)
+            .unwrap();
+        assert_eq!(sheet.format_cell(1, 2, false).italic, Some(true));
+        assert_eq!(sheet.format_cell(1, 3, false).italic, Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_auto_copy_formats_both() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_format(
+            1,
+            1,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                italic: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::Auto)
+            .unwrap();
+        assert_eq!(
+            sheet.format_cell(1, 2, false),
+            Format {
+                bold: Some(true),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_auto_copy_formats_neither() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_format(
+            1,
+            5,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::Auto)
+            .unwrap();
+        assert_eq!(sheet.format_cell(1, 2, false), Format::default());
+        assert_eq!(sheet.format_cell(1, 6, false).bold, Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_copy_formats_before_top() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_format(
+            1,
+            1,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet.set_formats_rows(
+            &[1],
+            &Formats::repeat(
+                FormatUpdate {
+                    italic: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+
+        // nothing above row 1, so the new row is blank
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::Before)
+            .unwrap();
+        assert_eq!(sheet.format_cell(1, 1, true), Format::default());
+        assert!(!sheet.formats_rows.contains_key(&1));
+        assert_eq!(sheet.format_cell(1, 2, false).bold, Some(true));
+        assert_eq!(sheet.formats_rows.get(&2).unwrap().0.italic, Some(true));
+
+        // negative rows are valid and behave the same way
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, -5, CopyFormats::Before)
+            .unwrap();
+        assert_eq!(sheet.format_cell(1, -5, true), Format::default());
+        assert_eq!(sheet.format_cell(1, 3, false).bold, Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_max_rows() {
+        let mut sheet = Sheet::test();
+        sheet.set_cell_value(Pos { x: 1, y: 4 }, "a");
+        sheet.recalculate_bounds();
+        sheet.max_rows = Some(5);
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 5 }),
+            Some(CellValue::Text("a".to_string()))
+        );
+
+        // content is already at the last row
+        let original = sheet.clone();
+        let mut transaction = PendingTransaction::default();
+        assert!(matches!(
+            sheet.insert_row(&mut transaction, 2, CopyFormats::None),
+            Err(CoreError::SheetLimitExceeded(_))
+        ));
+        assert_eq!(sheet, original);
+        assert!(transaction.reverse_operations.is_empty());
+
+        // the new row itself would be past the limit
+        assert!(sheet
+            .insert_row(&mut transaction, 6, CopyFormats::None)
+            .is_err());
+        assert_eq!(sheet, original);
+
+        // inserting below the content and within the limit is fine
+        sheet.max_rows = Some(10);
+        sheet
+            .insert_row(&mut transaction, 6, CopyFormats::None)
+            .unwrap();
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_numeric_formats() {
+        let mut sheet = Sheet::test();
+        sheet.set_cell_value(Pos { x: 1, y: 2 }, CellValue::Number(1234.into()));
+        sheet.set_cell_value(Pos { x: 2, y: 3 }, CellValue::Number(6789.into()));
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                numeric_decimals: Some(Some(3)),
+                numeric_commas: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_format(
+            2,
+            3,
+            FormatUpdate {
+                numeric_decimals: Some(Some(0)),
+                numeric_commas: Some(Some(false)),
+                ..Default::default()
+            },
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+
+        assert_eq!(sheet.format_cell(1, 2, false), Format::default());
+        assert_eq!(sheet.format_cell(2, 2, false), Format::default());
+
+        let format = sheet.format_cell(1, 3, false);
+        assert_eq!(format.numeric_decimals, Some(3));
+        assert_eq!(format.numeric_commas, Some(true));
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Number(1234.into()))
+        );
+
+        let format = sheet.format_cell(2, 4, false);
+        assert_eq!(format.numeric_decimals, Some(0));
+        assert_eq!(format.numeric_commas, Some(false));
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 4 }),
+            Some(CellValue::Number(6789.into()))
+        );
+        assert_eq!(sheet.format_cell(2, 3, false), Format::default());
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_formats_rows_timestamps() {
+        let mut sheet = Sheet::test();
+        let format = Format {
+            bold: Some(true),
+            ..Default::default()
+        };
+        sheet.formats_rows.insert(1, (format.clone(), 100));
+
+        let before = Utc::now().timestamp();
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::After)
+            .unwrap();
+
+        // the shifted row format keeps its original timestamp
+        assert_eq!(sheet.formats_rows.get(&2), Some(&(format.clone(), 100)));
+
+        // the copied row format gets a new timestamp
+        let (copied, timestamp) = sheet.formats_rows.get(&1).unwrap();
+        assert_eq!(copied, &format);
+        assert!(*timestamp >= before);
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_rows_with_heights() {
+        let mut sheet = Sheet::test();
+        sheet.offsets.set_row_height(2, 200.0);
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_rows_with_heights(&mut transaction, 2, &[30.0, 40.0, 50.0], CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.offsets.row_height(1), DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.offsets.row_height(2), 30.0);
+        assert_eq!(sheet.offsets.row_height(3), 40.0);
+        assert_eq!(sheet.offsets.row_height(4), 50.0);
+        assert_eq!(sheet.offsets.row_height(5), 200.0);
+
+        // all rows are checked against max_rows before any is inserted
+        sheet.set_cell_value(Pos { x: 1, y: 4 }, "a");
+        sheet.recalculate_bounds();
+        sheet.max_rows = Some(6);
+        let original = sheet.clone();
+        let mut transaction = PendingTransaction::default();
+        assert!(matches!(
+            sheet.insert_rows_with_heights(
+                &mut transaction,
+                2,
+                &[30.0, 40.0, 50.0],
+                CopyFormats::None
+            ),
+            Err(CoreError::SheetLimitExceeded(_))
+        ));
+        assert_eq!(sheet, original);
+        assert!(transaction.reverse_operations.is_empty());
+    }
+
+    #[test]
+    #[parallel]
+    fn reverse_content_ops_for_rows_chunks() {
+        let columns = [1, 600, 1700, 2900];
+        let mut sheet = Sheet::test();
+        for x in columns {
+            sheet.set_cell_value(Pos { x, y: 2 }, format!("{x}"));
+            sheet.set_cell_value(Pos { x, y: 3 }, format!("{x}!"));
+        }
+        let ops = sheet
+            .reverse_content_ops_for_rows(&[2, 3])
+            .into_iter()
+            .filter(|op| matches!(op, Operation::SetCellValues { .. }))
+            .collect::<Vec<_>>();
+        assert_eq!(ops.len(), 3);
+
+        // the chunks do not overlap, so they can be applied in any order (and
+        // more than once)
+        for order in [
+            vec![0, 1, 2],
+            vec![2, 1, 0],
+            vec![1, 2, 0],
+            vec![0, 1, 2, 1, 0],
+        ] {
+            let mut restored = Sheet::test();
+            for i in order {
+                let Operation::SetCellValues { sheet_pos, values } = &ops[i] else {
+                    unreachable!();
+                };
+                restored.merge_cell_values(
+                    &mut PendingTransaction::default(),
+                    (*sheet_pos).into(),
+                    values,
+                    false,
+                );
+            }
+            for x in columns {
+                assert_eq!(
+                    restored.cell_value(Pos { x, y: 2 }),
+                    Some(CellValue::Text(format!("{x}")))
+                );
+                assert_eq!(
+                    restored.cell_value(Pos { x, y: 3 }),
+                    Some(CellValue::Text(format!("{x}!")))
+                );
+            }
+            restored.recalculate_bounds();
+            assert_eq!(
+                restored.bounds(false),
+                GridBounds::NonEmpty(Rect::new(1, 2, 2900, 3))
+            );
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_rows_batched_offsets() {
+        let mut sheet = Sheet::test();
+        for row in 1..=6 {
+            sheet.offsets.set_row_height(row, row as f64 * 100.0);
+        }
+        let mut sequential = sheet.clone();
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_rows(&mut transaction, &[2, 5, 4]);
+
+        let mut sequential_transaction = PendingTransaction::default();
+        for row in [5, 4, 2] {
+            sequential.delete_row(&mut sequential_transaction, row);
+        }
+
+        assert_eq!(sheet.offsets, sequential.offsets);
+        assert_eq!(sheet.offsets.row_height(2), 300.0);
+        assert_eq!(sheet.offsets.row_height(3), 600.0);
+        assert_eq!(sheet.offsets.row_height(4), DEFAULT_ROW_HEIGHT);
+
+        let resized = |transaction: &PendingTransaction| {
+            transaction
+                .reverse_operations
+                .iter()
+                .filter_map(|op| match op {
+                    Operation::ResizeRow { row, new_size, .. } => Some((*row, *new_size)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            resized(&transaction),
+            vec![(5, 500.0), (4, 400.0), (2, 200.0)]
+        );
+        assert_eq!(resized(&transaction), resized(&sequential_transaction));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_offset() {
+        let mut sheet = Sheet::test();
+        sheet.offsets.set_row_height(1, 100.0);
+        sheet.offsets.set_row_height(2, 200.0);
+        sheet.offsets.set_row_height(4, 400.0);
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.offsets.row_height(1), 100.0);
+        assert_eq!(sheet.offsets.row_height(2), DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.offsets.row_height(3), 200.0);
+        assert_eq!(sheet.offsets.row_height(5), 400.0);
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_column_offset() {
+        let mut sheet = Sheet::test();
+        sheet.offsets.set_row_height(1, 100.0);
+        sheet.offsets.set_row_height(2, 200.0);
+        sheet.offsets.set_row_height(4, 400.0);
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 2);
+        assert_eq!(sheet.offsets.row_height(1), 100.0);
+        assert_eq!(sheet.offsets.row_height(2), DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.offsets.row_height(3), 400.0);
+    }
+
+    #[test]
+    #[parallel]
+    fn paste_rows_insert() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.sheet_mut(sheet_id)
+            .test_set_values(1, 1, 2, 3, vec!["A", "B", "C", "D", "E", "F"]);
+        gc.set_bold_selection(Selection::pos(2, 2, sheet_id), true, None)
+            .unwrap();
+        gc.set_borders_selection(
+            Selection::pos(1, 1, sheet_id),
+            BorderSelection::Top,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        // copy rows 1 and 2
+        let (_, html) = gc
+            .sheet(sheet_id)
+            .copy_to_clipboard(&Selection::sheet_rect(SheetRect::new(1, 1, 2, 2, sheet_id)))
+            .unwrap();
+        let data = html
+            .split("data-quadratic=\"")
+            .nth(1)
+            .and_then(|data| data.split("\"><tbody").next())
+            .unwrap();
+        let clipboard: Clipboard =
+            serde_json::from_str(&htmlescape::decode_html(data).unwrap()).unwrap();
+
+        let mut transaction = PendingTransaction {
+            transaction_type: TransactionType::User,
+            ..Default::default()
+        };
+        gc.sheet_mut(sheet_id)
+            .paste_rows_insert(&mut transaction, 2, &clipboard)
+            .unwrap();
+
+        let sheet = gc.sheet(sheet_id);
+        let values = |sheet: &Sheet, x: i64| {
+            (1..=6)
+                .map(|y| sheet.cell_value(Pos { x, y }))
+                .collect::<Vec<_>>()
+        };
+        let text = |s: &str| Some(CellValue::Text(s.to_string()));
+        assert_eq!(
+            values(sheet, 1),
+            vec![text("A"), text("A"), text("C"), text("C"), text("E"), None]
+        );
+        assert_eq!(
+            values(sheet, 2),
+            vec![text("B"), text("B"), text("D"), text("D"), text("F"), None]
+        );
+        assert_eq!(sheet.format_cell(2, 3, false).bold, Some(true));
+        assert_eq!(sheet.format_cell(2, 4, false).bold, Some(true));
+        assert!(sheet.borders.get(1, 2).top.is_some());
+        assert!(sheet.borders.get(1, 3).top.is_none());
+
+        // a single undo restores the original sheet
+        gc.start_user_transaction(
+            transaction.to_undo_transaction().operations,
+            None,
+            TransactionName::Unknown,
+        );
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            values(sheet, 1),
+            vec![text("A"), text("C"), text("E"), None, None, None]
+        );
+        assert_eq!(sheet.format_cell(2, 2, false).bold, Some(true));
+        assert_eq!(sheet.format_cell(2, 3, false).bold, None);
+        assert!(sheet.borders.get(1, 1).top.is_some());
+        assert!(sheet.borders.get(1, 2).top.is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn cut_row_paste() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 1, 2, 3, vec!["X", "", "A", "B", "Y", ""]);
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet
+            .borders
+            .set(1, 2, Some(BorderStyle::default()), None, None, None);
+        sheet.offsets.set_row_height(2, 50.0);
+        sheet.test_set_code_run_array(3, 2, vec!["1", "2"], false);
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        let snapshot = sheet.cut_row(&mut transaction, 2);
+
+        // the row below moved up
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("Y".to_string()))
+        );
+        assert!(sheet.code_run(Pos { x: 3, y: 2 }).is_none());
+
+        sheet
+            .paste_row_snapshot(&mut transaction, 5, &snapshot)
+            .unwrap();
+
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 5 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 5 }),
+            Some(CellValue::Text("B".to_string()))
+        );
+        assert_eq!(sheet.format_cell(1, 5, false).bold, Some(true));
+        assert_eq!(sheet.format_cell(2, 5, false).bold, None);
+        assert!(sheet.borders.get(1, 5).top.is_some());
+        assert!(sheet.borders.get(2, 5).top.is_none());
+        assert_eq!(sheet.offsets.row_height(5), 50.0);
+        assert!(sheet.code_run(Pos { x: 3, y: 5 }).is_some());
+        assert_eq!(
+            sheet.display_value(Pos { x: 4, y: 5 }),
+            Some(CellValue::Number(2.into()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_rows_reverse_ops() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 2, 2, 3, vec!["A", "B", "C", "D", "E", "F"]);
+        for y in 2..=4 {
+            sheet.test_set_format(
+                1,
+                y,
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    ..Default::default()
+                },
+            );
+            sheet
+                .borders
+                .set(1, y, Some(BorderStyle::default()), None, None, None);
+        }
+        sheet.recalculate_bounds();
+
+        let content_ops = |transaction: &PendingTransaction| {
+            transaction
+                .reverse_operations
+                .iter()
+                .filter(|op| {
+                    matches!(
+                        op,
+                        Operation::SetCellValues { .. }
+                            | Operation::SetCellFormatsSelection { .. }
+                            | Operation::SetBordersSelection { .. }
+                    )
+                })
+                .count()
+        };
+
+        let mut single = sheet.clone();
+        let mut transaction = PendingTransaction::default();
+        single.delete_row(&mut transaction, 2);
+        let single_count = content_ops(&transaction);
+        assert_eq!(single_count, 3);
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_rows(&mut transaction, &[2, 3, 4]);
+        assert_eq!(content_ops(&transaction), single_count);
+        assert!(content_ops(&transaction) < single_count * 3);
+        assert_eq!(
+            transaction
+                .reverse_operations
+                .iter()
+                .filter(|op| matches!(op, Operation::InsertRow { .. }))
+                .count(),
+            3
+        );
+        assert!(sheet.bounds(false).is_empty());
+    }
+
+    #[test]
+    #[parallel]
+    fn delete_row_range() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet.test_set_values(1, 1, 1, 6, vec!["1", "2", "3", "4", "5", "6"]);
+        sheet.test_set_format(
+            1,
+            6,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_code_run_single(2, 3, CellValue::Number(30.into()));
+        sheet.test_set_code_run_single(2, 5, CellValue::Number(50.into()));
+        sheet.recalculate_bounds();
+        let original = sheet.clone();
+
+        let mut transaction = PendingTransaction::default();
+        gc.sheet_mut(sheet_id)
+            .delete_row_range(&mut transaction, 2, 4);
+
+        let sheet = gc.sheet(sheet_id);
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Number(1.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Number(5.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Number(6.into()))
+        );
+        assert_eq!(sheet.display_value(Pos { x: 1, y: 4 }), None);
+        assert_eq!(sheet.format_cell(1, 3, false).bold, Some(true));
+        assert_eq!(sheet.format_cell(1, 6, false).bold, None);
+        assert_eq!(
+            sheet.code_runs.keys().copied().collect::<Vec<_>>(),
+            vec![Pos { x: 2, y: 2 }]
+        );
+        assert_eq!(
+            transaction
+                .reverse_operations
+                .iter()
+                .filter(|op| matches!(op, Operation::SetCellValues { .. }))
+                .count(),
+            1
+        );
+        assert_eq!(
+            transaction
+                .reverse_operations
+                .iter()
+                .filter(|op| matches!(op, Operation::InsertRow { row: 2, .. }))
+                .count(),
+            3
+        );
+
+        gc.start_user_transaction(
+            transaction.to_undo_transaction().operations,
+            None,
+            TransactionName::Unknown,
+        );
+        let sheet = gc.sheet(sheet_id);
+        for y in 1..=7 {
+            let pos = Pos { x: 1, y };
+            assert_eq!(sheet.display_value(pos), original.display_value(pos));
+        }
+        assert_eq!(sheet.format_cell(1, 6, false).bold, Some(true));
+        assert_eq!(
+            sheet.code_runs.keys().copied().collect::<Vec<_>>(),
+            vec![Pos { x: 2, y: 3 }, Pos { x: 2, y: 5 }]
+        );
+
+        // an empty range does nothing
+        let mut transaction = PendingTransaction::default();
+        gc.sheet_mut(sheet_id)
+            .delete_row_range(&mut transaction, 4, 2);
+        assert!(transaction.reverse_operations.is_empty());
+    }
+
+    #[test]
+    #[parallel]
+    fn compact_rows() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        let sheet = gc.sheet_mut(sheet_id);
+        sheet.test_set_values(1, 1, 1, 1, vec!["A"]);
+        sheet.test_set_values(1, 3, 1, 1, vec!["B"]);
+        sheet.test_set_values(1, 5, 1, 1, vec!["C"]);
+        sheet.recalculate_bounds();
+        assert!(sheet.row_is_empty(2));
+        assert!(!sheet.row_is_empty(3));
+        let original = sheet.clone();
+
+        let mut transaction = PendingTransaction {
+            transaction_type: TransactionType::User,
+            ..Default::default()
+        };
+        gc.sheet_mut(sheet_id)
+            .compact_rows(&mut transaction, (1, 5));
+
+        let sheet = gc.sheet(sheet_id);
+        for (y, value) in ["A", "B", "C"].iter().enumerate() {
+            assert_eq!(
+                sheet.cell_value(Pos {
+                    x: 1,
+                    y: y as i64 + 1
+                }),
+                Some(CellValue::Text(value.to_string()))
+            );
+        }
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 4 }), None);
+        assert_eq!(sheet.cell_value(Pos { x: 1, y: 5 }), None);
+
+        gc.start_user_transaction(
+            transaction.to_undo_transaction().operations,
+            None,
+            TransactionName::Unknown,
+        );
+        let sheet = gc.sheet(sheet_id);
+        for y in 1..=5 {
+            let pos = Pos { x: 1, y };
+            assert_eq!(sheet.cell_value(pos), original.cell_value(pos));
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn compact_rows_spill() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_array(1, 1, vec!["1", "2", "3"], true);
+        sheet.recalculate_bounds();
+
+        // rows the code output spills into are not empty
+        assert!(!sheet.row_is_empty(2));
+        assert!(sheet.row_is_empty(4));
+
+        let mut transaction = PendingTransaction::default();
+        sheet.compact_rows(&mut transaction, (1, 4));
+        assert!(sheet.code_runs.get(&Pos { x: 1, y: 1 }).is_some());
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Number(3.into()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn row_ops_move_image_render_size() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_single(2, 2, CellValue::Image("image".to_string()));
+        let render_size = RenderSize {
+            w: "100".to_string(),
+            h: "200".to_string(),
+        };
+        sheet.test_set_format(
+            2,
+            2,
+            FormatUpdate {
+                render_size: Some(Some(render_size.clone())),
+                ..Default::default()
+            },
+        );
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+        assert!(sheet.code_runs.get(&Pos { x: 2, y: 3 }).is_some());
+        assert_eq!(sheet.format_cell(2, 2, false).render_size, None);
+        assert_eq!(
+            sheet.format_cell(2, 3, false).render_size,
+            Some(render_size.clone())
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 1);
+        assert!(sheet.code_runs.get(&Pos { x: 2, y: 2 }).is_some());
+        assert_eq!(
+            sheet.format_cell(2, 2, false).render_size,
+            Some(render_size)
+        );
+        assert_eq!(sheet.format_cell(2, 3, false).render_size, None);
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_image_render_size_signals() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_single(1, 2, CellValue::Image("image".to_string()));
+        assert!(sheet.code_runs[&Pos { x: 1, y: 2 }].is_image());
+        let render_size = RenderSize {
+            w: "100".to_string(),
+            h: "200".to_string(),
+        };
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                render_size: Some(Some(render_size.clone())),
+                ..Default::default()
+            },
+        );
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+
+        assert!(sheet.code_runs.get(&Pos { x: 1, y: 2 }).is_none());
+        assert!(sheet.code_runs[&Pos { x: 1, y: 3 }].is_image());
+        assert_eq!(sheet.format_cell(1, 2, false).render_size, None);
+        assert_eq!(
+            sheet.format_cell(1, 3, false).render_size,
+            Some(render_size)
+        );
+
+        let image_cells = &transaction.image_cells[&sheet.id];
+        assert!(image_cells.contains(&Pos { x: 1, y: 2 }));
+        assert!(image_cells.contains(&Pos { x: 1, y: 3 }));
+        let code_cells = &transaction.code_cells[&sheet.id];
+        assert!(code_cells.contains(&Pos { x: 1, y: 2 }));
+        assert!(code_cells.contains(&Pos { x: 1, y: 3 }));
+    }
+
+    #[test]
+    #[parallel]
+    fn reverse_formats_ops_for_row_format_free() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(
+            1,
+            1,
+            3,
+            3,
+            vec!["1", "2", "3", "4", "5", "6", "7", "8", "9"],
+        );
+        sheet.recalculate_bounds();
+        assert!(sheet.reverse_formats_ops_for_row(2).is_empty());
+
+        // a row format alone still produces an operation
+        sheet.set_formats_rows(
+            &[2],
+            &Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+        assert_eq!(sheet.reverse_formats_ops_for_row(2).len(), 1);
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_code_runs_out_of_order() {
+        // code_runs is in insertion order, so the lower run is added first to
+        // make the shift visit the runs out of position order
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_single(1, 3, CellValue::Number(3.into()));
+        sheet.test_set_code_run_single(1, 2, CellValue::Number(2.into()));
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.code_runs.len(), 2);
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 3 }),
+            Some(CellValue::Number(2.into()))
+        );
+        assert_eq!(
+            sheet.display_value(Pos { x: 1, y: 4 }),
+            Some(CellValue::Number(3.into()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn row_ops_code_cell_notifications() {
+        let mut sheet = Sheet::test();
+        for y in 1..=3 {
+            sheet.test_set_code_run_single(1, y, CellValue::Number(y.into()));
+        }
+        let sheet_id = sheet.id;
+        let positions = |transaction: &PendingTransaction| {
+            let mut positions = transaction.code_cells[&sheet_id]
+                .iter()
+                .map(|pos| pos.y)
+                .collect::<Vec<_>>();
+            positions.sort_unstable();
+            positions
+        };
+
+        // each run notifies its old and new position, but a position shared by
+        // two runs (the old position of one and the new of another) is only
+        // notified once; row 1 is notified so the client clears it
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+        assert_eq!(positions(&transaction), vec![1, 2, 3, 4]);
+
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 1);
+        assert_eq!(positions(&transaction), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[parallel]
+    fn row_ops_chart_anchor() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_single(2, 2, CellValue::Html("<html></html>".to_string()));
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+        assert!(sheet.code_runs.get(&Pos { x: 2, y: 2 }).is_none());
+        assert!(sheet.code_runs.get(&Pos { x: 2, y: 3 }).is_some());
+
+        // deleting the anchor row removes the chart; the reverse operations
+        // restore it at its anchor
+        let mut transaction = PendingTransaction::default();
+        sheet.delete_row(&mut transaction, 3);
+        assert!(sheet.code_runs.is_empty());
+        assert!(transaction.reverse_operations.iter().any(|op| matches!(
+            op,
+            Operation::SetCodeRun {
+                sheet_pos,
+                code_run: Some(_),
+                ..
+            } if sheet_pos.x == 2 && sheet_pos.y == 3
+        )));
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_with_options() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_values(1, 2, 1, 1, vec!["A"]);
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                ..Default::default()
+            },
+        );
+        sheet
+            .borders
+            .set(1, 2, Some(BorderStyle::default()), None, None, None);
+        sheet.offsets.set_row_height(2, 50.0);
+        sheet.recalculate_bounds();
+
+        let insert = |options: CopyOptions| {
+            let mut sheet = sheet.clone();
+            let mut transaction = PendingTransaction::default();
+            sheet
+                .insert_row_with_options(&mut transaction, 2, CopyFormats::After, options)
+                .unwrap();
+            sheet
+        };
+        let none = CopyOptions {
+            values: false,
+            formats: false,
+            borders: false,
+            height: false,
+        };
+
+        // default copies formats only
+        let inserted = insert(CopyOptions::default());
+        assert_eq!(inserted.format_cell(1, 2, false).bold, Some(true));
+        assert_eq!(inserted.cell_value(Pos { x: 1, y: 2 }), None);
+        assert_eq!(inserted.borders.get(1, 2).top, None);
+        assert_eq!(inserted.offsets.row_height(2), DEFAULT_ROW_HEIGHT);
+
+        let inserted = insert(none);
+        assert_eq!(inserted.format_cell(1, 2, false).bold, None);
+
+        let inserted = insert(CopyOptions {
+            values: true,
+            ..none
+        });
+        assert_eq!(
+            inserted.cell_value(Pos { x: 1, y: 2 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+        assert_eq!(inserted.format_cell(1, 2, false).bold, None);
+        assert_eq!(inserted.borders.get(1, 2).top, None);
+
+        let inserted = insert(CopyOptions {
+            borders: true,
+            ..none
+        });
+        assert!(inserted.borders.get(1, 2).top.is_some());
+        assert_eq!(inserted.cell_value(Pos { x: 1, y: 2 }), None);
+        assert_eq!(inserted.offsets.row_height(2), DEFAULT_ROW_HEIGHT);
+
+        let inserted = insert(CopyOptions {
+            height: true,
+            ..none
+        });
+        assert_eq!(inserted.offsets.row_height(2), 50.0);
+        assert_eq!(inserted.offsets.row_height(3), 50.0);
+        assert!(inserted.borders.get(1, 2).top.is_none());
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_inside_spill() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_code_run_array(1, 1, vec!["1", "2", "3"], true);
+        sheet.test_set_values(2, 3, 1, 1, vec!["A"]);
+        sheet.recalculate_bounds();
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+
+        // the anchor is above the inserted row, so the code run does not move
+        // and its output stays contiguous (it is not split around the new row)
+        assert_eq!(sheet.code_runs.len(), 1);
+        let code_run = sheet.code_runs.get(&Pos { x: 1, y: 1 }).unwrap();
+        assert_eq!(
+            code_run.output_rect(Pos { x: 1, y: 1 }, false),
+            Rect::new(1, 1, 1, 3)
+        );
+        for (y, n) in [(1, 1), (2, 2), (3, 3)] {
+            assert_eq!(
+                sheet.display_value(Pos { x: 1, y }),
+                Some(CellValue::Number(n.into()))
+            );
+        }
+        assert_eq!(sheet.display_value(Pos { x: 1, y: 4 }), None);
+
+        // values below the inserted row still move
+        assert_eq!(sheet.cell_value(Pos { x: 2, y: 3 }), None);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 2, y: 4 }),
+            Some(CellValue::Text("A".to_string()))
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn insert_row_html_cells_once() {
+        let mut sheet = Sheet::test();
+        let html = CellValue::Html("<html></html>".to_string());
+        sheet.test_set_code_run_single(1, 2, html.clone());
+        sheet.test_set_code_run_single(1, 3, html.clone());
+        sheet.test_set_code_run_single(2, 3, html);
+
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 2, CopyFormats::None)
+            .unwrap();
+
+        // the old positions are included so the client clears them; (1, 3) is
+        // both an old and a new position but is only sent once
+        let html_cells = &transaction.html_cells[&sheet.id];
+        assert_eq!(html_cells.len(), 5);
+        for (x, y) in [(1, 3), (1, 4), (2, 4)] {
+            assert!(html_cells.contains(&Pos { x, y }));
+            assert!(sheet.code_run(Pos { x, y }).is_some());
+        }
+    }
+
+    #[test]
+    #[parallel]
+    fn row_to_json() {
+        let mut gc = GridController::test();
+        let sheet_id = gc.sheet_ids()[0];
+        gc.sheet_mut(sheet_id)
+            .test_set_values(1, 2, 2, 1, vec!["A", "B"]);
+        gc.set_bold_selection(Selection::pos(1, 2, sheet_id), true, None)
+            .unwrap();
+        gc.set_borders_selection(
+            Selection::pos(3, 2, sheet_id),
+            BorderSelection::Top,
+            Some(BorderStyle::default()),
+            None,
+        );
+
+        let sheet = gc.sheet(sheet_id);
+        let json = sheet.row_to_json(2);
+        assert_eq!(json["row"], 2);
+        assert_eq!(json["height"], DEFAULT_ROW_HEIGHT);
+
+        let cells = json["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0]["x"], 1);
+        assert_eq!(
+            cells[0]["value"],
+            serde_json::to_value(CellValue::Text("A".to_string())).unwrap()
+        );
+        assert_eq!(cells[0]["format"]["bold"], true);
+        assert_eq!(cells[1]["x"], 2);
+        assert!(cells[1]["format"]["bold"].is_null());
+        assert_eq!(cells[2]["x"], 3);
+        assert!(cells[2]["value"].is_null());
+        assert_eq!(
+            cells[2]["borders"]["top"],
+            serde_json::to_value(BorderStyle::default()).unwrap()
+        );
+        assert!(cells[2]["borders"]["bottom"].is_null());
+
+        assert_eq!(sheet.row_to_json(5)["cells"], serde_json::json!([]));
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::ops::Range;
 
 use crate::grid::formats::format::Format;
 use crate::grid::formats::format_update::FormatUpdate;
@@ -76,6 +77,25 @@ impl Sheet {
             .filter(|format| !format.is_default())
     }
 
+    /// Gets the effective formats (including column, row, and sheet formats)
+    /// across a row as runs of identical formats. The runs cover the row's
+    /// bounds (values, code output, and cell formats), so formats outside
+    /// those bounds (eg, the rest of a row format) are not returned.
+    pub fn get_row_format_runs(&self, row: i64) -> Vec<(Range<i64>, Format)> {
+        let mut runs: Vec<(Range<i64>, Format)> = vec![];
+        let Some((min, max)) = self.row_bounds(row, false) else {
+            return runs;
+        };
+        for x in min..=max {
+            let format = self.format_cell(x, row, true);
+            match runs.last_mut() {
+                Some((range, last)) if *last == format => range.end = x + 1,
+                _ => runs.push((x..x + 1, format)),
+            }
+        }
+        runs
+    }
+
     /// Sets a cell's format based on a FormatUpdate. Returns FormatUpdate, which is
     /// used to undo the change.
     /// * send_client - if true, send the changes to the client
@@ -324,4 +344,44 @@ mod tests {
         );
         assert_eq!(sheet.decimal_places(0, 0), Some(5));
     }
+
+    #[test]
+    #[parallel]
+    fn get_row_format_runs() {
+        let mut sheet = Sheet::test();
+        assert!(sheet.get_row_format_runs(1).is_empty());
+
+        sheet.test_set_values(
+            1,
+            1,
+            10,
+            1,
+            vec!["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"],
+        );
+        for x in 1..=5 {
+            sheet.test_set_format(
+                x,
+                1,
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    ..Default::default()
+                },
+            );
+        }
+        sheet.recalculate_bounds();
+
+        assert_eq!(
+            sheet.get_row_format_runs(1),
+            vec![
+                (
+                    1..6,
+                    Format {
+                        bold: Some(true),
+                        ..Default::default()
+                    }
+                ),
+                (6..11, Format::default()),
+            ]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
 use chrono::Utc;
 
@@ -6,6 +7,7 @@ use crate::{
     controller::operations::operation::Operation,
     grid::{
         formats::{format::Format, format_update::FormatUpdate, Formats},
+        js_types::{FormatState, FormatSummary},
         CellWrap, Sheet,
     },
     selection::Selection,
@@ -25,6 +27,73 @@ impl Sheet {
         self.formats_rows.get(&row).map(|f| f.0.clone())
     }
 
+    /// Gets the format of a row merged over the sheet format. This is what a
+    /// blank cell in the row without column or cell formats renders with; a
+    /// cell's effective format (see format_cell) also layers its column format
+    /// and then its own cell format over this.
+    pub fn get_effective_row_format(&self, row: i64) -> Format {
+        Format::combine(
+            None,
+            None,
+            self.formats_rows.get(&row).map(|f| &f.0),
+            self.format_all.as_ref(),
+        )
+    }
+
+    /// Summarizes the formats of the cells in a row within x_range (including
+    /// sheet formats). This is used by the formatting toolbar to display the
+    /// state of a selected row.
+    pub fn row_format_summary(&self, row: i64, x_range: Range<i64>) -> FormatSummary {
+        let total = (x_range.end - x_range.start).max(0) as usize;
+        if total == 0 {
+            return FormatSummary::default();
+        }
+
+        let mut bold = 0;
+        let mut italic = 0;
+        let mut underline = 0;
+        let mut strike_through = 0;
+        let mut text_color: Option<Option<String>> = None;
+        let mut fill_color: Option<Option<String>> = None;
+
+        for x in x_range {
+            let format = self.format_cell(x, row, true);
+            if format.bold == Some(true) {
+                bold += 1;
+            }
+            if format.italic == Some(true) {
+                italic += 1;
+            }
+            if format.underline == Some(true) {
+                underline += 1;
+            }
+            if format.strike_through == Some(true) {
+                strike_through += 1;
+            }
+
+            // a color is only kept if it matches the color of every other cell
+            match &text_color {
+                None => text_color = Some(format.text_color),
+                Some(color) if *color != format.text_color => text_color = Some(None),
+                _ => (),
+            }
+            match &fill_color {
+                None => fill_color = Some(format.fill_color),
+                Some(color) if *color != format.fill_color => fill_color = Some(None),
+                _ => (),
+            }
+        }
+
+        FormatSummary {
+            bold: FormatState::from_count(bold, total),
+            italic: FormatState::from_count(italic, total),
+            underline: FormatState::from_count(underline, total),
+            strike_through: FormatState::from_count(strike_through, total),
+            text_color: text_color.flatten(),
+            fill_color: fill_color.flatten(),
+        }
+    }
+
     /// Sets the Formats for rows and returns existing Formats for rows.
     ///
     /// Changing the row's format also removes any set formatting for cells
@@ -180,6 +249,85 @@ mod tests {
     use super::*;
     use crate::{grid::formats::format_update::FormatUpdate, wasm_bindings::js::expect_js_call};
 
+    #[test]
+    #[parallel]
+    fn get_effective_row_format() {
+        let mut sheet = Sheet::test();
+        assert_eq!(sheet.get_effective_row_format(2), Format::default());
+
+        sheet.set_formats_rows(
+            &[2],
+            &Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+        sheet.format_all = Some(Format {
+            italic: Some(true),
+            bold: Some(false),
+            ..Default::default()
+        });
+
+        let format = sheet.get_effective_row_format(2);
+        assert_eq!(format.bold, Some(true));
+        assert_eq!(format.italic, Some(true));
+
+        let format = sheet.get_effective_row_format(3);
+        assert_eq!(format.bold, Some(false));
+        assert_eq!(format.italic, Some(true));
+    }
+
+    #[test]
+    #[parallel]
+    fn row_format_summary() {
+        let mut sheet = Sheet::test();
+        sheet.test_set_format(
+            1,
+            1,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                italic: Some(Some(true)),
+                fill_color: Some(Some("red".to_string())),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_format(
+            2,
+            1,
+            FormatUpdate {
+                italic: Some(Some(true)),
+                fill_color: Some(Some("red".to_string())),
+                ..Default::default()
+            },
+        );
+        sheet.test_set_format(
+            3,
+            1,
+            FormatUpdate {
+                bold: Some(Some(true)),
+                italic: Some(Some(true)),
+                fill_color: Some(Some("blue".to_string())),
+                ..Default::default()
+            },
+        );
+
+        let summary = sheet.row_format_summary(1, 1..4);
+        assert_eq!(summary.bold, FormatState::Mixed);
+        assert_eq!(summary.italic, FormatState::All);
+        assert_eq!(summary.underline, FormatState::None);
+        assert_eq!(summary.strike_through, FormatState::None);
+        assert_eq!(summary.text_color, None);
+        assert_eq!(summary.fill_color, None);
+
+        let summary = sheet.row_format_summary(1, 1..3);
+        assert_eq!(summary.fill_color, Some("red".to_string()));
+
+        assert_eq!(sheet.row_format_summary(1, 1..1), FormatSummary::default());
+    }
+
     #[test]
     #[parallel]
     fn format_row() {
@@ -1,12 +1,15 @@
-use super::Sheet;
+use super::{borders::BorderStyleCellUpdate, Sheet};
 use crate::{
-    controller::operations::{
-        clipboard::{ClipboardOrigin, ClipboardSheetFormats},
-        operation::Operation,
+    controller::{
+        active_transactions::pending_transaction::PendingTransaction,
+        operations::{
+            clipboard::{ClipboardOrigin, ClipboardSheetFormats},
+            operation::Operation,
+        },
     },
     grid::formats::{format::Format, format_update::FormatUpdate, Formats},
     selection::Selection,
-    Pos,
+    Pos, RunLengthEncoding,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -93,6 +96,50 @@ impl Sheet {
         }
     }
 
+    /// Clears all formats (and borders if `borders` is true) within a
+    /// selection, leaving the cell values intact. Column and row selections
+    /// clear the column and row formats along with the cell formats within
+    /// them. Rects only clear cell formats, so a column or row format covering
+    /// the rect still applies.
+    pub fn clear_formatting(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        selection: &Selection,
+        borders: bool,
+    ) {
+        let count = selection.count();
+
+        let formats = Formats::repeat(FormatUpdate::cleared(), count);
+        let (reverse_operations, hashes, rows) = self.set_formats_selection(selection, &formats);
+        transaction.reverse_operations.extend(reverse_operations);
+        if !transaction.is_server() {
+            transaction
+                .dirty_hashes
+                .entry(self.id)
+                .or_default()
+                .extend(hashes);
+            if !rows.is_empty() && transaction.is_user() {
+                transaction
+                    .resize_rows
+                    .entry(self.id)
+                    .or_default()
+                    .extend(rows);
+            }
+        }
+
+        if borders {
+            let borders = RunLengthEncoding::repeat(BorderStyleCellUpdate::clear(false), count);
+            let (reverse_operations, affected) = self.set_borders_selection(selection, &borders);
+            transaction.reverse_operations.extend(reverse_operations);
+            transaction.sheet_borders.insert(self.id);
+            if let Some(rects) = affected {
+                for rect in rects {
+                    transaction.add_dirty_hashes_from_sheet_rect(rect.to_sheet_rect(self.id));
+                }
+            }
+        }
+    }
+
     /// Gets sheet formats (ie, all, columns, and row formats) for a selection.
     pub fn sheet_formats(
         &self,
@@ -137,15 +184,101 @@ impl Sheet {
 #[cfg(test)]
 mod tests {
     use crate::{
-        controller::operations::clipboard::ClipboardOrigin,
+        controller::{
+            active_transactions::pending_transaction::PendingTransaction,
+            operations::{clipboard::ClipboardOrigin, operation::Operation},
+        },
         grid::{
             formats::{format::Format, format_update::FormatUpdate, Formats},
-            sheet,
+            sheet::{
+                self,
+                borders::{BorderStyleCell, BorderStyleCellUpdate},
+            },
         },
         selection::Selection,
+        CellValue, Pos, Rect, RunLengthEncoding,
     };
     use serial_test::parallel;
 
+    #[test]
+    #[parallel]
+    fn clear_formatting() {
+        let mut sheet = sheet::Sheet::test();
+        let rect = Rect::new(1, 1, 2, 2);
+        for (x, y) in [(1, 1), (2, 2), (5, 3)] {
+            sheet.set_cell_value(Pos { x, y }, format!("{x},{y}"));
+        }
+        sheet.set_formats_rects(
+            &[rect],
+            &Formats::repeat(
+                FormatUpdate {
+                    bold: Some(Some(true)),
+                    fill_color: Some(Some("red".to_string())),
+                    ..Default::default()
+                },
+                4,
+            ),
+        );
+        sheet.set_formats_columns(
+            &[5],
+            &Formats::repeat(
+                FormatUpdate {
+                    italic: Some(Some(true)),
+                    ..Default::default()
+                },
+                1,
+            ),
+        );
+        sheet.borders.set_borders(
+            &Selection::rect(rect, sheet.id),
+            &RunLengthEncoding::repeat(BorderStyleCellUpdate::all(), 4),
+        );
+        let border = sheet.borders.get(1, 1);
+        assert!(border.top.is_some());
+
+        let mut selection = Selection::rect(rect, sheet.id);
+        selection.columns = Some(vec![5]);
+        let mut transaction = PendingTransaction::default();
+        sheet.clear_formatting(&mut transaction, &selection, true);
+
+        for (x, y) in [(1, 1), (2, 2), (5, 3)] {
+            assert_eq!(
+                sheet.cell_value(Pos { x, y }),
+                Some(CellValue::Text(format!("{x},{y}")))
+            );
+            assert_eq!(sheet.format_cell(x, y, true), Format::default());
+        }
+        assert_eq!(sheet.format_column(5), Format::default());
+        assert_eq!(sheet.borders.get(1, 1), BorderStyleCell::default());
+        assert_eq!(sheet.borders.get(2, 2), BorderStyleCell::default());
+        assert!(transaction.sheet_borders.contains(&sheet.id));
+
+        // undo
+        for op in transaction.to_undo_transaction().operations {
+            match op {
+                Operation::SetCellFormatsSelection { selection, formats } => {
+                    sheet.set_formats_selection(&selection, &formats);
+                }
+                Operation::SetBordersSelection { selection, borders } => {
+                    sheet.borders.set_borders(&selection, &borders);
+                }
+                _ => panic!("Unexpected operation: {op:?}"),
+            }
+        }
+        assert_eq!(sheet.format_cell(1, 1, false).bold, Some(true));
+        assert_eq!(
+            sheet.format_cell(2, 2, false).fill_color,
+            Some("red".to_string())
+        );
+        assert_eq!(sheet.format_column(5).italic, Some(true));
+        assert_eq!(sheet.format_cell(5, 3, true).italic, Some(true));
+        assert_eq!(sheet.borders.get(1, 1), border);
+        assert_eq!(
+            sheet.cell_value(Pos { x: 1, y: 1 }),
+            Some(CellValue::Text("1,1".to_string()))
+        );
+    }
+
     #[test]
     #[parallel]
     fn sheet_formats() {
@@ -1,7 +1,20 @@
-use crate::grid::resize::Resize;
+use std::ops::Range;
+
+use crate::{
+    controller::{
+        active_transactions::pending_transaction::PendingTransaction,
+        operations::operation::Operation,
+    },
+    grid::{resize::Resize, CellWrap},
+    DEFAULT_ROW_HEIGHT,
+};
 
 use super::Sheet;
 
+// approximate width of a character in pixels, used to estimate where wrapped
+// text breaks
+const AUTO_SIZE_CHAR_WIDTH: f64 = 7.0;
+
 impl Sheet {
     pub fn get_row_resize(&self, row: i64) -> Resize {
         self.rows_resize.get_resize(row)
@@ -31,17 +44,160 @@ impl Sheet {
             .filter(|&row| self.get_row_resize(row) == Resize::Auto)
             .collect()
     }
+
+    /// Sets the row's height to fit its wrapped text, and returns the new
+    /// height. Rows that were manually resized are left unchanged.
+    ///
+    /// The client measures text precisely; this is a deterministic estimate
+    /// (based on text length and column width) for use without the client,
+    /// eg, to auto-fit after a paste.
+    pub fn auto_size_row(&mut self, transaction: &mut PendingTransaction, row: i64) -> f64 {
+        if self.get_row_resize(row) == Resize::Manual {
+            return self.offsets.row_height(row);
+        }
+
+        let lines = self
+            .row_cells_iter(row)
+            .filter(|(x, _)| self.format_cell(*x, row, true).wrap == Some(CellWrap::Wrap))
+            .map(|(x, value)| {
+                let chars_per_line =
+                    ((self.offsets.column_width(x) / AUTO_SIZE_CHAR_WIDTH).floor() as usize).max(1);
+                value
+                    .to_display()
+                    .split('\n')
+                    .map(|line| line.chars().count().div_ceil(chars_per_line).max(1))
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or(1);
+
+        let height = DEFAULT_ROW_HEIGHT * lines as f64;
+        let old_height = self.offsets.set_row_height(row, height);
+        if old_height != height {
+            transaction.reverse_operations.push(Operation::ResizeRow {
+                sheet_id: self.id,
+                row,
+                new_size: old_height,
+                client_resized: false,
+            });
+            if !transaction.is_server() {
+                transaction.offsets_modified(self.id, None, Some(row), Some(height));
+            }
+        }
+        height
+    }
+
+    /// Recomputes the auto heights of rows (see auto_size_row), eg, after
+    /// inserting content that changes wrapping. Only rows whose height
+    /// changed get a reverse ResizeRow operation.
+    pub fn reflow_rows(&mut self, transaction: &mut PendingTransaction, rows: Range<i64>) {
+        for row in rows {
+            self.auto_size_row(transaction, row);
+        }
+    }
+
+    /// Returns the total height of rows start..=end, including custom row
+    /// heights (all other rows are DEFAULT_ROW_HEIGHT).
+    pub fn row_height_sum(&self, start: i64, end: i64) -> f64 {
+        if end < start {
+            return 0.0;
+        }
+        let (start_position, _) = self.offsets.row_position_size(start);
+        let (end_position, end_height) = self.offsets.row_position_size(end);
+        end_position + end_height - start_position
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        controller::GridController, grid::resize::Resize,
-        sheet_offsets::resize_transient::TransientResize, Pos, Rect,
+        controller::{operations::operation::CopyFormats, GridController},
+        grid::formats::format_update::FormatUpdate,
+        grid::resize::Resize,
+        sheet_offsets::resize_transient::TransientResize,
+        Pos, Rect,
     };
     use serial_test::parallel;
 
+    #[test]
+    #[parallel]
+    fn auto_size_row() {
+        let mut sheet = Sheet::test();
+        sheet.set_cell_value(Pos { x: 1, y: 1 }, "a ".repeat(100));
+        sheet.set_cell_value(Pos { x: 2, y: 1 }, "short");
+        sheet.test_set_format(
+            1,
+            1,
+            FormatUpdate {
+                wrap: Some(Some(CellWrap::Wrap)),
+                ..Default::default()
+            },
+        );
+
+        let mut transaction = PendingTransaction::default();
+        let height = sheet.auto_size_row(&mut transaction, 1);
+        assert!(height > DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.offsets.row_height(1), height);
+        assert_eq!(
+            transaction.reverse_operations,
+            vec![Operation::ResizeRow {
+                sheet_id: sheet.id,
+                row: 1,
+                new_size: DEFAULT_ROW_HEIGHT,
+                client_resized: false,
+            }]
+        );
+
+        // a row without wrapped text keeps the default height
+        sheet.set_cell_value(Pos { x: 1, y: 2 }, "a ".repeat(100));
+        let mut transaction = PendingTransaction::default();
+        assert_eq!(sheet.auto_size_row(&mut transaction, 2), DEFAULT_ROW_HEIGHT);
+        assert!(transaction.reverse_operations.is_empty());
+
+        // manually resized rows are not changed
+        sheet.offsets.set_row_height(1, 50.0);
+        sheet.set_row_resize(1, Resize::Manual);
+        assert_eq!(sheet.auto_size_row(&mut transaction, 1), 50.0);
+    }
+
+    #[test]
+    #[parallel]
+    fn reflow_rows() {
+        let mut sheet = Sheet::test();
+        sheet.set_cell_value(Pos { x: 1, y: 1 }, "short");
+        sheet.set_cell_value(Pos { x: 1, y: 2 }, "a ".repeat(100));
+        sheet.set_cell_value(Pos { x: 1, y: 3 }, "short");
+        sheet.test_set_format(
+            1,
+            2,
+            FormatUpdate {
+                wrap: Some(Some(CellWrap::Wrap)),
+                ..Default::default()
+            },
+        );
+
+        let mut transaction = PendingTransaction::default();
+        sheet.reflow_rows(&mut transaction, 1..4);
+        assert!(sheet.offsets.row_height(2) > DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.offsets.row_height(1), DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.offsets.row_height(3), DEFAULT_ROW_HEIGHT);
+        assert_eq!(
+            transaction.reverse_operations,
+            vec![Operation::ResizeRow {
+                sheet_id: sheet.id,
+                row: 2,
+                new_size: DEFAULT_ROW_HEIGHT,
+                client_resized: false,
+            }]
+        );
+
+        // reflowing again changes nothing
+        let mut transaction = PendingTransaction::default();
+        sheet.reflow_rows(&mut transaction, 1..4);
+        assert!(transaction.reverse_operations.is_empty());
+    }
+
     #[test]
     #[parallel]
     fn test_get_row_resize_default() {
@@ -279,4 +435,25 @@ mod tests {
         assert_eq!(Resize::Auto, sheet.get_row_resize(0));
         assert_eq!(Resize::Auto, sheet.get_row_resize(1));
     }
+
+    #[test]
+    #[parallel]
+    fn row_height_sum() {
+        let mut sheet = Sheet::test();
+        sheet.offsets.set_row_height(1, 50.0);
+        sheet.offsets.set_row_height(2, 60.0);
+        assert_eq!(sheet.row_height_sum(1, 3), 110.0 + DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.row_height_sum(3, 3), DEFAULT_ROW_HEIGHT);
+        assert_eq!(sheet.row_height_sum(3, 1), 0.0);
+
+        // the custom heights move with their rows
+        let mut transaction = PendingTransaction::default();
+        sheet
+            .insert_row(&mut transaction, 1, CopyFormats::None)
+            .unwrap();
+        assert_eq!(sheet.row_height_sum(2, 4), 110.0 + DEFAULT_ROW_HEIGHT);
+        sheet.delete_row(&mut transaction, 1);
+        sheet.delete_row(&mut transaction, 1);
+        assert_eq!(sheet.row_height_sum(1, 3), 60.0 + DEFAULT_ROW_HEIGHT * 2.0);
+    }
 }
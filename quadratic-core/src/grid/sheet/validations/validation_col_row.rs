@@ -68,6 +68,42 @@ impl Validations {
         transaction.reverse_operations.extend(reverse_operations);
     }
 
+    /// Removes the rows in the inclusive range (start, end) from all
+    /// validations, with a single reverse operation per changed validation.
+    pub fn remove_row_range(
+        &mut self,
+        transaction: &mut PendingTransaction,
+        sheet_id: SheetId,
+        start: i64,
+        end: i64,
+    ) {
+        let mut reverse_operations = Vec::new();
+
+        self.validations.retain_mut(|validation| {
+            let original_selection = validation.selection.clone();
+            let mut changed = false;
+
+            // from the bottom so the remaining rows keep their positions
+            for row in (start..=end).rev() {
+                changed |= validation.selection.removed_row(row);
+            }
+            if changed {
+                transaction.validation_changed(sheet_id, validation, Some(&original_selection));
+                reverse_operations.push(Operation::SetValidation {
+                    validation: Validation {
+                        selection: original_selection,
+                        ..validation.clone()
+                    },
+                });
+                !validation.selection.is_empty()
+            } else {
+                true
+            }
+        });
+
+        transaction.reverse_operations.extend(reverse_operations);
+    }
+
     /// Inserts a column into all validations.
     ///
     /// Returns a list of operations that reverse the changes.
@@ -279,6 +315,32 @@ mod tests {
         assert_eq!(validations.validations[1], validation_not_changed);
     }
 
+    #[test]
+    #[parallel]
+    fn remove_row_range() {
+        let mut validations = Validations::default();
+        let validation = Validation {
+            id: Uuid::new_v4(),
+            selection: Selection {
+                rects: Some(vec![Rect::new(1, 1, 3, 6)]),
+                ..Default::default()
+            },
+            rule: ValidationRule::Logical(ValidationLogical::default()),
+            message: Default::default(),
+            error: Default::default(),
+        };
+        validations.set(validation.clone());
+
+        // remove rows 2 through 4 with one reverse operation
+        let mut transaction = PendingTransaction::default();
+        validations.remove_row_range(&mut transaction, SheetId::test(), 2, 4);
+        assert_eq!(transaction.reverse_operations.len(), 1);
+        assert_eq!(
+            validations.validations[0].selection.rects,
+            Some(vec![Rect::new(1, 1, 3, 3)])
+        );
+    }
+
     #[test]
     #[parallel]
     fn inserted_column() {
@@ -255,20 +255,34 @@ impl Selection {
         false
     }
 
-    /// Returns whether a column is located inside a selection.
+    /// Returns whether any cell of a column is inside the selection (via all,
+    /// columns, rects, or rows, since a whole row crosses every column).
     pub fn contains_column(&self, x: i64) -> bool {
-        if let Some(columns) = self.columns.as_ref() {
-            return columns.contains(&x);
-        }
-        false
+        self.all
+            || self
+                .columns
+                .as_ref()
+                .is_some_and(|columns| columns.contains(&x))
+            || self.rows.as_ref().is_some_and(|rows| !rows.is_empty())
+            || self
+                .rects
+                .as_ref()
+                .is_some_and(|rects| rects.iter().any(|rect| rect.x_range().contains(&x)))
     }
 
-    /// Returns whether a row is located inside a selection.
+    /// Returns whether any cell of a row is inside the selection (via all,
+    /// rows, rects, or columns, since a whole column crosses every row).
     pub fn contains_row(&self, y: i64) -> bool {
-        if let Some(rows) = self.rows.as_ref() {
-            return rows.contains(&y);
-        }
-        false
+        self.all
+            || self.rows.as_ref().is_some_and(|rows| rows.contains(&y))
+            || self
+                .columns
+                .as_ref()
+                .is_some_and(|columns| !columns.is_empty())
+            || self
+                .rects
+                .as_ref()
+                .is_some_and(|rects| rects.iter().any(|rect| rect.y_range().contains(&y)))
     }
 
     /// Returns whether a rect is located inside the Selection.rects. Note: this
@@ -521,7 +535,7 @@ impl Selection {
         // remove the column from any selection rects
         if let Some(rects) = self.rects.as_mut() {
             rects.retain_mut(|rect| {
-                if rect.min.x >= column {
+                if rect.min.x > column {
                     rect.min.x -= 1;
                     changed = true;
                 }
@@ -564,7 +578,7 @@ impl Selection {
         // remove the row from any selection rects
         if let Some(rects) = self.rects.as_mut() {
             rects.retain_mut(|rect| {
-                if rect.min.y >= row {
+                if rect.min.y > row {
                     rect.min.y -= 1;
                     changed = true;
                 }
@@ -595,6 +609,33 @@ impl Selection {
         }
         hashes
     }
+
+    /// Converts the selection to a list of rects. Whole columns, rows, and
+    /// all are bounded by sheet_bounds (normally the sheet's data bounds);
+    /// selection.rects are returned as-is.
+    pub fn to_rects(&self, sheet_bounds: Rect) -> Vec<Rect> {
+        if self.all {
+            return vec![sheet_bounds];
+        }
+        let mut rects = Vec::new();
+        if let Some(columns) = self.columns.as_ref() {
+            rects.extend(
+                columns
+                    .iter()
+                    .map(|&x| Rect::new(x, sheet_bounds.min.y, x, sheet_bounds.max.y)),
+            );
+        }
+        if let Some(rows) = self.rows.as_ref() {
+            rects.extend(
+                rows.iter()
+                    .map(|&y| Rect::new(sheet_bounds.min.x, y, sheet_bounds.max.x, y)),
+            );
+        }
+        if let Some(selection_rects) = self.rects.as_ref() {
+            rects.extend(selection_rects.iter().cloned());
+        }
+        rects
+    }
 }
 
 impl FromStr for Selection {
@@ -953,6 +994,28 @@ mod test {
         assert!(!selection.contains_row(4));
     }
 
+    #[test]
+    #[parallel]
+    fn contains_row_column_mixed() {
+        let sheet_id = SheetId::test();
+        let mut selection = Selection::rect(Rect::new(2, 1, 3, 3), sheet_id);
+        assert!(selection.contains_row(1));
+        assert!(selection.contains_row(3));
+        assert!(!selection.contains_row(4));
+        assert!(selection.contains_column(2));
+        assert!(!selection.contains_column(5));
+
+        // a whole column crosses every row
+        selection.columns = Some(vec![5]);
+        assert!(selection.contains_row(4));
+        assert!(selection.contains_column(5));
+        assert!(!selection.contains_column(6));
+
+        assert!(Selection::all(sheet_id).contains_row(100));
+        assert!(Selection::all(sheet_id).contains_column(100));
+        assert!(!Selection::new(sheet_id).contains_row(1));
+    }
+
     #[test]
     #[parallel]
     fn in_rect() {
@@ -1252,6 +1315,15 @@ mod test {
         };
         selection.removed_column(1);
         assert!(selection.columns.is_none());
+
+        // a rect entirely within the removed column is removed
+        let mut selection = Selection {
+            sheet_id,
+            rects: Some(vec![Rect::new(2, 1, 2, 3), Rect::new(3, 1, 4, 3)]),
+            ..Default::default()
+        };
+        assert!(selection.removed_column(2));
+        assert_eq!(selection.rects, Some(vec![Rect::new(2, 1, 3, 3)]));
     }
 
     #[test]
@@ -1282,6 +1354,15 @@ mod test {
         };
         selection.removed_row(1);
         assert!(selection.rows.is_none());
+
+        // a rect entirely within the removed row is removed
+        let mut selection = Selection {
+            sheet_id,
+            rects: Some(vec![Rect::new(1, 2, 3, 2), Rect::new(1, 3, 3, 4)]),
+            ..Default::default()
+        };
+        assert!(selection.removed_row(2));
+        assert_eq!(selection.rects, Some(vec![Rect::new(1, 2, 3, 3)]));
     }
 
     #[test]
@@ -1298,6 +1379,42 @@ mod test {
         );
     }
 
+    #[test]
+    #[parallel]
+    fn to_rects_columns() {
+        let selection = Selection::columns(&[2, 4], SheetId::test());
+        assert_eq!(
+            selection.to_rects(Rect::new(0, -1, 5, 10)),
+            vec![Rect::new(2, -1, 2, 10), Rect::new(4, -1, 4, 10)]
+        );
+    }
+
+    #[test]
+    #[parallel]
+    fn to_rects_mixed() {
+        let selection = Selection {
+            sheet_id: SheetId::test(),
+            columns: Some(vec![1]),
+            rows: Some(vec![3]),
+            rects: Some(vec![Rect::new(7, 7, 8, 9)]),
+            ..Default::default()
+        };
+        assert_eq!(
+            selection.to_rects(Rect::new(0, 0, 5, 5)),
+            vec![
+                Rect::new(1, 0, 1, 5),
+                Rect::new(0, 3, 5, 3),
+                Rect::new(7, 7, 8, 9)
+            ]
+        );
+
+        let selection = Selection::all(SheetId::test());
+        assert_eq!(
+            selection.to_rects(Rect::new(0, 0, 5, 5)),
+            vec![Rect::new(0, 0, 5, 5)]
+        );
+    }
+
     #[test]
     #[parallel]
     fn test_rects() {
@@ -273,6 +273,23 @@ impl SheetOffsets {
     pub fn delete_row(&mut self, row: i64) -> (Vec<(i64, f64)>, Option<f64>) {
         self.row_heights.delete(row)
     }
+
+    /// Inserts `count` row offsets at the given row index.
+    ///
+    /// Returns the combined changes made to the offsets structure as
+    /// (index, new_size).
+    pub fn insert_rows(&mut self, row: i64, count: i64) -> Vec<(i64, f64)> {
+        self.row_heights.insert_many(row, count)
+    }
+
+    /// Deletes the row offsets at the given row indices.
+    ///
+    /// Returns a tuple of the combined changes made to the offsets structure
+    /// as (index, new_size), and the old sizes of the removed rows that had
+    /// custom heights as (row, old_size).
+    pub fn delete_rows(&mut self, rows: &[i64]) -> (Vec<(i64, f64)>, Vec<(i64, f64)>) {
+        self.row_heights.delete_many(rows)
+    }
 }
 
 #[cfg(test)]
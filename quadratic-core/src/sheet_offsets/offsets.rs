@@ -225,6 +225,62 @@ impl Offsets {
             old,
         )
     }
+
+    /// Inserts `count` offsets at the specified index and shifts all later
+    /// indices by `count`. This is the same as calling insert `count` times.
+    ///
+    /// Returns the combined changes as (index, new_size).
+    pub fn insert_many(&mut self, index: i64, count: i64) -> Vec<(i64, f64)> {
+        if count <= 0 {
+            return vec![];
+        }
+        let old = std::mem::take(&mut self.sizes);
+        self.sizes = old
+            .iter()
+            .map(|(&k, &size)| (if k >= index { k + count } else { k }, size))
+            .collect();
+        self.changed_sizes(&old)
+    }
+
+    /// Removes the offsets at the specified indices (in any order) and shifts
+    /// the remaining ones to close the gaps. This is the same as calling
+    /// delete for each index from the largest to the smallest.
+    ///
+    /// Returns a tuple of the combined changes as (index, new_size) and the
+    /// old sizes of the removed offsets that existed as (index, old_size).
+    pub fn delete_many(&mut self, indices: &[i64]) -> (Vec<(i64, f64)>, Vec<(i64, f64)>) {
+        let indices = indices
+            .iter()
+            .copied()
+            .sorted_unstable()
+            .dedup()
+            .collect_vec();
+        let old = std::mem::take(&mut self.sizes);
+        let mut removed = vec![];
+        for (&k, &size) in old.iter() {
+            match indices.binary_search(&k) {
+                Ok(_) => removed.push((k, size)),
+                Err(deleted_before) => {
+                    self.sizes.insert(k - deleted_before as i64, size);
+                }
+            }
+        }
+        (self.changed_sizes(&old), removed)
+    }
+
+    /// Returns (index, new_size) for every index whose size differs from the
+    /// old sizes.
+    fn changed_sizes(&self, old: &BTreeMap<i64, f64>) -> Vec<(i64, f64)> {
+        old.keys()
+            .chain(self.sizes.keys())
+            .sorted_unstable()
+            .dedup()
+            .filter_map(|&k| {
+                let size = self.get_size(k);
+                (old.get(&k).copied().unwrap_or(self.default) != size).then_some((k, size))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -396,4 +452,31 @@ mod tests {
         assert_eq!(offsets.get_size(1), 40.0); // Shifted
         assert_eq!(offsets.get_size(2), offsets.default);
     }
+
+    #[test]
+    #[parallel]
+    fn insert_delete_many() {
+        let mut offsets = Offsets::new(10.0);
+        offsets.set_size(0, 20.0);
+        offsets.set_size(2, 30.0);
+        offsets.set_size(5, 40.0);
+
+        let mut sequential = offsets.clone();
+        sequential.insert(2);
+        sequential.insert(2);
+        let changes = offsets.insert_many(2, 2);
+        assert_eq!(offsets, sequential);
+        assert_eq!(changes, vec![(2, 10.0), (4, 30.0), (5, 10.0), (7, 40.0)]);
+        assert_eq!(offsets.insert_many(2, 0), vec![]);
+
+        let mut sequential = offsets.clone();
+        for index in [7, 4, 1] {
+            sequential.delete(index);
+        }
+        let (changes, removed) = offsets.delete_many(&[4, 1, 7, 4]);
+        assert_eq!(offsets, sequential);
+        assert_eq!(removed, vec![(4, 30.0), (7, 40.0)]);
+        assert_eq!(changes, vec![(4, 10.0), (7, 10.0)]);
+        assert_eq!(offsets.get_size(0), 20.0);
+    }
 }
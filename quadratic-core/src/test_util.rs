@@ -156,6 +156,78 @@ pub fn assert_cell_format_fill_color(
     );
 }
 
+/// Run an assertion that two lists of sheets look the same: values, formats,
+/// borders (ignoring timestamps), row heights, code runs, and validations.
+#[cfg(test)]
+pub fn assert_sheets_match(expected: &[Sheet], actual: &[Sheet], context: &str) {
+    use crate::grid::sheet::borders::BorderStyle;
+
+    assert_eq!(expected.len(), actual.len(), "{context}: sheet count");
+    for (expected, actual) in expected.iter().zip(actual) {
+        assert_eq!(expected.id, actual.id, "{context}: sheet order");
+        assert_eq!(
+            expected.validations.validations, actual.validations.validations,
+            "{context}: validations"
+        );
+        assert_eq!(
+            expected.code_runs.keys().collect::<Vec<_>>(),
+            actual.code_runs.keys().collect::<Vec<_>>(),
+            "{context}: code runs"
+        );
+
+        let mut bounds = GridBounds::default();
+        for sheet in [expected, actual] {
+            if let GridBounds::NonEmpty(rect) = sheet.bounds(false) {
+                bounds.add_rect(rect);
+            }
+            if let Some(rect) = sheet.borders.bounds() {
+                bounds.add_rect(rect);
+            }
+            for row in sheet.formats_rows.keys() {
+                bounds.add(Pos { x: 1, y: *row });
+            }
+        }
+        let GridBounds::NonEmpty(rect) = bounds else {
+            continue;
+        };
+
+        let borders = |sheet: &Sheet, x: i64, y: i64| {
+            let cell = sheet.borders.effective(x, y);
+            [cell.top, cell.bottom, cell.left, cell.right].map(|side| side.map(BorderStyle::from))
+        };
+        for y in rect.y_range() {
+            assert_eq!(
+                expected.offsets.row_height(y),
+                actual.offsets.row_height(y),
+                "{context}: height of row {y}"
+            );
+            for x in rect.x_range() {
+                let pos = Pos { x, y };
+                assert_eq!(
+                    expected.cell_value(pos),
+                    actual.cell_value(pos),
+                    "{context}: value at {pos:?}"
+                );
+                assert_eq!(
+                    expected.display_value(pos),
+                    actual.display_value(pos),
+                    "{context}: display value at {pos:?}"
+                );
+                assert_eq!(
+                    expected.format_cell(x, y, true),
+                    actual.format_cell(x, y, true),
+                    "{context}: format at {pos:?}"
+                );
+                assert_eq!(
+                    borders(expected, x, y),
+                    borders(actual, x, y),
+                    "{context}: borders at {pos:?}"
+                );
+            }
+        }
+    }
+}
+
 // Util to print a simple grid to assist in TDD
 pub fn print_table(grid_controller: &GridController, sheet_id: SheetId, rect: Rect) {
     let Some(sheet) = grid_controller.try_sheet(sheet_id) else {
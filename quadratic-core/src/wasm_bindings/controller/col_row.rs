@@ -20,10 +20,11 @@ impl GridController {
         column: i64,
         after: bool,
         cursor: Option<String>,
-    ) {
-        if let Ok(sheet_id) = SheetId::from_str(sheet_id) {
-            self.insert_column(sheet_id, column, after, cursor);
-        }
+    ) -> Result<(), JsValue> {
+        let sheet_id = SheetId::from_str(sheet_id).map_err(|e| e.to_string())?;
+        self.insert_column(sheet_id, column, after, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(())
     }
 
     #[allow(non_snake_case)]
@@ -37,9 +38,25 @@ impl GridController {
 
     #[allow(non_snake_case)]
     #[wasm_bindgen(js_name = "insertRow")]
-    pub fn js_insert_row(&mut self, sheet_id: &str, row: i64, after: bool, cursor: Option<String>) {
+    pub fn js_insert_row(
+        &mut self,
+        sheet_id: &str,
+        row: i64,
+        after: bool,
+        cursor: Option<String>,
+    ) -> Result<Option<String>, JsValue> {
+        let sheet_id = SheetId::from_str(sheet_id).map_err(|e| e.to_string())?;
+        let selection = self
+            .insert_row(sheet_id, row, after, cursor)
+            .map_err(|e| e.to_string())?;
+        Ok(selection.and_then(|selection| serde_json::to_string(&selection).ok()))
+    }
+
+    #[allow(non_snake_case)]
+    #[wasm_bindgen(js_name = "swapRows")]
+    pub fn js_swap_rows(&mut self, sheet_id: &str, row_a: i64, row_b: i64, cursor: Option<String>) {
         if let Ok(sheet_id) = SheetId::from_str(sheet_id) {
-            self.insert_row(sheet_id, row, after, cursor);
+            self.swap_rows(sheet_id, row_a, row_b, cursor);
         }
     }
 }
@@ -93,6 +93,13 @@ extern "C" {
 
     pub fn jsTransactionProgress(transaction_id: String, remaining_operations: i32);
 
+    pub fn jsTransactionErrors(transaction_id: String, errors: String /* Vec<CoreError> */);
+
+    pub fn jsTransactionWarnings(
+        transaction_id: String,
+        warnings: String, /* Vec<TransactionWarning> */
+    );
+
     pub fn jsUndoRedo(undo: bool, redo: bool);
 
     pub fn jsConnection(
@@ -533,6 +540,27 @@ pub fn jsTransactionProgress(transaction_id: String, remaining_operations: i32)
     ));
 }
 
+#[cfg(test)]
+#[allow(non_snake_case)]
+pub fn jsTransactionErrors(transaction_id: String, errors: String /* Vec<CoreError> */) {
+    TEST_ARRAY.lock().unwrap().push(TestFunction::new(
+        "jsTransactionErrors",
+        format!("{},{}", transaction_id, errors),
+    ));
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+pub fn jsTransactionWarnings(
+    transaction_id: String,
+    warnings: String, /* Vec<TransactionWarning> */
+) {
+    TEST_ARRAY.lock().unwrap().push(TestFunction::new(
+        "jsTransactionWarnings",
+        format!("{},{}", transaction_id, warnings),
+    ));
+}
+
 #[cfg(test)]
 #[allow(non_snake_case)]
 pub fn jsUndoRedo(undo: bool, redo: bool) {